@@ -0,0 +1,53 @@
+// Benchmarks for document embedding throughput by session count.
+//
+// `og build` embeds blocks in chunks of one model batch per session
+// (`OG_EMBED_SESSIONS`, default 1). One session already spreads each batch
+// over every core, so more sessions only pay off when a batch leaves cores
+// idle; this measures whether they do on the current machine. Each extra
+// session also loads another copy of the model, so weigh any gain against
+// the memory it costs.
+//
+// Needs the model (downloaded on first run, like `og build`).
+// Run: cargo bench --bench embed
+
+use divan::{black_box, Bencher};
+use omengrep::embedder::onnx::SESSIONS_ENV;
+use omengrep::embedder::{create_embedder, Precision, MODEL};
+
+fn main() {
+    divan::main();
+}
+
+/// Blocks embedded per iteration — a small repository's worth.
+const BLOCKS: usize = 256;
+
+/// Code blocks of 5 to 40 lines, sorted by length as `og build` sorts them.
+fn make_blocks() -> Vec<String> {
+    let mut blocks: Vec<String> = (0..BLOCKS)
+        .map(|i| {
+            let body: String = (0..5 + i % 36)
+                .map(|j| format!("    let value_{j} = compute_{}(input, {j});\n", i % 17))
+                .collect();
+            format!(
+                "function handler_{i}\nfn handler_{i}(input: &str) -> usize {{\n{body}    0\n}}"
+            )
+        })
+        .collect();
+    blocks.sort_by_key(String::len);
+    blocks
+}
+
+#[divan::bench(args = [1, 2, 4], sample_count = 3, sample_size = 1)]
+fn embed_documents(bencher: Bencher, sessions: usize) {
+    std::env::set_var(SESSIONS_ENV, sessions.to_string());
+    let embedder = create_embedder(Precision::default()).expect("model files not available");
+    let blocks = make_blocks();
+    let texts: Vec<&str> = blocks.iter().map(String::as_str).collect();
+    let chunk = MODEL.batch_size * embedder.concurrency();
+
+    bencher.bench(|| {
+        for batch in texts.chunks(chunk) {
+            black_box(embedder.embed_documents(batch).unwrap());
+        }
+    });
+}
@@ -0,0 +1,54 @@
+// Benchmarks for loading the index manifest, JSON vs binary format.
+//
+// Every search and incremental build loads the manifest; on large
+// repositories it is tens of MB of JSON.
+//
+// Run: cargo bench --bench manifest
+
+use divan::{black_box, Bencher};
+use omengrep::index::manifest::{FileEntry, Manifest};
+use tempfile::TempDir;
+
+fn main() {
+    divan::main();
+}
+
+/// Blocks recorded per file — a typical source file.
+const BLOCKS_PER_FILE: usize = 8;
+
+fn make_index(files: usize, binary: bool) -> TempDir {
+    let dir = TempDir::new().unwrap();
+    let mut manifest = Manifest {
+        binary,
+        ..Manifest::default()
+    };
+    for i in 0..files {
+        let path = format!("src/module_{}/file_{i}.rs", i % 100);
+        let blocks = (0..BLOCKS_PER_FILE)
+            .map(|b| format!("{path}:{}:function_{b}", b * 20))
+            .collect();
+        manifest.files.insert(
+            path,
+            FileEntry {
+                hash: format!("{:016x}", i * 2654435761),
+                blocks,
+                mtime: 1_700_000_000 + i as u64,
+                shard: None,
+            },
+        );
+    }
+    manifest.save(dir.path()).unwrap();
+    dir
+}
+
+#[divan::bench(args = [1_000, 10_000, 100_000], sample_count = 10)]
+fn load_json(bencher: Bencher, files: usize) {
+    let dir = make_index(files, false);
+    bencher.bench(|| black_box(Manifest::load(dir.path()).unwrap()));
+}
+
+#[divan::bench(args = [1_000, 10_000, 100_000], sample_count = 10)]
+fn load_binary(bencher: Bencher, files: usize) {
+    let dir = make_index(files, true);
+    bencher.bench(|| black_box(Manifest::load(dir.path()).unwrap()));
+}
@@ -8,7 +8,7 @@
 // Run: cargo bench --bench omendb
 // Compare two builds: run on each, diff the output.
 
-use std::path::PathBuf;
+use std::path::Path;
 
 use divan::{black_box, Bencher};
 use omendb::{MultiVectorConfig, SearchOptions, VectorStore};
@@ -39,7 +39,7 @@ fn make_tokens(seed: usize) -> Vec<Vec<f32>> {
         .collect()
 }
 
-fn make_store(dir: &PathBuf) -> VectorStore {
+fn make_store(dir: &Path) -> VectorStore {
     let path = dir.join("bench").to_string_lossy().into_owned();
     let mut store = VectorStore::multi_vector_with(TOKEN_DIM, MultiVectorConfig::compact())
         .unwrap()
@@ -100,7 +100,7 @@ fn store_write(bencher: Bencher) {
 #[divan::bench]
 fn search_hybrid(bencher: Bencher) {
     let dir = tempfile::tempdir().unwrap();
-    let store = make_store(&dir.path().to_path_buf());
+    let store = make_store(dir.path());
 
     let query_tokens = make_tokens(42);
     let token_refs: Vec<&[f32]> = query_tokens.iter().map(|v| v.as_slice()).collect();
@@ -121,7 +121,7 @@ fn search_hybrid(bencher: Bencher) {
 #[divan::bench]
 fn search_semantic(bencher: Bencher) {
     let dir = tempfile::tempdir().unwrap();
-    let store = make_store(&dir.path().to_path_buf());
+    let store = make_store(dir.path());
 
     let query_tokens = make_tokens(42);
     let token_refs: Vec<&[f32]> = query_tokens.iter().map(|v| v.as_slice()).collect();
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::extractor::text::TEXT_EXTENSIONS;
+use crate::types::SearchResult;
+
+/// Lines searched either side of the indexed start line (`--verify`).
+pub const VERIFY_WINDOW: usize = 20;
+
+/// Re-read each result's file and re-anchor its line range on the block's first
+/// content line. Results whose line can't be found nearby are marked stale.
+/// Text/markdown chunks carry a header prefix in their content and are skipped.
+pub fn verify(results: &mut [SearchResult]) {
+    let mut files: HashMap<String, Option<String>> = HashMap::new();
+
+    for r in results.iter_mut() {
+        if is_text_file(&r.file) || r.block_type == crate::index::git::COMMIT_TYPE {
+            continue;
+        }
+        let Some((offset, first)) = r.content.as_deref().and_then(first_content_line) else {
+            continue;
+        };
+        let source = files
+            .entry(r.file.clone())
+            .or_insert_with(|| std::fs::read_to_string(&r.file).ok());
+        let Some(source) = source else {
+            r.stale = true;
+            continue;
+        };
+
+        let lines: Vec<&str> = source.lines().collect();
+        // A match fewer than `offset` lines from the top can't hold the whole block
+        match anchor_line(&lines, first, r.line + offset)
+            .and_then(|found| found.checked_sub(offset))
+        {
+            Some(start) => {
+                r.end_line = start + (r.end_line - r.line);
+                r.line = start;
+            }
+            None => r.stale = true,
+        }
+    }
+}
+
+/// Replace each result's stored content with its line range as currently on
+/// disk. Results keep the stored content when the file is gone or now ends
+/// before the range. Text chunks and commits are skipped, as in `verify`.
+pub fn reload_content(results: &mut [SearchResult]) {
+    let mut files: HashMap<String, Option<String>> = HashMap::new();
+
+    for r in results.iter_mut() {
+        if is_text_file(&r.file) || r.block_type == crate::index::git::COMMIT_TYPE {
+            continue;
+        }
+        let source = files
+            .entry(r.file.clone())
+            .or_insert_with(|| std::fs::read_to_string(&r.file).ok());
+        if let Some(content) = source
+            .as_deref()
+            .and_then(|s| line_range(s, r.line, r.end_line))
+        {
+            r.content = Some(content);
+        }
+    }
+}
+
+/// Lines `start..=end` of `source`, or None when the file is shorter.
+fn line_range(source: &str, start: usize, end: usize) -> Option<String> {
+    let lines: Vec<&str> = source.lines().collect();
+    (start <= end && end < lines.len()).then(|| lines[start..=end].join("\n"))
+}
+
+fn is_text_file(file: &str) -> bool {
+    Path::new(file)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| TEXT_EXTENSIONS.contains(&format!(".{}", e.to_lowercase()).as_str()))
+}
+
+/// Index within the block and trimmed text of its first non-blank line.
+fn first_content_line(content: &str) -> Option<(usize, &str)> {
+    content
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i, l.trim()))
+        .find(|(_, l)| !l.is_empty())
+}
+
+/// Nearest line to `expected` (within `VERIFY_WINDOW`) whose trimmed text is `first`.
+fn anchor_line(lines: &[&str], first: &str, expected: usize) -> Option<usize> {
+    let matches = |i: usize| lines.get(i).is_some_and(|l| l.trim() == first);
+    (0..=VERIFY_WINDOW).find_map(|delta| {
+        if matches(expected + delta) {
+            Some(expected + delta)
+        } else {
+            expected
+                .checked_sub(delta)
+                .filter(|&i| delta > 0 && matches(i))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &[&str] = &["// header", "", "fn a() {", "}", "", "fn b() {", "}"];
+
+    #[test]
+    fn anchors_nearest_match() {
+        assert_eq!(anchor_line(SOURCE, "fn b() {", 5), Some(5));
+        assert_eq!(anchor_line(SOURCE, "fn b() {", 2), Some(5));
+        assert_eq!(anchor_line(SOURCE, "fn a() {", 6), Some(2));
+        assert_eq!(anchor_line(SOURCE, "fn c() {", 3), None);
+    }
+
+    #[test]
+    fn match_above_block_offset_is_stale() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let file = tmp.path().join("a.rs");
+        std::fs::write(&file, "fn a() {\n}\n").unwrap();
+        // Stored block starts with two blank lines; its first line now sits at line 0
+        let mut results = [SearchResult {
+            file: file.to_string_lossy().into_owned(),
+            line: 2,
+            end_line: 5,
+            content: Some("\n\nfn a() {\n}".to_string()),
+            ..Default::default()
+        }];
+        verify(&mut results);
+        assert!(results[0].stale);
+        assert_eq!(results[0].line, 2);
+    }
+
+    #[test]
+    fn line_range_falls_back_when_file_shrank() {
+        let source = SOURCE.join("\n");
+        assert_eq!(line_range(&source, 5, 6).as_deref(), Some("fn b() {\n}"));
+        assert_eq!(line_range(&source, 5, 9), None);
+    }
+
+    #[test]
+    fn first_line_skips_blanks() {
+        assert_eq!(
+            first_content_line("\n  \n  fn x() {}\n"),
+            Some((2, "fn x() {}"))
+        );
+        assert_eq!(first_content_line(" \n"), None);
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
 use crate::tokenize;
 use crate::types::SearchResult;
@@ -23,99 +24,261 @@ pub fn boost_results(results: &mut [SearchResult], query: &str) {
         return;
     }
 
-    let query_terms = tokenize::extract_terms(query);
-    let query_set: HashSet<&str> = query_terms
-        .iter()
-        .filter(|t| t.len() >= 3 || SHORT_WHITELIST.contains(&t.as_str()))
-        .map(|s| s.as_str())
-        .collect();
+    let query = BoostQuery::new(query);
+    for r in results.iter_mut() {
+        let boost = total_boost(&boost_factors(r, &query));
+        r.score = scale_score(r.score, boost);
+    }
 
-    // Name/term boosts only apply to code-style queries (camelCase or snake_case).
-    // NL queries like "parse HTTP headers" have no identifier patterns — applying
-    // name boosts promotes wrong results that happen to share English words with the query.
-    let is_code_query = looks_like_code_query(query);
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
 
-    let query_wants_class = query_set
-        .iter()
-        .any(|t| matches!(*t, "class" | "struct" | "type"));
-    let query_wants_func = query_set
-        .iter()
-        .any(|t| matches!(*t, "function" | "func" | "fn" | "method" | "def"));
+/// Max total boost from `boost_factors`.
+pub const MAX_BOOST: f64 = 4.0;
 
-    for r in results.iter_mut() {
-        let mut boost: f64 = 1.0;
-        let block_type = r.block_type.to_lowercase();
+/// Query features the boosts depend on, computed once per query.
+pub struct BoostQuery {
+    terms: Vec<String>,
+    is_code_query: bool,
+    wants_class: bool,
+    wants_func: bool,
+}
+
+impl BoostQuery {
+    pub fn new(query: &str) -> Self {
+        let terms: Vec<String> = tokenize::extract_terms(query)
+            .into_iter()
+            .filter(|t| t.len() >= 3 || SHORT_WHITELIST.contains(&t.as_str()))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let wants_class = terms
+            .iter()
+            .any(|t| matches!(t.as_str(), "class" | "struct" | "type"));
+        let wants_func = terms
+            .iter()
+            .any(|t| matches!(t.as_str(), "function" | "func" | "fn" | "method" | "def"));
 
-        // 1. Name and term matching (code queries only)
-        if is_code_query {
-            let name_lower = r.name.to_lowercase();
-            let name_terms = tokenize::extract_terms(&r.name);
-            let name_set: HashSet<&str> = name_terms.iter().map(|s| s.as_str()).collect();
-
-            if !name_lower.is_empty() && query_set.contains(name_lower.as_str()) {
-                boost *= 2.5;
-            } else {
-                let overlap = query_set.intersection(&name_set).count();
-                if overlap > 0 {
-                    boost *= 1.0 + (0.3 * overlap as f64);
-                }
+        Self {
+            terms,
+            // Name/term boosts only apply to code-style queries (camelCase or snake_case).
+            // NL queries like "parse HTTP headers" have no identifier patterns — applying
+            // name boosts promotes wrong results that happen to share English words with the query.
+            is_code_query: looks_like_code_query(query),
+            wants_class,
+            wants_func,
+        }
+    }
+}
+
+/// Named multipliers `boost_results` applies to one result (before the cap).
+/// Factors of 1.0 are omitted.
+pub fn boost_factors(r: &SearchResult, query: &BoostQuery) -> Vec<(&'static str, f64)> {
+    let mut factors = Vec::new();
+    let query_set: HashSet<&str> = query.terms.iter().map(|s| s.as_str()).collect();
+    let block_type = r.block_type.to_lowercase();
+
+    // 1. Name and term matching (code queries only)
+    if query.is_code_query {
+        let name_lower = r.name.to_lowercase();
+        let name_terms = tokenize::extract_terms(&r.name);
+        let name_set: HashSet<&str> = name_terms.iter().map(|s| s.as_str()).collect();
+
+        if !name_lower.is_empty() && query_set.contains(name_lower.as_str()) {
+            factors.push(("exact name", 2.5));
+        } else {
+            let overlap = query_set.intersection(&name_set).count();
+            if overlap > 0 {
+                factors.push(("name terms", 1.0 + (0.3 * overlap as f64)));
             }
         }
+    }
 
-        // 2. Content match (NL queries only)
-        // Count how many query terms appear in the block content. Functions whose body/docstring
-        // contains most query terms are likely the semantically correct result.
-        if !is_code_query && !query_set.is_empty() {
-            if let Some(content) = &r.content {
-                let content_lower = content.to_lowercase();
-                let matching = query_set
-                    .iter()
-                    .filter(|&&t| content_lower.contains(t))
-                    .count();
-                if matching > 0 {
-                    let ratio = matching as f64 / query_set.len() as f64;
-                    boost *= 1.0 + ratio; // up to 2.0x at full match
-                }
+    // 2. Content match (NL queries only)
+    // Count how many query terms appear in the block content. Functions whose body/docstring
+    // contains most query terms are likely the semantically correct result.
+    if !query.is_code_query && !query_set.is_empty() {
+        if let Some(content) = &r.content {
+            let content_lower = content.to_lowercase();
+            let matching = query_set
+                .iter()
+                .filter(|&&t| content_lower.contains(t))
+                .count();
+            if matching > 0 {
+                let ratio = matching as f64 / query_set.len() as f64;
+                factors.push(("content terms", 1.0 + ratio)); // up to 2.0x at full match
             }
         }
+    }
 
-        // 3. Type boost
-        let type_matches_query = (query_wants_class
-            && matches!(block_type.as_str(), "class" | "struct"))
-            || (query_wants_func && matches!(block_type.as_str(), "function" | "method"));
-
-        if type_matches_query {
-            boost *= 1.5;
-        } else if !query_wants_class && !query_wants_func {
-            boost *= match block_type.as_str() {
-                "function" | "method" => 1.3,
-                "class" | "struct" => 1.2,
-                "interface" | "type" | "trait" | "enum" => 1.1,
-                _ => 1.0,
-            };
+    // 3. Type boost
+    let type_matches_query = (query.wants_class
+        && matches!(block_type.as_str(), "class" | "struct"))
+        || (query.wants_func && matches!(block_type.as_str(), "function" | "method"));
+
+    if type_matches_query {
+        factors.push(("type in query", 1.5));
+    } else if !query.wants_class && !query.wants_func {
+        let weight = match block_type.as_str() {
+            "function" | "method" => 1.3,
+            "class" | "struct" => 1.2,
+            "interface" | "type" | "trait" | "enum" => 1.1,
+            _ => 1.0,
+        };
+        if weight != 1.0 {
+            factors.push(("block type", weight));
         }
+    }
 
-        // 4. File path relevance (code queries only)
-        if is_code_query {
-            let file_path = r.file.to_lowercase();
-            if query_set
-                .iter()
-                .any(|t| t.len() >= 3 && file_path.contains(*t))
-            {
-                boost *= 1.15;
+    // 4. File path relevance (code queries only)
+    if query.is_code_query {
+        let file_path = r.file.to_lowercase();
+        if query_set
+            .iter()
+            .any(|t| t.len() >= 3 && file_path.contains(*t))
+        {
+            factors.push(("file path", 1.15));
+        }
+    }
+
+    factors
+}
+
+/// Product of `factors`, capped at `MAX_BOOST`.
+pub fn total_boost(factors: &[(&'static str, f64)]) -> f64 {
+    factors
+        .iter()
+        .map(|(_, f)| f)
+        .product::<f64>()
+        .min(MAX_BOOST)
+}
+
+/// Apply a boost: divide negative scores (moves toward zero = more similar),
+/// multiply positive scores. Multiplying negative scores by >1 makes them worse.
+pub fn scale_score(score: f32, boost: f64) -> f32 {
+    if score < 0.0 {
+        score / boost as f32
+    } else {
+        score * boost as f32
+    }
+}
+
+/// Default multiplier for `--prefer-type <type>` without an explicit weight.
+pub const DEFAULT_TYPE_WEIGHT: f32 = 1.5;
+
+/// User-specified block-type multiplier (`--prefer-type <type>[:weight]`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeWeight {
+    pub block_type: String,
+    pub weight: f32,
+}
+
+impl std::str::FromStr for TypeWeight {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (block_type, weight) = match s.split_once(':') {
+            Some((t, w)) => {
+                let weight: f32 = w
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("invalid weight '{w}'"))?;
+                (t, weight)
             }
+            None => (s, DEFAULT_TYPE_WEIGHT),
+        };
+        let block_type = block_type.trim().to_lowercase();
+        if block_type.is_empty() {
+            return Err("missing block type".to_string());
+        }
+        if !weight.is_finite() || weight <= 0.0 {
+            return Err(format!("weight must be positive, got {weight}"));
         }
+        Ok(Self { block_type, weight })
+    }
+}
 
-        // Cap at 4x
-        boost = boost.min(4.0);
+/// Apply explicit per-type multipliers after `boost_results`, then re-sort.
+/// Uses the same sign-aware scaling as `boost_results`.
+pub fn apply_type_weights(results: &mut [SearchResult], weights: &[TypeWeight]) {
+    if weights.is_empty() {
+        return;
+    }
 
-        // Apply boost: divide negative scores (moves toward zero = more similar),
-        // multiply positive scores. Multiplying negative scores by >1 makes them worse.
-        if r.score < 0.0 {
-            r.score /= boost as f32;
-        } else {
-            r.score *= boost as f32;
+    for r in results.iter_mut() {
+        let block_type = r.block_type.to_lowercase();
+        let Some(w) = weights.iter().find(|w| w.block_type == block_type) else {
+            continue;
+        };
+        r.score = scale_score(r.score, w.weight as f64);
+    }
+
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+/// Multiplier for files marked with `:boost` in `og --repl`.
+pub const FEEDBACK_BOOST: f32 = 2.0;
+/// Multiplier for files marked with `:demote` in `og --repl`.
+pub const FEEDBACK_DEMOTE: f32 = 0.5;
+
+/// Path prior (`--path-boost prefix:weight`) or session relevance feedback
+/// (`:boost`/`:demote`): scale results whose path (relative to the search
+/// root) starts with `prefix`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathWeight {
+    pub prefix: String,
+    pub weight: f32,
+}
+
+impl std::str::FromStr for PathWeight {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((prefix, weight)) = s.rsplit_once(':') else {
+            return Err("expected PREFIX:WEIGHT".to_string());
+        };
+        let weight: f32 = weight
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid weight '{weight}'"))?;
+        let prefix = prefix.trim().trim_start_matches("./");
+        if prefix.is_empty() {
+            return Err("missing path prefix".to_string());
+        }
+        if !weight.is_finite() || weight <= 0.0 {
+            return Err(format!("weight must be positive, got {weight}"));
         }
+        Ok(Self {
+            prefix: prefix.to_string(),
+            weight,
+        })
+    }
+}
+
+/// Apply path weights after `boost_results`, then re-sort. The weights of all
+/// matching prefixes multiply, capped to `[1/MAX_BOOST, MAX_BOOST]`.
+pub fn apply_path_weights(results: &mut [SearchResult], root: &Path, weights: &[PathWeight]) {
+    if weights.is_empty() {
+        return;
+    }
+
+    for r in results.iter_mut() {
+        let file = Path::new(&r.file);
+        let rel = file.strip_prefix(root).unwrap_or(file).to_string_lossy();
+        let weight: f64 = weights
+            .iter()
+            .filter(|w| rel.starts_with(w.prefix.as_str()))
+            .map(|w| w.weight as f64)
+            .product();
+        r.score = scale_score(r.score, weight.clamp(1.0 / MAX_BOOST, MAX_BOOST));
     }
 
     results.sort_by(|a, b| {
@@ -125,6 +288,82 @@ pub fn boost_results(results: &mut [SearchResult], query: &str) {
     });
 }
 
+/// `--near` boost for a block containing the anchor line; it decays with
+/// line distance within the anchor's file.
+const NEAR_SAME_FILE: f64 = 2.0;
+
+/// Lines from the anchor at which the same-file boost has halved.
+const NEAR_LINE_SCALE: f64 = 100.0;
+
+/// `--near` boost for files in the anchor's directory, halving per
+/// directory step (up or down) between the two.
+const NEAR_SAME_DIR: f64 = 1.3;
+
+/// Location a search is centered on (`--near file:line`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct NearAnchor {
+    /// Canonical path of the anchor file.
+    pub file: PathBuf,
+    pub line: usize,
+}
+
+impl std::str::FromStr for NearAnchor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((file, line)) = s.rsplit_once(':') else {
+            return Err("expected FILE:LINE".to_string());
+        };
+        let line = line
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid line '{line}'"))?;
+        let file = Path::new(file)
+            .canonicalize()
+            .map_err(|_| format!("no such file '{file}'"))?;
+        Ok(Self { file, line })
+    }
+}
+
+/// Boost results near `anchor` (`--near`), then re-sort: blocks in the anchor's
+/// file by line distance, other files by directory distance. Result files
+/// must be absolute.
+pub fn apply_near_boost(results: &mut [SearchResult], anchor: &NearAnchor) {
+    for r in results.iter_mut() {
+        r.score = scale_score(r.score, near_boost(r, anchor));
+    }
+
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+/// The `--near` multiplier for one result.
+pub fn near_boost(r: &SearchResult, anchor: &NearAnchor) -> f64 {
+    let file = Path::new(&r.file);
+    if file == anchor.file {
+        let distance = if anchor.line < r.line {
+            r.line - anchor.line
+        } else {
+            anchor.line.saturating_sub(r.end_line)
+        };
+        return 1.0 + (NEAR_SAME_FILE - 1.0) / (1.0 + distance as f64 / NEAR_LINE_SCALE);
+    }
+
+    let (Some(dir), Some(anchor_dir)) = (file.parent(), anchor.file.parent()) else {
+        return 1.0;
+    };
+    let common = dir
+        .components()
+        .zip(anchor_dir.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let steps = dir.components().count() + anchor_dir.components().count() - 2 * common;
+    1.0 + (NEAR_SAME_DIR - 1.0) * 0.5f64.powi(steps.min(32) as i32)
+}
+
 /// Returns true if the query looks like a code identifier (camelCase or snake_case).
 /// NL queries ("parse HTTP headers") return false — they contain no identifier patterns.
 fn looks_like_code_query(query: &str) -> bool {
@@ -144,3 +383,121 @@ const SHORT_WHITELIST: &[&str] = &[
     "db", "fs", "io", "ui", "id", "ok", "fn", "rx", "tx", "api", "vm", "os", "gc", "ip", "sql",
     "cli", "tls", "rpc",
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(block_type: &str, score: f32) -> SearchResult {
+        SearchResult {
+            file: "a.rs".to_string(),
+            block_type: block_type.to_string(),
+            name: "x".to_string(),
+            line: 0,
+            end_line: 0,
+            content: None,
+            score,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn factors_match_applied_boost() {
+        let mut r = result("function", 2.0);
+        r.content = Some("fn parse() { read headers }".to_string());
+        let factors = boost_factors(&r, &BoostQuery::new("parse headers"));
+        let labels: Vec<&str> = factors.iter().map(|(l, _)| *l).collect();
+        assert_eq!(labels, ["content terms", "block type"]);
+
+        let mut results = vec![r];
+        boost_results(&mut results, "parse headers");
+        assert!((results[0].score - 2.0 * total_boost(&factors) as f32).abs() < 1e-5);
+    }
+
+    #[test]
+    fn path_weights_reorder() {
+        let mut a = result("function", 1.0);
+        a.file = "/repo/tests/a.rs".to_string();
+        let mut b = result("function", 0.8);
+        b.file = "/repo/src/auth/b.rs".to_string();
+        let mut results = vec![a, b];
+        let weights = [
+            PathWeight {
+                prefix: "src/auth".to_string(),
+                weight: FEEDBACK_BOOST,
+            },
+            PathWeight {
+                prefix: "tests/".to_string(),
+                weight: FEEDBACK_DEMOTE,
+            },
+        ];
+        apply_path_weights(&mut results, Path::new("/repo"), &weights);
+        assert_eq!(results[0].file, "/repo/src/auth/b.rs");
+        assert_eq!(results[1].score, 0.5);
+    }
+
+    #[test]
+    fn parse_type_weight() {
+        let w: TypeWeight = "Trait:2".parse().unwrap();
+        assert_eq!(w.block_type, "trait");
+        assert_eq!(w.weight, 2.0);
+
+        let w: TypeWeight = "interface".parse().unwrap();
+        assert_eq!(w.weight, DEFAULT_TYPE_WEIGHT);
+    }
+
+    #[test]
+    fn parse_type_weight_rejects_non_positive() {
+        assert!("trait:0".parse::<TypeWeight>().is_err());
+        assert!("trait:-1".parse::<TypeWeight>().is_err());
+        assert!("trait:abc".parse::<TypeWeight>().is_err());
+        assert!(":2".parse::<TypeWeight>().is_err());
+    }
+
+    #[test]
+    fn near_boost_decays_with_distance() {
+        let anchor = NearAnchor {
+            file: PathBuf::from("/repo/src/net/http.rs"),
+            line: 200,
+        };
+        let at = |file: &str, line: usize| SearchResult {
+            file: file.to_string(),
+            line,
+            end_line: line + 10,
+            ..result("function", 1.0)
+        };
+        let containing = near_boost(&at("/repo/src/net/http.rs", 195), &anchor);
+        let far = near_boost(&at("/repo/src/net/http.rs", 300), &anchor);
+        let sibling = near_boost(&at("/repo/src/net/tls.rs", 0), &anchor);
+        let cousin = near_boost(&at("/repo/src/db/pool.rs", 0), &anchor);
+        assert_eq!(containing, NEAR_SAME_FILE);
+        assert!(far > sibling && far < containing);
+        assert_eq!(sibling, NEAR_SAME_DIR);
+        assert!(cousin > 1.0 && cousin < sibling);
+
+        let mut results = vec![
+            at("/repo/src/db/pool.rs", 0),
+            at("/repo/src/net/http.rs", 195),
+        ];
+        apply_near_boost(&mut results, &anchor);
+        assert_eq!(results[0].file, "/repo/src/net/http.rs");
+    }
+
+    #[test]
+    fn parse_path_weight() {
+        let w: PathWeight = "./src/payments/:1.5".parse().unwrap();
+        assert_eq!(w.prefix, "src/payments/");
+        assert_eq!(w.weight, 1.5);
+        assert!("src".parse::<PathWeight>().is_err());
+        assert!("src:0".parse::<PathWeight>().is_err());
+        assert!(":2".parse::<PathWeight>().is_err());
+    }
+
+    #[test]
+    fn type_weight_reorders_negative_scores() {
+        let mut results = vec![result("function", -10.0), result("trait", -12.0)];
+        apply_type_weights(&mut results, &["trait:2".parse().unwrap()]);
+        assert_eq!(results[0].block_type, "trait");
+        assert_eq!(results[0].score, -6.0);
+    }
+}
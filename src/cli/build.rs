@@ -1,13 +1,28 @@
 use std::path::Path;
 use std::time::Instant;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 
-use crate::index::{self, walker, SemanticIndex};
-use crate::types::EXIT_ERROR;
+use crate::embedder::Precision;
+use crate::index::manifest::Manifest;
+use crate::index::{self, clear_index_dir, walker, SemanticIndex};
+use crate::types::{IndexOptionFlags, IndexOptions, EXIT_ERROR};
+
+pub fn run(
+    path: &Path,
+    force: bool,
+    quiet: bool,
+    verbose: bool,
+    manifest_binary: Option<bool>,
+    flags: &IndexOptionFlags,
+    precision: Option<Precision>,
+) -> Result<()> {
+    if flags.fallback_lines == Some(0) {
+        bail!("--fallback-lines must be at least 1");
+    }
 
-pub fn run(path: &Path, force: bool, quiet: bool) -> Result<()> {
     let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    index::install_interrupt_handler();
 
     // Check for parent index that already covers this path
     let build_path = if !index_exists(&path) {
@@ -20,6 +35,11 @@ pub fn run(path: &Path, force: bool, quiet: bool) -> Result<()> {
             } else {
                 path.clone()
             }
+        } else if let Some(base) = index::find_workspace_root(&path) {
+            if !quiet && base != path {
+                eprintln!("Building workspace index at {}", base.display());
+            }
+            base
         } else {
             path.clone()
         }
@@ -27,25 +47,56 @@ pub fn run(path: &Path, force: bool, quiet: bool) -> Result<()> {
         path.clone()
     };
 
+    // Rebuilds clear the index dir, so remember an existing manifest.bin
+    let index_dir = build_path.join(crate::index::INDEX_DIR);
+    let binary = manifest_binary.unwrap_or_else(|| Manifest::is_binary(&index_dir));
+
+    // Flags not passed keep the existing index's settings; --force starts from defaults
+    let existing = (!force && index_exists(&build_path))
+        .then(|| Manifest::load(&index_dir).ok())
+        .flatten();
+    let options = &flags.apply(
+        existing
+            .as_ref()
+            .map_or_else(IndexOptions::default, |m| m.options.clone()),
+    );
+    let precision = precision
+        .or_else(|| existing.as_ref().map(Manifest::precision))
+        .unwrap_or_default();
+
     // Find subdir indexes that will be superseded
     let subdir_indexes = index::find_subdir_indexes(&build_path, false);
 
-    if force {
-        // Full rebuild: always clear index dir (handles corrupt/partial state)
-        let index_dir = build_path.join(crate::index::INDEX_DIR);
-        if index_dir.exists() {
-            std::fs::remove_dir_all(&index_dir)?;
+    if !force && index_exists(&build_path) && bm25_options_changed(&build_path, options, precision)
+    {
+        // Stored embeddings stay valid; only BM25 text needs rebuilding.
+        // The incremental update below then picks up changed files.
+        if !quiet {
+            eprint!("Rebuilding BM25 text (index options changed)...");
         }
-        build_index(&build_path, quiet)?;
-    } else if index_exists(&build_path) {
-        // Incremental update
+        let stats = SemanticIndex::new(&build_path, None)?.rebuild_text(options.clone())?;
         if !quiet {
-            eprint!("Scanning files...");
+            eprintln!(
+                "\rRebuilt BM25 text for {} blocks (no re-embedding)        ",
+                stats.blocks
+            );
         }
-        let files = walker::scan(&build_path)?;
+    }
+
+    if force {
+        // Full rebuild: always clear index dir (handles corrupt/partial state)
+        clear_index_dir(&index_dir)?;
+        build_index(&build_path, quiet, verbose, options, precision)?;
+    } else if index_exists(&build_path) && options_changed(&build_path, options, precision) {
+        // Blocks extracted under different options can't be mixed
         if !quiet {
-            eprintln!("\r                 \r");
+            eprintln!("Rebuilding (index options changed)...");
         }
+        clear_index_dir(&index_dir)?;
+        build_index(&build_path, quiet, verbose, options, precision)?;
+    } else if index_exists(&build_path) {
+        // Incremental update
+        let files = scan_files(&build_path, options, quiet, verbose)?;
 
         let index = SemanticIndex::new(&build_path, None)?;
         let stale_result = index.get_stale_files(&files);
@@ -67,10 +118,25 @@ pub fn run(path: &Path, force: bool, quiet: bool) -> Result<()> {
                             "\rUpdated {} blocks from {} files        ",
                             stats.blocks, stats.files
                         );
+                        if stats.renamed > 0 {
+                            eprintln!(
+                                "  Moved {} renamed files without re-embedding",
+                                stats.renamed
+                            );
+                        }
+                        if stats.reused > 0 {
+                            eprintln!(
+                                "  Kept {} unchanged blocks without re-embedding",
+                                stats.reused
+                            );
+                        }
                         if stats.deleted > 0 {
                             eprintln!("  Removed {} stale blocks", stats.deleted);
                         }
                     }
+                    if verbose && stats.pruned > 0 {
+                        eprintln!("Pruned {} trivial blocks", stats.pruned);
+                    }
                 }
             }
             Err(e) => {
@@ -80,11 +146,8 @@ pub fn run(path: &Path, force: bool, quiet: bool) -> Result<()> {
                     if !quiet {
                         eprintln!("Rebuilding (index format changed)...");
                     }
-                    let index_dir = build_path.join(crate::index::INDEX_DIR);
-                    if index_dir.exists() {
-                        std::fs::remove_dir_all(&index_dir)?;
-                    }
-                    build_index(&build_path, quiet)?;
+                    clear_index_dir(&index_dir)?;
+                    build_index(&build_path, quiet, verbose, options, precision)?;
                 } else {
                     eprintln!("{e}");
                     std::process::exit(EXIT_ERROR);
@@ -92,7 +155,13 @@ pub fn run(path: &Path, force: bool, quiet: bool) -> Result<()> {
             }
         }
     } else {
-        build_index(&build_path, quiet)?;
+        build_index(&build_path, quiet, verbose, options, precision)?;
+    }
+
+    if index_exists(&build_path) && Manifest::is_binary(&index_dir) != binary {
+        let mut manifest = Manifest::load(&index_dir)?;
+        manifest.binary = binary;
+        manifest.save(&index_dir)?;
     }
 
     // Clean up subdir indexes now superseded by parent
@@ -108,20 +177,55 @@ pub fn run(path: &Path, force: bool, quiet: bool) -> Result<()> {
     Ok(())
 }
 
-fn index_exists(path: &Path) -> bool {
-    path.join(crate::index::INDEX_DIR)
-        .join("manifest.json")
-        .exists()
-}
-
-pub fn build_index(path: &Path, quiet: bool) -> Result<()> {
+/// Scan files to index, reporting transcoded files under `--verbose`.
+fn scan_files(
+    path: &Path,
+    options: &IndexOptions,
+    quiet: bool,
+    verbose: bool,
+) -> Result<walker::ScannedFiles> {
     if !quiet {
         eprint!("Scanning files...");
     }
-    let files = walker::scan(path)?;
+    let (files, transcoded) = walker::scan_counted(path, options)?;
     if !quiet {
         eprintln!("\r                 \r");
     }
+    if verbose && transcoded > 0 {
+        eprintln!("Transcoded {transcoded} non-UTF-8 files to UTF-8");
+    }
+    Ok(files)
+}
+
+fn index_exists(path: &Path) -> bool {
+    Manifest::exists(&path.join(crate::index::INDEX_DIR))
+}
+
+/// Options changed only in ways that affect BM25 text, which can be rebuilt
+/// from stored embeddings.
+fn bm25_options_changed(path: &Path, options: &IndexOptions, precision: Precision) -> bool {
+    Manifest::load(&path.join(crate::index::INDEX_DIR))
+        .map(|m| m.options.differs_only_in_bm25(options) && m.precision() == precision)
+        .unwrap_or(false)
+}
+
+/// Whether the existing index was built with different options or model precision,
+/// i.e. a passed flag changes extraction or embedding. Load errors fall through
+/// to the incremental path, which reports them.
+fn options_changed(path: &Path, options: &IndexOptions, precision: Precision) -> bool {
+    Manifest::load(&path.join(crate::index::INDEX_DIR))
+        .map(|m| m.options != *options || m.precision() != precision)
+        .unwrap_or(false)
+}
+
+pub fn build_index(
+    path: &Path,
+    quiet: bool,
+    verbose: bool,
+    options: &IndexOptions,
+    precision: Precision,
+) -> Result<()> {
+    let files = scan_files(path, options, quiet, verbose)?;
 
     if files.is_empty() {
         if !quiet {
@@ -130,7 +234,9 @@ pub fn build_index(path: &Path, quiet: bool) -> Result<()> {
         return Ok(());
     }
 
-    let index = SemanticIndex::new(path, None)?;
+    let mut index = SemanticIndex::new(path, None)?;
+    index.set_options(options.clone());
+    index.set_precision(precision);
     let t0 = Instant::now();
 
     let progress_fn = if quiet {
@@ -162,6 +268,9 @@ pub fn build_index(path: &Path, quiet: bool) -> Result<()> {
             eprintln!("{} files failed to index", stats.errors);
         }
     }
+    if verbose && stats.pruned > 0 {
+        eprintln!("Pruned {} trivial blocks", stats.pruned);
+    }
 
     Ok(())
 }
@@ -2,6 +2,7 @@ use std::path::Path;
 
 use anyhow::Result;
 
+use crate::index::manifest::Manifest;
 use crate::index::{self, SemanticIndex, INDEX_DIR};
 use crate::types::EXIT_ERROR;
 
@@ -10,7 +11,7 @@ pub fn run(path: &Path, recursive: bool) -> Result<()> {
     let mut deleted_count = 0;
 
     // Delete root index if exists
-    if path.join(INDEX_DIR).join("manifest.json").exists() {
+    if Manifest::exists(&path.join(INDEX_DIR)) {
         let index = SemanticIndex::new(&path, None)?;
         index.clear()?;
         println!("Deleted ./.og/");
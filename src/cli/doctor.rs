@@ -0,0 +1,278 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Result;
+use hf_hub::Cache;
+
+use crate::cli::model;
+use crate::embedder::{self, Precision, MODEL};
+use crate::index::manifest::Manifest;
+use crate::index::{find_index_root, find_index_roots, walker, SemanticIndex};
+use crate::types::{IndexOptions, EXIT_ERROR};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// One line of the report, with the step that fixes it.
+struct Check {
+    status: Status,
+    name: &'static str,
+    message: String,
+    fix: Option<String>,
+}
+
+impl Check {
+    fn new(status: Status, name: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            name,
+            message: message.into(),
+            fix: None,
+        }
+    }
+
+    fn fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
+    }
+
+    fn render(&self) -> String {
+        let label = match self.status {
+            Status::Ok => "ok  ",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+        };
+        let mut line = format!("{label}  {}: {}", self.name, self.message);
+        if let Some(fix) = &self.fix {
+            line.push_str(&format!("\n      -> {fix}"));
+        }
+        line
+    }
+}
+
+/// `og doctor`: check the setup a search depends on — model files in the
+/// cache, the ONNX session, the index and its freshness, and what the walker
+/// finds versus what is indexed — and print each result with the step that
+/// fixes it. Exits non-zero when any check fails.
+pub fn run(path: &Path) -> Result<()> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if !path.exists() {
+        eprintln!("Path does not exist: {}", path.display());
+        std::process::exit(EXIT_ERROR);
+    }
+
+    let (root, index_dir) = find_index_root(&path);
+    let info = index_dir.as_deref().map(Manifest::info);
+    let precision = info
+        .as_ref()
+        .and_then(|i| i.as_ref().ok())
+        .and_then(|i| i.precision())
+        .unwrap_or_default();
+
+    let mut checks = model_checks(precision);
+    // Manifest of a current index; an outdated one still lends the scan its options
+    let mut current = None;
+    let options = match (&index_dir, info) {
+        (None, _) => {
+            checks.push(
+                Check::new(
+                    Status::Fail,
+                    "index",
+                    format!("none at or above {}", path.display()),
+                )
+                .fix(format!("Run 'og build {}'", path.display())),
+            );
+            IndexOptions::default()
+        }
+        (Some(_), Some(Err(e))) => {
+            checks.push(
+                Check::new(
+                    Status::Fail,
+                    "index",
+                    format!("manifest is unreadable: {e}"),
+                )
+                .fix("Run 'og build --force'"),
+            );
+            IndexOptions::default()
+        }
+        (Some(index_dir), Some(Ok(info))) if info.needs_rebuild() => {
+            checks.push(
+                Check::new(
+                    Status::Fail,
+                    "index",
+                    format!(
+                        "built by another og version or model ('{}', format {})",
+                        info.model, info.version
+                    ),
+                )
+                .fix("Run 'og build --force'"),
+            );
+            Manifest::load(index_dir)
+                .map(|m| m.options)
+                .unwrap_or_default()
+        }
+        (Some(index_dir), _) => {
+            let manifest = Manifest::load(index_dir)?;
+            checks.push(index_check(&root, &manifest));
+            let options = manifest.options.clone();
+            current = Some(manifest);
+            options
+        }
+    };
+
+    let roots = find_index_roots(&path);
+    if let [_, .., outermost] = roots.as_slice() {
+        checks.push(
+            Check::new(
+                Status::Warn,
+                "nested",
+                format!(
+                    "{} indexes along this path; searches use the nearest",
+                    roots.len()
+                ),
+            )
+            .fix(format!(
+                "Delete the stale one with 'og clean <dir>' ('og clean -r {}' deletes all)",
+                outermost.display()
+            )),
+        );
+    }
+
+    let report = walker::scan_report(&root, &options)?;
+    checks.push(files_check(report.files.len(), &report.skipped));
+    if let Some(manifest) = current {
+        let index = SemanticIndex::new(&root, None)?;
+        let (changed, deleted) = index.get_stale_files(&report.files)?;
+        checks.push(freshness_check(
+            report.files.len(),
+            manifest.files.len(),
+            changed.len(),
+            deleted.len(),
+        ));
+    }
+
+    for check in &checks {
+        println!("{}", check.render());
+    }
+    if checks.iter().any(|c| c.status == Status::Fail) {
+        std::process::exit(EXIT_ERROR);
+    }
+    Ok(())
+}
+
+/// Model files in the cache, then (only if they are, so nothing downloads)
+/// whether the ONNX session starts.
+fn model_checks(precision: Precision) -> Vec<Check> {
+    let name = format!("{} ({})", MODEL.repo, precision.name());
+    let install = match precision {
+        Precision::Int8 => "Run 'og model install' while online",
+        Precision::Fp32 => "Run 'og model install --model-fp32' while online",
+    };
+    if !model::installed(&Cache::default(), precision) {
+        return vec![
+            Check::new(Status::Fail, "model", format!("{name} not installed")).fix(install),
+            Check::new(Status::Warn, "onnx", "not checked (model not installed)"),
+        ];
+    }
+    let onnx = match embedder::create_embedder(precision) {
+        Ok(_) => Check::new(Status::Ok, "onnx", "session initialized"),
+        Err(e) => Check::new(Status::Fail, "onnx", format!("session failed: {e:#}"))
+            .fix("Reinstall the model: 'og model rm', then 'og model install'"),
+    };
+    vec![
+        Check::new(Status::Ok, "model", format!("{name} installed")),
+        onnx,
+    ]
+}
+
+/// A current-format index, failing when it holds no blocks.
+fn index_check(root: &Path, manifest: &Manifest) -> Check {
+    let blocks: usize = manifest.files.values().map(|e| e.blocks.len()).sum();
+    if blocks == 0 {
+        return Check::new(
+            Status::Fail,
+            "index",
+            format!("{} is empty", root.display()),
+        )
+        .fix("Check the path holds supported files (see 'files' below), then 'og build --force'");
+    }
+    Check::new(
+        Status::Ok,
+        "index",
+        format!(
+            "{} ({} files, {blocks} blocks)",
+            root.display(),
+            manifest.files.len()
+        ),
+    )
+}
+
+/// Files the walker keeps, and those it left out by reason.
+fn files_check(found: usize, skipped: &BTreeMap<&'static str, usize>) -> Check {
+    let total: usize = skipped.values().sum();
+    let mut message = format!("walker finds {found} files");
+    if total > 0 {
+        let reasons: Vec<String> = skipped
+            .iter()
+            .map(|(reason, n)| format!("{n} {reason}"))
+            .collect();
+        message.push_str(&format!(", skipped {total} ({})", reasons.join(", ")));
+    }
+    if found == 0 {
+        return Check::new(Status::Fail, "files", message)
+            .fix("Point og at a directory with source files, or check .gitignore/.ignore rules");
+    }
+    let check = Check::new(Status::Ok, "files", message);
+    if total > 0 {
+        check.fix("Run 'og build -vv' to list skipped files")
+    } else {
+        check
+    }
+}
+
+/// Found files versus indexed ones, warning on changes since the last build.
+/// Files without blocks (unsupported languages, empty files) are not indexed.
+fn freshness_check(found: usize, indexed: usize, changed: usize, deleted: usize) -> Check {
+    let message = format!("{indexed} of {found} files have indexed blocks");
+    if changed + deleted == 0 {
+        return Check::new(Status::Ok, "fresh", format!("{message}, up to date"));
+    }
+    Check::new(
+        Status::Warn,
+        "fresh",
+        format!("{message}; {changed} changed, {deleted} deleted since the last build"),
+    )
+    .fix("Run 'og build' (searches also update the index)")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_skips_and_staleness() {
+        let none = BTreeMap::new();
+        let check = files_check(12, &none);
+        assert_eq!(check.status, Status::Ok);
+        assert!(check.fix.is_none());
+        assert_eq!(check.render(), "ok    files: walker finds 12 files");
+
+        let skipped = BTreeMap::from([("too large", 1), ("binary extension", 3)]);
+        let check = files_check(0, &skipped);
+        assert_eq!(check.status, Status::Fail);
+        assert!(check
+            .render()
+            .starts_with("FAIL  files: walker finds 0 files, skipped 4 (3 binary extension, 1 too large)\n      -> "));
+
+        assert_eq!(freshness_check(10, 8, 0, 0).status, Status::Ok);
+        let stale = freshness_check(10, 8, 2, 1);
+        assert_eq!(stale.status, Status::Warn);
+        assert!(stale
+            .message
+            .ends_with("2 changed, 1 deleted since the last build"));
+    }
+}
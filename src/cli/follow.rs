@@ -0,0 +1,115 @@
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{bail, Result};
+
+use crate::cli::output::{limit_content_bytes, print_results};
+use crate::cli::search::{self, SearchParams};
+use crate::index::manifest::Manifest;
+use crate::index::{walker, SemanticIndex, INDEX_DIR};
+use crate::types::{OutputFormat, EXIT_ERROR};
+
+/// How often to check the tree and manifest for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// `og --follow`: run the search, then keep the index and model loaded and
+/// re-run it whenever files under the index change (updating the index
+/// first) or another process rewrites the manifest. Runs until Ctrl-C.
+pub fn run(params: &SearchParams) -> Result<()> {
+    let Some(query) = params.query else {
+        bail!("--follow needs a query");
+    };
+    crate::index::check_query(query)?;
+    if search::parse_file_reference(query).is_some() {
+        bail!("--follow needs a text query, not a file reference");
+    }
+
+    let path = params
+        .path
+        .canonicalize()
+        .unwrap_or_else(|_| params.path.to_path_buf());
+    if !path.exists() {
+        eprintln!("Path does not exist: {}", path.display());
+        std::process::exit(EXIT_ERROR);
+    }
+
+    if params.rebuild_if_stale {
+        search::rebuild_if_stale(&path, params.quiet)?;
+    }
+    let mut index = search::open_index(&path, params.quiet, params.no_index, None)?;
+    let owners = search::load_owners(&index, params.owner)?;
+    search::check_git_filters(&index, params)?;
+    search::configure_index(&mut index, &path, params)?;
+    let k = search::fetch_k(params, owners.as_ref());
+    let clear = io::stdout().is_terminal();
+
+    loop {
+        let t0 = Instant::now();
+        let results = index.search(query, k)?;
+        let search_time = t0.elapsed();
+
+        let mut results = search::apply_filters(results, query, params);
+        search::filter_post(&mut results, owners.as_ref(), params);
+        results.truncate(params.num_results);
+        if let Some(limit) = params.limit_bytes {
+            limit_content_bytes(&mut results, limit);
+        }
+
+        if clear {
+            print!("\x1b[2J\x1b[H");
+        }
+        if results.is_empty() {
+            eprintln!("No results found");
+        } else {
+            print_results(
+                &results,
+                params.format,
+                false,
+                Some(&path),
+                params.context_lines,
+                params.fields,
+            )?;
+        }
+        io::stdout().flush()?;
+        if !params.quiet && !matches!(params.format, OutputFormat::Json | OutputFormat::FilesOnly) {
+            eprintln!(
+                "{} results ({:.2}s), following changes (Ctrl-C to exit)",
+                results.len(),
+                search_time.as_secs_f64()
+            );
+        }
+
+        wait_for_change(&index, params.no_index)?;
+        // Recomputed each run, since matching files and neighbours change with the index
+        index.set_changed_since(params.changed_since)?;
+        index.set_and_file(params.and_file)?;
+    }
+}
+
+/// Block until the index changes: files on disk are updated into it (unless
+/// `no_index`), or its manifest is rewritten by another process.
+fn wait_for_change(index: &SemanticIndex, no_index: bool) -> Result<()> {
+    let index_dir = index.root().join(INDEX_DIR);
+    let saved = manifest_mtime(&index_dir);
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        if manifest_mtime(&index_dir) != saved {
+            return Ok(());
+        }
+        if no_index {
+            continue;
+        }
+        let metadata = walker::scan_metadata(index.root(), &index.options()?)?;
+        let (stale_count, _) = index.check_and_update(&metadata)?;
+        if stale_count > 0 {
+            return Ok(());
+        }
+    }
+}
+
+fn manifest_mtime(index_dir: &Path) -> Option<SystemTime> {
+    std::fs::metadata(Manifest::file_path(index_dir))
+        .and_then(|m| m.modified())
+        .ok()
+}
@@ -2,12 +2,18 @@ use std::path::Path;
 
 use anyhow::Result;
 
+use crate::index::manifest::Manifest;
 use crate::index::{self, SemanticIndex};
 
-pub fn run(path: &Path) -> Result<()> {
+pub fn run(path: &Path, json: bool) -> Result<()> {
     let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
     let indexes = index::find_subdir_indexes(&path, true);
 
+    if json {
+        print_json(&indexes);
+        return Ok(());
+    }
+
     if indexes.is_empty() {
         eprintln!("No indexes found");
         return Ok(());
@@ -36,3 +42,34 @@ pub fn run(path: &Path) -> Result<()> {
 
     Ok(())
 }
+
+/// Print `[{ path, blocks, model, version, needs_rebuild }]` for each index.
+fn print_json(indexes: &[std::path::PathBuf]) {
+    let output: Vec<serde_json::Value> = indexes
+        .iter()
+        .filter_map(|idx_path| {
+            let root = idx_path.parent()?;
+            let entry = match Manifest::info(idx_path) {
+                Ok(info) => serde_json::json!({
+                    "path": root.display().to_string(),
+                    "blocks": info.blocks,
+                    "model": info.model,
+                    "version": info.version,
+                    "needs_rebuild": info.needs_rebuild(),
+                }),
+                Err(_) => serde_json::json!({
+                    "path": root.display().to_string(),
+                    "blocks": 0,
+                    "model": null,
+                    "version": null,
+                    "needs_rebuild": true,
+                }),
+            };
+            Some(entry)
+        })
+        .collect();
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&output).unwrap_or_default()
+    );
+}
@@ -1,5 +1,8 @@
 use std::io::{self, BufRead, Write};
 use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use serde_json::{json, Value};
@@ -7,9 +10,13 @@ use serde_json::{json, Value};
 use crate::boost::boost_results;
 use crate::index::manifest::Manifest;
 use crate::index::{self, walker, SemanticIndex, INDEX_DIR};
+use crate::types::SearchResult;
 
 const PROTOCOL_VERSION: &str = "2024-11-05";
 
+/// JSON-RPC server error code for `OG_TIMEOUT_MS` expiry.
+pub(crate) const TIMEOUT_ERROR: i64 = -32001;
+
 pub fn run() -> Result<()> {
     let stdin = io::stdin().lock();
     let mut stdout = io::stdout().lock();
@@ -47,7 +54,7 @@ pub fn run() -> Result<()> {
         let response = match method {
             "initialize" => handle_initialize(),
             "tools/list" => handle_tools_list(),
-            "tools/call" => handle_tools_call(&params),
+            "tools/call" => call_with_timeout(params),
             _ => Err(json_rpc_error(-32601, "Method not found")),
         };
 
@@ -72,7 +79,7 @@ pub fn run() -> Result<()> {
     Ok(())
 }
 
-fn json_rpc_error(code: i64, message: &str) -> Value {
+pub(crate) fn json_rpc_error(code: i64, message: &str) -> Value {
     json!({
         "code": code,
         "message": message,
@@ -152,13 +159,61 @@ fn handle_tools_list() -> Result<Value, Value> {
     }))
 }
 
-fn handle_tools_call(params: &Value) -> Result<Value, Value> {
+/// Held by whichever worker is running a handler. A worker abandoned at its
+/// timeout may still be writing the store or manifest, so later handlers wait
+/// for it rather than open the same index alongside it.
+static WORKER: Mutex<()> = Mutex::new(());
+
+fn call_with_timeout(params: Value) -> Result<Value, Value> {
+    with_timeout(move |deadline| handle_tools_call(&params, deadline))
+}
+
+/// Run a handler on a worker thread so a runaway operation can't block the
+/// server past `OG_TIMEOUT_MS`. The index checks the same deadline itself, so an
+/// abandoned update stops at its next batch with completed files flushed.
+/// Handlers run one at a time (see `WORKER`); one still waiting for an
+/// abandoned worker at its own deadline is skipped.
+pub(crate) fn with_timeout<T, F>(handler: F) -> Result<T, Value>
+where
+    T: Send + 'static,
+    F: FnOnce(Option<Instant>) -> Result<T, Value> + Send + 'static,
+{
+    let Some(timeout) = index::timeout_from_env() else {
+        let _running = WORKER.lock().unwrap_or_else(PoisonError::into_inner);
+        return handler(None);
+    };
+    let deadline = Instant::now() + timeout;
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _running = WORKER.lock().unwrap_or_else(PoisonError::into_inner);
+        let response = if Instant::now() < deadline {
+            handler(Some(deadline))
+        } else {
+            Err(timed_out(timeout))
+        };
+        let _ = tx.send(response);
+    });
+    match rx.recv_timeout(timeout) {
+        Ok(response) => response,
+        Err(RecvTimeoutError::Timeout) => Err(timed_out(timeout)),
+        Err(RecvTimeoutError::Disconnected) => Err(json_rpc_error(-32603, "Internal error")),
+    }
+}
+
+fn timed_out(timeout: Duration) -> Value {
+    json_rpc_error(
+        TIMEOUT_ERROR,
+        &format!("Operation timed out after {} ms", timeout.as_millis()),
+    )
+}
+
+fn handle_tools_call(params: &Value, deadline: Option<Instant>) -> Result<Value, Value> {
     let tool_name = params.get("name").and_then(|n| n.as_str()).unwrap_or("");
     let args = params.get("arguments").cloned().unwrap_or(json!({}));
 
     match tool_name {
-        "og_search" => tool_search(&args),
-        "og_similar" => tool_similar(&args),
+        "og_search" => tool_search(&args, deadline),
+        "og_similar" => tool_similar(&args, deadline),
         "og_status" => tool_status(&args),
         _ => Err(json_rpc_error(
             -32602,
@@ -167,7 +222,7 @@ fn handle_tools_call(params: &Value) -> Result<Value, Value> {
     }
 }
 
-fn format_results(results: &[crate::types::SearchResult]) -> String {
+fn format_results(results: &[SearchResult]) -> String {
     results
         .iter()
         .map(|r| {
@@ -181,7 +236,15 @@ fn format_results(results: &[crate::types::SearchResult]) -> String {
         .join("\n\n")
 }
 
-fn tool_search(args: &Value) -> Result<Value, Value> {
+fn tool_search(args: &Value, deadline: Option<Instant>) -> Result<Value, Value> {
+    let results = search(args, deadline)?;
+    Ok(json!({
+        "content": [{ "type": "text", "text": format_results(&results) }]
+    }))
+}
+
+/// `og_search` handler, shared with `og serve`.
+pub(crate) fn search(args: &Value, deadline: Option<Instant>) -> Result<Vec<SearchResult>, Value> {
     let query = args
         .get("query")
         .and_then(|q| q.as_str())
@@ -207,10 +270,12 @@ fn tool_search(args: &Value) -> Result<Value, Value> {
 
     let mut idx = SemanticIndex::new(&index_root, None)
         .map_err(|e| json_rpc_error(-32000, &e.to_string()))?;
+    idx.set_deadline(deadline);
 
     // Auto-update stale files (metadata-only scan, read content only for changed files)
-    let metadata =
-        walker::scan_metadata(&index_root).map_err(|e| json_rpc_error(-32000, &e.to_string()))?;
+    let options = idx.options().unwrap_or_default();
+    let metadata = walker::scan_metadata(&index_root, &options)
+        .map_err(|e| json_rpc_error(-32000, &e.to_string()))?;
     idx.check_and_update(&metadata)
         .map_err(|e| json_rpc_error(-32000, &e.to_string()))?;
 
@@ -220,13 +285,18 @@ fn tool_search(args: &Value) -> Result<Value, Value> {
         .map_err(|e| json_rpc_error(-32000, &e.to_string()))?;
 
     boost_results(&mut results, query);
+    Ok(results)
+}
 
+fn tool_similar(args: &Value, deadline: Option<Instant>) -> Result<Value, Value> {
+    let results = similar(args, deadline)?;
     Ok(json!({
         "content": [{ "type": "text", "text": format_results(&results) }]
     }))
 }
 
-fn tool_similar(args: &Value) -> Result<Value, Value> {
+/// `og_similar` handler, shared with `og serve`.
+pub(crate) fn similar(args: &Value, deadline: Option<Instant>) -> Result<Vec<SearchResult>, Value> {
     let reference = args
         .get("reference")
         .and_then(|r| r.as_str())
@@ -265,20 +335,33 @@ fn tool_similar(args: &Value) -> Result<Value, Value> {
         ));
     }
 
-    let idx = SemanticIndex::new(&index_root, None)
+    let mut idx = SemanticIndex::new(&index_root, None)
         .map_err(|e| json_rpc_error(-32000, &e.to_string()))?;
+    idx.set_deadline(deadline);
 
     let abs_str = abs_path.to_string_lossy();
-    let results = idx
-        .find_similar(&abs_str, line, name, num_results)
-        .map_err(|e| json_rpc_error(-32000, &e.to_string()))?;
+    idx.find_similar(&abs_str, line, name, num_results)
+        .map_err(|e| json_rpc_error(-32000, &e.to_string()))
+}
 
+fn tool_status(args: &Value) -> Result<Value, Value> {
+    let text = match status(args)? {
+        Some(status) => format!(
+            "Index: {}\nModel: {}\nFiles: {}\nBlocks: {}",
+            status["index"].as_str().unwrap_or(""),
+            status["model"].as_str().unwrap_or(""),
+            status["files"],
+            status["blocks"],
+        ),
+        None => "No index found. Run 'og build' first.".to_string(),
+    };
     Ok(json!({
-        "content": [{ "type": "text", "text": format_results(&results) }]
+        "content": [{ "type": "text", "text": text }]
     }))
 }
 
-fn tool_status(args: &Value) -> Result<Value, Value> {
+/// `og_status` handler, shared with `og serve`. `None` when no index exists.
+pub(crate) fn status(args: &Value) -> Result<Option<Value>, Value> {
     let path_str = args.get("path").and_then(|p| p.as_str()).unwrap_or(".");
     let path = Path::new(path_str)
         .canonicalize()
@@ -286,9 +369,7 @@ fn tool_status(args: &Value) -> Result<Value, Value> {
 
     let (index_root, existing) = index::find_index_root(&path);
     if existing.is_none() {
-        return Ok(json!({
-            "content": [{ "type": "text", "text": "No index found. Run 'og build' first." }]
-        }));
+        return Ok(None);
     }
 
     let index_dir = index_root.join(INDEX_DIR);
@@ -298,15 +379,12 @@ fn tool_status(args: &Value) -> Result<Value, Value> {
     let files = manifest.files.len();
     let blocks: usize = manifest.files.values().map(|e| e.blocks.len()).sum();
 
-    let text = format!(
-        "Index: {}\nModel: {}\nFiles: {files}\nBlocks: {blocks}",
-        index_root.display(),
-        manifest.model,
-    );
-
-    Ok(json!({
-        "content": [{ "type": "text", "text": text }]
-    }))
+    Ok(Some(json!({
+        "index": index_root.to_string_lossy(),
+        "model": manifest.model,
+        "files": files,
+        "blocks": blocks,
+    })))
 }
 
 /// Install og as an MCP server in Claude Code settings.
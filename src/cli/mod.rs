@@ -1,17 +1,31 @@
 pub mod build;
 pub mod clean;
+pub mod doctor;
+pub mod follow;
 pub mod list;
 pub mod mcp;
 pub mod model;
+pub mod open;
 pub mod outline;
 pub mod output;
+pub mod repl;
 pub mod search;
+pub mod serve;
+pub mod similar;
+pub mod stats;
 pub mod status;
+pub mod tags;
+pub mod trace;
+pub mod vacuum;
+pub mod validate;
+pub mod why;
 
 use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
 
+use crate::types::IndexOptionFlags;
+
 #[derive(Parser)]
 #[command(name = "og", about = "Semantic code search", version)]
 pub struct Cli {
@@ -34,6 +48,10 @@ pub struct Cli {
     #[arg(long = "threshold", default_value = "0.0")]
     threshold: f32,
 
+    /// Keep results scoring at or above this percentile of the result set (after boosts, before -n).
+    #[arg(long = "min-score-percentile", value_name = "P", value_parser = parse_percentile)]
+    min_score_percentile: Option<f32>,
+
     /// JSON output.
     #[arg(short = 'j', long = "json")]
     json: bool,
@@ -46,6 +64,38 @@ pub struct Cli {
     #[arg(long = "no-content")]
     no_content: bool,
 
+    /// Keys to keep in JSON results, e.g. file,line,score (implies --json).
+    #[arg(
+        long = "fields",
+        value_name = "FIELDS",
+        value_delimiter = ',',
+        value_parser = parse_field,
+        conflicts_with_all = ["files_only", "group_by_file"]
+    )]
+    fields: Option<Vec<String>>,
+
+    /// Print the JSON Schema of --json result objects and exit.
+    #[arg(long = "json-schema")]
+    json_schema: bool,
+
+    /// Group results under one header per file, sorted by line.
+    #[arg(long = "group-by-file", conflicts_with_all = ["json", "files_only", "no_content"])]
+    group_by_file: bool,
+
+    /// Show results as a directory tree with match counts.
+    #[arg(
+        long = "pretty",
+        conflicts_with_all = ["json", "files_only", "no_content", "group_by_file", "fields"]
+    )]
+    pretty: bool,
+
+    /// One plain line per result: score file:line type name (no preview, no color).
+    #[arg(
+        long = "summary",
+        conflicts_with_all = ["json", "files_only", "no_content", "group_by_file", "pretty", "fields"]
+    )]
+    summary: bool,
+
     /// Suppress progress.
     #[arg(short = 'q', long = "quiet")]
     quiet: bool,
@@ -66,13 +116,221 @@ pub struct Cli {
     #[arg(long = "no-index")]
     no_index: bool,
 
+    /// If the index was built by another og version or model, rebuild it before searching.
+    #[arg(long = "rebuild-if-stale", conflicts_with = "no_index")]
+    rebuild_if_stale: bool,
+
     /// Content preview lines (0 = none).
     #[arg(short = 'C', long = "context", default_value = "5")]
     context_lines: usize,
 
+    /// Cap total result content at N bytes, in rank order: the block crossing the cap is cut, later results keep only metadata.
+    #[arg(long = "limit-bytes", value_name = "N", conflicts_with = "stream")]
+    limit_bytes: Option<usize>,
+
     /// Filter results by regex (applied to content and name).
     #[arg(short = 'e', long = "regex")]
     regex: Option<String>,
+
+    /// Pure semantic results only (skip BM25 fusion).
+    #[arg(long = "semantic-only", conflicts_with = "bm25_only")]
+    semantic_only: bool,
+
+    /// BM25 candidates only (skip pure semantic results).
+    #[arg(long = "bm25-only")]
+    bm25_only: bool,
+
+    /// Read queries from a file (one per line) and search them in one session.
+    /// The positional argument is then the directory to search.
+    #[arg(long = "batch", value_name = "FILE")]
+    batch: Option<PathBuf>,
+
+    /// Interactive session: load the index and model once, then read queries from a prompt.
+    #[arg(long = "repl", conflicts_with_all = ["batch", "stream", "count"])]
+    repl: bool,
+
+    /// Keep running: update the index and re-run the search whenever files change.
+    #[arg(
+        long = "follow",
+        conflicts_with_all = [
+            "batch", "stream", "repl", "count", "explain_empty", "output", "max_tokens", "timeout"
+        ]
+    )]
+    follow: bool,
+
+    /// Multiply scores of a block type, e.g. trait:2 (repeatable; default weight 1.5).
+    #[arg(long = "prefer-type", value_name = "TYPE[:WEIGHT]")]
+    prefer_types: Vec<crate::boost::TypeWeight>,
+
+    /// Multiply scores of files under a path prefix, e.g. src/payments/:2 (repeatable).
+    #[arg(long = "path-boost", value_name = "PREFIX:WEIGHT")]
+    path_boosts: Vec<crate::boost::PathWeight>,
+
+    /// Favor code near a location: same file by line distance, then nearby directories.
+    #[arg(long = "near", value_name = "FILE:LINE")]
+    near: Option<crate::boost::NearAnchor>,
+
+    /// Print only the number of results (exit 1 if zero).
+    #[arg(short = 'c', long = "count", conflicts_with = "batch")]
+    count: bool,
+
+    /// Only files owned by this CODEOWNERS owner (e.g. @org/team).
+    #[arg(long = "owner")]
+    owner: Option<String>,
+
+    /// Print semantic results immediately, then append BM25 additions.
+    #[arg(long = "stream", conflicts_with_all = ["json", "no_content", "count", "batch"])]
+    stream: bool,
+
+    /// Fuse normalized scores: alpha*semantic + (1-alpha)*BM25 (default: max per result).
+    #[arg(long = "alpha", value_parser = parse_alpha)]
+    alpha: Option<f32>,
+
+    /// Only blocks mostly written by this author (needs `og build --with-git`).
+    #[arg(long = "author")]
+    author: Option<String>,
+
+    /// Only blocks last changed on or after this date (YYYY-MM-DD; needs `--with-git`).
+    #[arg(long = "after", value_name = "DATE", value_parser = parse_after)]
+    after: Option<u64>,
+
+    /// Re-check line numbers against files on disk; flag results that drifted too far.
+    #[arg(long = "verify")]
+    verify: bool,
+
+    /// Show content re-read from disk at each result's line range instead of the index-time snapshot.
+    #[arg(long = "context-lines-from-disk")]
+    context_lines_from_disk: bool,
+
+    /// Abort search (and any auto-update) after this many milliseconds [env: OG_TIMEOUT_MS].
+    #[arg(long = "timeout", value_name = "MS")]
+    timeout: Option<u64>,
+
+    /// Split queries longer than N tokens into sub-queries and merge their results.
+    #[arg(long = "max-tokens", value_name = "N", conflicts_with_all = ["stream", "batch"])]
+    max_tokens: Option<usize>,
+
+    /// Only blocks whose raw tree-sitter node kind matches (e.g. trait_item).
+    #[arg(long = "capture", value_name = "KIND")]
+    capture: Option<String>,
+
+    /// Only blocks in these languages (rust,python; markdown for docs).
+    #[arg(long = "lang", value_name = "LANGS", value_delimiter = ',')]
+    langs: Vec<String>,
+
+    #[command(flatten)]
+    docs: DocArgs,
+
+    /// Collapse results with identical (whitespace-normalized) content, keeping the best.
+    #[arg(long = "dedup")]
+    dedup: bool,
+
+    /// Keep the best result per block name (collapses overloads), for symbol lists.
+    #[arg(long = "unique-names")]
+    unique_names: bool,
+
+    /// Like --unique-names, but per file: same-named blocks in different files are kept.
+    #[arg(long = "unique-names-per-file", conflicts_with = "unique_names")]
+    unique_names_per_file: bool,
+
+    /// Keep at most N results per block type (function, class, section, ...) for balanced coverage.
+    #[arg(long = "max-per-type", value_name = "N")]
+    max_per_type: Option<usize>,
+
+    /// Merge results from the same file that sit within a few lines of each other into one region.
+    #[arg(long = "group-adjacent")]
+    group_adjacent: bool,
+
+    /// When nothing matches, print why: index size, candidates per path, scope and filter drops, top scores, BM25 terms.
+    #[arg(long = "explain-empty", conflicts_with_all = ["batch", "stream", "repl"])]
+    explain_empty: bool,
+
+    /// Bias results toward the file being edited: its top block's embedding is blended into the query.
+    #[arg(long = "context-file", value_name = "PATH")]
+    context_file: Option<PathBuf>,
+
+    /// Write every candidate per retrieval path, merged scores, boosts and the final ranking as JSON.
+    #[arg(long = "trace-json", value_name = "FILE", conflicts_with_all = ["batch", "stream", "repl", "follow"])]
+    trace_json: Option<PathBuf>,
+
+    /// Write results to this file instead of stdout (replaced atomically, without colors).
+    #[arg(long = "output", value_name = "FILE", conflicts_with_all = ["count", "stream", "repl", "follow"])]
+    output: Option<PathBuf>,
+
+    /// Keep only results also similar to this file's code (query AND file).
+    #[arg(long = "and-file", value_name = "PATH")]
+    and_file: Option<PathBuf>,
+
+    /// Only files modified within a window at the last build/update (12h, 3d, 2w, or YYYY-MM-DD).
+    #[arg(long = "changed-since", value_name = "WHEN", value_parser = parse_changed_since)]
+    changed_since: Option<u64>,
+
+    /// Re-rank by each block's signature (declaration up to the body), for API-shape queries.
+    #[arg(long = "signatures")]
+    signatures: bool,
+
+    /// Drop language keywords and English stopwords (the, that, fn, ...) from the BM25 query.
+    #[arg(long = "strip-stopwords")]
+    strip_stopwords: bool,
+
+    /// Make a missing .og/acronyms.txt an error (acronyms in it are expanded whenever the file exists).
+    #[arg(long = "expand-acronyms")]
+    expand_acronyms: bool,
+
+    /// Skip test files (tests/ dirs, *_test.*, test_*.py, *.test.ts, *.spec.js, FooTest.java, ...).
+    #[arg(long = "no-tests", conflicts_with = "tests_only")]
+    no_tests: bool,
+
+    /// Only test files (same conventions as --no-tests).
+    #[arg(long = "tests-only")]
+    tests_only: bool,
+
+    /// Print extra diagnostics to stderr; -vv adds debug logs [env: OG_LOG].
+    #[arg(short = 'v', long = "verbose", action = clap::ArgAction::Count)]
+    verbose: u8,
+}
+
+fn parse_after(s: &str) -> Result<u64, String> {
+    crate::index::git::parse_date(s)
+        .ok_or_else(|| format!("invalid date '{s}' (expected YYYY-MM-DD)"))
+}
+
+fn parse_changed_since(s: &str) -> Result<u64, String> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    search::parse_changed_since(s, now)
+        .ok_or_else(|| format!("invalid time '{s}' (expected e.g. 12h, 3d, 2w or YYYY-MM-DD)"))
+}
+
+fn parse_field(s: &str) -> Result<String, String> {
+    let field = s.trim();
+    if output::JSON_FIELDS.contains(&field) {
+        Ok(field.to_string())
+    } else {
+        Err(format!(
+            "unknown field '{field}' (expected one of: {})",
+            output::JSON_FIELDS.join(", ")
+        ))
+    }
+}
+
+fn parse_percentile(s: &str) -> Result<f32, String> {
+    let p: f32 = s.parse().map_err(|_| format!("invalid number '{s}'"))?;
+    if (0.0..=100.0).contains(&p) {
+        Ok(p)
+    } else {
+        Err(format!("percentile must be in [0, 100], got {p}"))
+    }
+}
+
+fn parse_alpha(s: &str) -> Result<f32, String> {
+    let alpha: f32 = s.parse().map_err(|_| format!("invalid number '{s}'"))?;
+    if (0.0..=1.0).contains(&alpha) {
+        Ok(alpha)
+    } else {
+        Err(format!("alpha must be in [0, 1], got {alpha}"))
+    }
 }
 
 #[derive(Subcommand)]
@@ -82,12 +340,71 @@ enum Command {
         /// Directory to index.
         #[arg(default_value = ".")]
         path: PathBuf,
-        /// Force full rebuild.
+        /// Force full rebuild (options not passed again revert to their defaults).
         #[arg(short = 'f', long = "force")]
         force: bool,
         /// Suppress progress.
         #[arg(short = 'q', long = "quiet")]
         quiet: bool,
+        /// Lines per block for files without a grammar (split into sequential blocks; default 50).
+        #[arg(long = "fallback-lines", value_name = "LINES")]
+        fallback_lines: Option<usize>,
+        /// Include leading imports and the enclosing type in block embeddings.
+        #[arg(long = "embed-context")]
+        embed_context: bool,
+        /// Prepend doc comments and docstrings to block embeddings (experimental).
+        #[arg(long = "embed-docs")]
+        embed_docs: bool,
+        /// Record git blame author and last-commit date per block (for --author/--after).
+        #[arg(long = "with-git")]
+        with_git: bool,
+        /// Also index recent git commit messages, searchable alongside code ("when did we add X").
+        #[arg(long = "with-commits")]
+        with_commits: bool,
+        /// One vector store per top-level directory; searches scoped to a subdirectory open only its store.
+        #[arg(long = "shard")]
+        shard: bool,
+        /// Don't index top-level constants and variables as blocks.
+        #[arg(long = "no-constants")]
+        no_constants: bool,
+        /// Index minified files and files marked @generated / DO NOT EDIT.
+        #[arg(long = "no-skip-minified")]
+        no_skip_minified: bool,
+        /// Index files marked linguist-generated in .gitattributes.
+        #[arg(long = "index-generated")]
+        index_generated: bool,
+        /// Convert UTF-16 and Latin-1 files to UTF-8 instead of skipping them.
+        #[arg(long = "transcode")]
+        transcode: bool,
+        /// Index raw BM25 text without splitting identifiers (getUrl stays one term).
+        #[arg(long = "no-bm25-expand", conflicts_with_all = ["split_min_word", "split_min_part"])]
+        no_bm25_expand: bool,
+        /// Shortest identifier split into BM25 terms (e.g. getUrl -> get, url; default 3).
+        #[arg(long = "split-min-word", value_name = "CHARS")]
+        split_min_word: Option<usize>,
+        /// Shortest split part kept as a BM25 term (1 keeps getX -> x; default 2).
+        #[arg(long = "split-min-part", value_name = "CHARS")]
+        split_min_part: Option<usize>,
+        /// Drop blocks with fewer than TOKENS code tokens (default 3), e.g. empty impls and stubs; 0 keeps all.
+        #[arg(long = "prune", value_name = "TOKENS", num_args = 0..=1, default_missing_value = "3")]
+        prune: Option<usize>,
+        /// Add directory and file name words to each block's BM25 text, so queries match on paths.
+        #[arg(long = "path-tokens")]
+        path_tokens: bool,
+        /// Split code blocks longer than LINES with no nested blocks (e.g. huge classes)
+        /// into sequential parts, each embedded separately.
+        #[arg(long = "max-block-lines", value_name = "LINES", value_parser = clap::value_parser!(u64).range(1..))]
+        max_block_lines: Option<u64>,
+        /// Manifest storage: json (default) or bin, a compact binary file that
+        /// loads faster on large indexes. Kept across rebuilds once chosen.
+        #[arg(long = "manifest-format", value_name = "FORMAT", value_parser = ["json", "bin"])]
+        manifest_format: Option<String>,
+        /// Print extra diagnostics (e.g. transcoded and pruned counts); -vv adds
+        /// skipped files with reasons, embedding batches and store operations [env: OG_LOG].
+        #[arg(short = 'v', long = "verbose", action = clap::ArgAction::Count)]
+        verbose: u8,
+        #[command(flatten)]
+        precision: PrecisionArgs,
     },
     /// Show index status.
     Status {
@@ -104,11 +421,65 @@ enum Command {
         #[arg(short = 'r', long = "recursive")]
         recursive: bool,
     },
+    /// Drop orphaned vectors from the store.
+    Vacuum {
+        /// Directory.
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Check index integrity: manifest, model/version, store blocks (non-zero exit on problems).
+    Validate {
+        /// Directory.
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Diagnose setup problems: model, ONNX session, index freshness, skipped files.
+    Doctor {
+        /// Directory.
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
     /// List all indexes under a directory.
     List {
         /// Directory to search.
         #[arg(default_value = ".")]
         path: PathBuf,
+        /// JSON output (path, blocks, model, version, needs_rebuild).
+        #[arg(short = 'j', long = "json")]
+        json: bool,
+    },
+    /// Find code similar to a snippet (from --from-text or stdin).
+    Similar {
+        /// Code snippet to match (reads stdin if omitted).
+        #[arg(long = "from-text", value_name = "TEXT")]
+        from_text: Option<String>,
+        /// Directory to search.
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Number of results.
+        #[arg(short = 'n', default_value = "10")]
+        num_results: usize,
+        /// JSON output.
+        #[arg(short = 'j', long = "json")]
+        json: bool,
+        /// Content preview lines (0 = none).
+        #[arg(short = 'C', long = "context", default_value = "5")]
+        context_lines: usize,
+        /// Suppress progress.
+        #[arg(short = 'q', long = "quiet")]
+        quiet: bool,
+        #[command(flatten)]
+        docs: DocArgs,
+    },
+    /// Explain how one block ranked for a query (scores and boosts).
+    Why {
+        /// Search query.
+        query: String,
+        /// Block to explain: file:line or file#name.
+        target: String,
+        /// Directory to search.
+        #[arg(default_value = ".")]
+        path: PathBuf,
     },
     /// Show block structure of an indexed file.
     Outline {
@@ -119,6 +490,41 @@ enum Command {
         #[arg(short = 'j', long = "json")]
         json: bool,
     },
+    /// List every indexed block name with its file:line (no model needed).
+    Tags {
+        /// Directory to list.
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// plain (name, file:line, type), json, or ctags (a sorted `tags` file for editors).
+        #[arg(long = "format", default_value = "plain", value_parser = ["plain", "json", "ctags"])]
+        format: String,
+    },
+    /// Corpus analytics: blocks by type and extension, largest files, index size.
+    Stats {
+        /// Directory to summarize.
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// JSON output.
+        #[arg(short = 'j', long = "json")]
+        json: bool,
+    },
+    /// Open the top search result in $VISUAL/$EDITOR.
+    Open {
+        /// Search query.
+        query: String,
+        /// Directory to search.
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Filter file types (py,js,ts).
+        #[arg(short = 't', long = "type")]
+        file_types: Option<String>,
+        /// Exclude glob patterns.
+        #[arg(long = "exclude")]
+        exclude: Vec<String>,
+        /// Exclude docs (md, txt, rst).
+        #[arg(long = "code-only")]
+        code_only: bool,
+    },
     /// Show embedding model status.
     Model {
         #[command(subcommand)]
@@ -128,53 +534,267 @@ enum Command {
     Mcp,
     /// Install og as MCP server in Claude Code.
     InstallClaudeCode,
+    /// Serve search over a local HTTP JSON API.
+    Serve {
+        /// Port to listen on (127.0.0.1).
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+    },
 }
 
 #[derive(Subcommand)]
 enum ModelAction {
     /// Download embedding model.
-    Install,
+    Install {
+        #[command(flatten)]
+        precision: PrecisionArgs,
+    },
+    /// Delete the cached model files.
+    Rm,
+}
+
+/// Documentation blocks in similar-code search (file references, `og similar`).
+#[derive(clap::Args)]
+struct DocArgs {
+    /// Include doc blocks (markdown/text sections) in similar results.
+    #[arg(long = "include-docs", conflicts_with = "docs_only")]
+    include_docs: bool,
+    /// Return only doc blocks in similar results.
+    #[arg(long = "docs-only")]
+    docs_only: bool,
+}
+
+impl DocArgs {
+    fn doc_filter(&self) -> crate::types::DocFilter {
+        crate::types::DocFilter::from_flags(self.include_docs, self.docs_only)
+    }
+}
+
+/// Model precision selector (changing it rebuilds the index).
+#[derive(clap::Args)]
+struct PrecisionArgs {
+    /// Use the INT8 model (default).
+    #[arg(long = "model-int8", conflicts_with = "model_fp32")]
+    model_int8: bool,
+    /// Use the FP32 model (more accurate, larger and slower).
+    #[arg(long = "model-fp32")]
+    model_fp32: bool,
+}
+
+impl PrecisionArgs {
+    fn precision(&self) -> crate::embedder::Precision {
+        crate::embedder::Precision::from_flags(self.model_fp32 && !self.model_int8)
+    }
+
+    /// The precision passed, if either flag was.
+    fn requested(&self) -> Option<crate::embedder::Precision> {
+        (self.model_fp32 || self.model_int8).then(|| self.precision())
+    }
 }
 
 /// Main CLI entry point.
 pub fn run() -> anyhow::Result<()> {
     let cli = Cli::parse();
+    let verbosity = match &cli.command {
+        Some(Command::Build { verbose, .. }) => *verbose,
+        _ => cli.verbose,
+    };
+    crate::logging::init(verbosity);
 
     match cli.command {
-        Some(Command::Build { path, force, quiet }) => build::run(&path, force, quiet),
+        Some(Command::Build {
+            path,
+            force,
+            quiet,
+            fallback_lines,
+            embed_context,
+            embed_docs,
+            with_git,
+            with_commits,
+            shard,
+            no_constants,
+            no_skip_minified,
+            index_generated,
+            transcode,
+            no_bm25_expand,
+            split_min_word,
+            split_min_part,
+            prune,
+            path_tokens,
+            max_block_lines,
+            manifest_format,
+            verbose,
+            precision,
+        }) => build::run(
+            &path,
+            force,
+            quiet,
+            verbose > 0,
+            manifest_format.map(|f| f == "bin"),
+            &IndexOptionFlags {
+                fallback_lines,
+                embed_context: embed_context.then_some(true),
+                embed_docs: embed_docs.then_some(true),
+                with_git: with_git.then_some(true),
+                skip_constants: no_constants.then_some(true),
+                keep_minified: no_skip_minified.then_some(true),
+                index_generated: index_generated.then_some(true),
+                transcode: transcode.then_some(true),
+                no_bm25_expand: no_bm25_expand.then_some(true),
+                split_min_word,
+                split_min_part,
+                prune_min_tokens: prune,
+                path_tokens: path_tokens.then_some(true),
+                max_block_lines: max_block_lines.map(|n| n as usize),
+                with_commits: with_commits.then_some(true),
+                shard: shard.then_some(true),
+            },
+            precision.requested(),
+        ),
         Some(Command::Status { path }) => status::run(&path),
         Some(Command::Clean { path, recursive }) => clean::run(&path, recursive),
-        Some(Command::List { path }) => list::run(&path),
+        Some(Command::Vacuum { path }) => vacuum::run(&path),
+        Some(Command::Validate { path }) => validate::run(&path),
+        Some(Command::Doctor { path }) => doctor::run(&path),
+        Some(Command::List { path, json }) => list::run(&path, json),
         Some(Command::Outline { path, json }) => outline::run(&path, json),
+        Some(Command::Tags { path, format }) => tags::run(&path, &format),
+        Some(Command::Stats { path, json }) => stats::run(&path, json),
+        Some(Command::Similar {
+            from_text,
+            path,
+            num_results,
+            json,
+            context_lines,
+            quiet,
+            docs,
+        }) => similar::run(
+            from_text.as_deref(),
+            &path,
+            num_results,
+            crate::types::OutputFormat::from_flags(json, false, false, false, false, false),
+            quiet,
+            context_lines,
+            docs.doc_filter(),
+        ),
+        Some(Command::Why {
+            query,
+            target,
+            path,
+        }) => why::run(&query, &target, &path),
+        Some(Command::Open {
+            query,
+            path,
+            file_types,
+            exclude,
+            code_only,
+        }) => open::run(&query, &path, file_types.as_deref(), &exclude, code_only),
         Some(Command::Model { action }) => match action {
-            Some(ModelAction::Install) => model::install(),
+            Some(ModelAction::Install { precision }) => model::install(precision.precision()),
+            Some(ModelAction::Rm) => model::rm(),
             None => model::status(),
         },
         Some(Command::Mcp) => mcp::run(),
         Some(Command::InstallClaudeCode) => mcp::install_claude_code(),
-        None if cli.query.is_none() => {
+        Some(Command::Serve { port }) => serve::run(port),
+        None if cli.json_schema => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&output::result_schema())?
+            );
+            Ok(())
+        }
+        None if cli.query.is_none() && cli.batch.is_none() && !cli.repl => {
             use clap::CommandFactory;
             Cli::command().print_help()?;
             println!();
             Ok(())
         }
-        None => search::run(&search::SearchParams {
-            query: cli.query.as_deref(),
-            path: &cli.path,
-            num_results: cli.num_results,
-            threshold: cli.threshold,
-            format: crate::types::OutputFormat::from_flags(
-                cli.json,
-                cli.files_only,
-                cli.no_content,
-            ),
-            quiet: cli.quiet,
-            file_types: cli.file_types.as_deref(),
-            exclude: &cli.exclude,
-            code_only: cli.code_only,
-            no_index: cli.no_index,
-            context_lines: cli.context_lines,
-            regex: cli.regex.as_deref(),
-        }),
+        None if cli.batch.is_some() || cli.repl => {
+            // With --batch or --repl there is no query; a single positional is the path
+            let path = match (&cli.query, cli.path.as_os_str() == ".") {
+                (Some(q), true) => PathBuf::from(q),
+                _ => cli.path.clone(),
+            };
+            let params = search::SearchParams {
+                query: None,
+                path: &path,
+                ..search_params(&cli)
+            };
+            if cli.repl {
+                repl::run(&params)
+            } else {
+                search::run(&params)
+            }
+        }
+        None if cli.follow => follow::run(&search_params(&cli)),
+        None => search::run(&search_params(&cli)),
+    }
+}
+
+fn search_params(cli: &Cli) -> search::SearchParams<'_> {
+    search::SearchParams {
+        query: cli.query.as_deref(),
+        path: &cli.path,
+        num_results: cli.num_results,
+        threshold: cli.threshold,
+        min_score_percentile: cli.min_score_percentile,
+        format: crate::types::OutputFormat::from_flags(
+            cli.json || (cli.fields.is_some() && !cli.no_content),
+            cli.files_only,
+            cli.no_content,
+            cli.group_by_file,
+            cli.pretty,
+            cli.summary,
+        ),
+        quiet: cli.quiet,
+        file_types: cli.file_types.as_deref(),
+        exclude: &cli.exclude,
+        code_only: cli.code_only,
+        no_index: cli.no_index,
+        rebuild_if_stale: cli.rebuild_if_stale,
+        context_lines: cli.context_lines,
+        limit_bytes: cli.limit_bytes,
+        regex: cli.regex.as_deref(),
+        mode: crate::types::SearchMode::from_flags(cli.semantic_only, cli.bm25_only),
+        batch: cli.batch.as_deref(),
+        prefer_types: &cli.prefer_types,
+        path_boosts: &cli.path_boosts,
+        near: cli.near.as_ref(),
+        count: cli.count,
+        owner: cli.owner.as_deref(),
+        stream: cli.stream,
+        alpha: cli.alpha,
+        author: cli.author.as_deref(),
+        after: cli.after,
+        verify: cli.verify,
+        context_lines_from_disk: cli.context_lines_from_disk,
+        timeout: match cli.timeout {
+            Some(ms) => Some(std::time::Duration::from_millis(ms)),
+            None => crate::index::timeout_from_env(),
+        },
+        max_tokens: cli.max_tokens,
+        capture: cli.capture.as_deref(),
+        langs: &cli.langs,
+        doc_filter: cli.docs.doc_filter(),
+        dedup: cli.dedup,
+        unique_names: crate::types::UniqueNames::from_flags(
+            cli.unique_names,
+            cli.unique_names_per_file,
+        ),
+        max_per_type: cli.max_per_type,
+        group_adjacent: cli.group_adjacent,
+        explain_empty: cli.explain_empty,
+        context_file: cli.context_file.as_deref(),
+        and_file: cli.and_file.as_deref(),
+        trace_json: cli.trace_json.as_deref(),
+        output: cli.output.as_deref(),
+        tests: crate::types::TestFilter::from_flags(cli.no_tests, cli.tests_only),
+        changed_since: cli.changed_since,
+        signatures: cli.signatures,
+        strip_stopwords: cli.strip_stopwords,
+        expand_acronyms: cli.expand_acronyms,
+        fields: cli.fields.as_deref(),
+        verbose: cli.verbose > 0,
     }
 }
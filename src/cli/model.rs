@@ -1,34 +1,53 @@
-use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
 use hf_hub::api::sync::Api;
-use hf_hub::Cache;
+use hf_hub::{Cache, Repo};
 
-use crate::embedder;
+use crate::embedder::{self, Precision};
 
 pub fn status() -> Result<()> {
     let config = embedder::MODEL;
     let cache = Cache::default();
-    let repo = cache.model(config.repo.to_string());
-    let installed =
-        repo.get(config.model_file).is_some() && repo.get(config.tokenizer_file).is_some();
-    let marker = if installed {
-        "installed"
+    let size = dir_size(&repo_dir(&cache));
+    if size > 0 {
+        println!(
+            "  {} ({}d/token, {} on disk)",
+            config.repo,
+            config.token_dim,
+            format_size(size)
+        );
     } else {
-        "not installed"
-    };
-    println!("  {} ({}d/token, {marker})", config.repo, config.token_dim);
+        println!("  {} ({}d/token)", config.repo, config.token_dim);
+    }
+    for precision in [Precision::Int8, Precision::Fp32] {
+        if config.model_file_for(precision).is_none() {
+            continue;
+        }
+        let marker = if installed(&cache, precision) {
+            "installed"
+        } else {
+            "not installed"
+        };
+        println!("    {}: {marker}", precision.name());
+    }
 
     Ok(())
 }
 
-pub fn install() -> Result<()> {
+pub fn install(precision: Precision) -> Result<()> {
     let config = embedder::MODEL;
+    let Some(model_file) = config.model_file_for(precision) else {
+        eprintln!("{} has no {} model variant", config.repo, precision.name());
+        std::process::exit(crate::types::EXIT_ERROR);
+    };
     let api = Api::new()?;
     let repo = api.model(config.repo.to_string());
 
-    println!("Downloading {}...", config.repo);
+    println!("Downloading {} ({})...", config.repo, precision.name());
 
-    for filename in [config.model_file, config.tokenizer_file] {
-        match repo.get(filename) {
+    for filename in [model_file, config.tokenizer_file] {
+        match embedder::download(&repo, filename) {
             Ok(path) => {
                 println!("  {filename} -> {}", path.display());
             }
@@ -40,6 +59,84 @@ pub fn install() -> Result<()> {
         }
     }
 
-    println!("Model installed: {}", config.repo);
+    println!("Model installed: {} ({})", config.repo, precision.name());
+    Ok(())
+}
+
+/// Whether the model file for `precision` and the tokenizer are in the cache.
+pub(super) fn installed(cache: &Cache, precision: Precision) -> bool {
+    let config = embedder::MODEL;
+    let repo = cache.model(config.repo.to_string());
+    config
+        .model_file_for(precision)
+        .is_some_and(|file| repo.get(file).is_some())
+        && repo.get(config.tokenizer_file).is_some()
+}
+
+/// Delete the configured model repo from the Hugging Face cache.
+pub fn rm() -> Result<()> {
+    let config = embedder::MODEL;
+    let dir = repo_dir(&Cache::default());
+    if !dir.exists() {
+        println!("Model not installed: {}", config.repo);
+        return Ok(());
+    }
+
+    let size = dir_size(&dir);
+    std::fs::remove_dir_all(&dir).with_context(|| format!("Failed to remove {}", dir.display()))?;
+    println!("Removed {} ({})", dir.display(), format_size(size));
     Ok(())
 }
+
+/// Cache directory holding the configured repo's blobs, snapshots and refs.
+fn repo_dir(cache: &Cache) -> PathBuf {
+    cache
+        .path()
+        .join(Repo::model(embedder::MODEL.repo.to_string()).folder_name())
+}
+
+/// Total size of regular files under `dir`; snapshot symlinks are not followed,
+/// so each blob is counted once.
+pub(super) fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(ft) if ft.is_dir() => dir_size(&entry.path()),
+            Ok(ft) if ft.is_file() => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            _ => 0,
+        })
+        .sum()
+}
+
+pub(super) fn format_size(bytes: u64) -> String {
+    const MB: f64 = 1024.0 * 1024.0;
+    if bytes as f64 >= MB {
+        format!("{:.1} MB", bytes as f64 / MB)
+    } else {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_are_human_readable() {
+        assert_eq!(format_size(17 * 1024 * 1024 + 300 * 1024), "17.3 MB");
+        assert_eq!(format_size(2048), "2.0 KB");
+    }
+
+    #[test]
+    fn dir_size_sums_nested_files() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp.path().join("blobs")).unwrap();
+        std::fs::write(tmp.path().join("blobs/a"), [0u8; 100]).unwrap();
+        std::fs::write(tmp.path().join("b"), [0u8; 20]).unwrap();
+        assert_eq!(dir_size(tmp.path()), 120);
+        assert_eq!(dir_size(&tmp.path().join("missing")), 0);
+    }
+}
@@ -0,0 +1,155 @@
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+
+use crate::cli::search::{self, SearchParams};
+use crate::index;
+use crate::types::{EXIT_ERROR, EXIT_NO_MATCH};
+
+/// Candidates fetched before filtering; only the top result is opened.
+const CANDIDATES: usize = 10;
+
+/// Search and open the top result in `$VISUAL`/`$EDITOR` at its start line.
+/// Prints `file:line` when no editor is configured.
+pub fn run(
+    query: &str,
+    path: &Path,
+    file_types: Option<&str>,
+    exclude: &[String],
+    code_only: bool,
+) -> Result<()> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if !path.exists() {
+        eprintln!("Path does not exist: {}", path.display());
+        std::process::exit(EXIT_ERROR);
+    }
+
+    let params = SearchParams {
+        num_results: CANDIDATES,
+        quiet: true,
+        file_types,
+        exclude,
+        code_only,
+        verify: true,
+        ..SearchParams::new(Some(query), &path)
+    };
+
+    let mut index = search::open_index(
+        &path,
+        false,
+        false,
+        search::deadline(index::timeout_from_env()),
+    )?;
+    index.set_search_scope(Some(&path));
+    let results = index.search(query, CANDIDATES)?;
+    let results = search::apply_filters(results, query, &params);
+
+    let Some(top) = results.first() else {
+        eprintln!("No results found");
+        std::process::exit(EXIT_NO_MATCH);
+    };
+
+    // Result lines are 0-indexed; editors count from 1
+    let line = top.line + 1;
+    let editor = std::env::var("VISUAL")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .or_else(|| std::env::var("EDITOR").ok())
+        .filter(|v| !v.trim().is_empty());
+
+    let Some(editor) = editor else {
+        println!("{}:{}", top.file, line);
+        return Ok(());
+    };
+
+    let Some((program, args)) = editor_command(&editor, &top.file, line) else {
+        bail!("Invalid editor command: {editor}");
+    };
+    eprintln!("{} {}:{} ({})", top.name, top.file, line, top.block_type);
+
+    let status = Command::new(&program)
+        .args(&args)
+        .status()
+        .with_context(|| format!("Failed to launch editor: {program}"))?;
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(EXIT_ERROR));
+    }
+    Ok(())
+}
+
+/// Build the editor invocation for opening `file` at `line` (1-indexed).
+///
+/// `editor` may carry its own arguments (e.g. `code --wait`). The jump syntax
+/// is chosen by the program's basename; unknown editors just get the file.
+fn editor_command(editor: &str, file: &str, line: usize) -> Option<(String, Vec<String>)> {
+    let mut parts = editor.split_whitespace().map(String::from);
+    let program = parts.next()?;
+    let mut args: Vec<String> = parts.collect();
+
+    let name = Path::new(&program)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match name.as_str() {
+        "code" | "code-insiders" | "codium" | "cursor" | "windsurf" => {
+            args.push("-g".to_string());
+            args.push(format!("{file}:{line}"));
+        }
+        "vim" | "nvim" | "vi" | "gvim" | "nano" | "emacs" | "emacsclient" | "kak" | "micro" => {
+            args.push(format!("+{line}"));
+            args.push(file.to_string());
+        }
+        "hx" | "helix" | "subl" | "zed" => {
+            args.push(format!("{file}:{line}"));
+        }
+        "idea" | "pycharm" | "goland" | "clion" | "rustrover" | "webstorm" => {
+            args.push("--line".to_string());
+            args.push(line.to_string());
+            args.push(file.to_string());
+        }
+        _ => args.push(file.to_string()),
+    }
+
+    Some((program, args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cmd(editor: &str) -> (String, Vec<String>) {
+        editor_command(editor, "src/main.rs", 42).unwrap()
+    }
+
+    #[test]
+    fn vscode_uses_goto() {
+        let (program, args) = cmd("code --wait");
+        assert_eq!(program, "code");
+        assert_eq!(args, ["--wait", "-g", "src/main.rs:42"]);
+    }
+
+    #[test]
+    fn vim_uses_plus_line() {
+        let (program, args) = cmd("/usr/bin/nvim");
+        assert_eq!(program, "/usr/bin/nvim");
+        assert_eq!(args, ["+42", "src/main.rs"]);
+    }
+
+    #[test]
+    fn colon_suffix_editors() {
+        assert_eq!(cmd("hx").1, ["src/main.rs:42"]);
+        assert_eq!(cmd("zed").1, ["src/main.rs:42"]);
+    }
+
+    #[test]
+    fn unknown_editor_gets_file_only() {
+        assert_eq!(cmd("ed").1, ["src/main.rs"]);
+    }
+
+    #[test]
+    fn empty_editor() {
+        assert!(editor_command("  ", "a.rs", 1).is_none());
+    }
+}
@@ -3,15 +3,15 @@ use std::path::Path;
 use anyhow::Result;
 use owo_colors::OwoColorize;
 
-use crate::index::{find_index_root, manifest::Manifest, VECTORS_DIR};
+use crate::index::{find_index_root, manifest::Manifest, store::Store, VECTORS_DIR};
 use crate::types::EXIT_ERROR;
 
 /// A block entry for outline display.
-struct OutlineEntry {
-    name: String,
-    block_type: String,
-    start_line: usize,
-    end_line: usize,
+pub(super) struct OutlineEntry {
+    pub name: String,
+    pub block_type: String,
+    pub start_line: usize,
+    pub end_line: usize,
 }
 
 pub fn run(path: &Path, json: bool) -> Result<()> {
@@ -31,8 +31,7 @@ pub fn run(path: &Path, json: bool) -> Result<()> {
         }
     };
 
-    let vectors_path = index_dir.join(VECTORS_DIR).to_string_lossy().into_owned();
-    let store = match omendb::VectorStore::open(&vectors_path) {
+    let store = match Store::open(&index_dir.join(VECTORS_DIR), manifest.options.shard, None) {
         Ok(s) => s,
         Err(e) => {
             eprintln!("Failed to open index: {e}");
@@ -76,7 +75,8 @@ pub fn run(path: &Path, json: bool) -> Result<()> {
     Ok(())
 }
 
-fn get_blocks(block_ids: &[String], store: &omendb::VectorStore) -> Vec<OutlineEntry> {
+/// Block metadata for `block_ids`, sorted by start line (0-based).
+pub(super) fn get_blocks(block_ids: &[String], store: &Store) -> Vec<OutlineEntry> {
     let mut entries: Vec<OutlineEntry> = block_ids
         .iter()
         .filter_map(|id| {
@@ -101,7 +101,7 @@ fn get_blocks(block_ids: &[String], store: &omendb::VectorStore) -> Vec<OutlineE
     entries
 }
 
-fn print_default(file_entries: &[(&str, &[String])], store: &omendb::VectorStore) {
+fn print_default(file_entries: &[(&str, &[String])], store: &Store) {
     for (rel_path, block_ids) in file_entries {
         println!("{}", rel_path.bold());
         let blocks = get_blocks(block_ids, store);
@@ -117,7 +117,7 @@ fn print_default(file_entries: &[(&str, &[String])], store: &omendb::VectorStore
     }
 }
 
-fn print_json(file_entries: &[(&str, &[String])], store: &omendb::VectorStore) -> Result<()> {
+fn print_json(file_entries: &[(&str, &[String])], store: &Store) -> Result<()> {
     let output: Vec<serde_json::Value> = file_entries
         .iter()
         .map(|(rel_path, block_ids)| {
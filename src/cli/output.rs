@@ -1,16 +1,271 @@
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::io::{self, Write};
 use std::path::Path;
 
+use anyhow::{Context, Result};
+use owo_colors::{OwoColorize, Style};
+
 use crate::types::{OutputFormat, SearchResult};
 
-/// Print search results in the specified format.
+/// Keys a JSON result can have, selectable with `--fields`.
+pub const JSON_FIELDS: &[&str] = &[
+    "file",
+    "type",
+    "name",
+    "line",
+    "end_line",
+    "content",
+    "score",
+    "author",
+    "commit_time",
+    "capture",
+    "language",
+    "content_hash",
+    "stale",
+];
+
+/// JSON Schema for `--json` output (`--json-schema`): an array of result
+/// objects. Keep in sync with `SearchResult`'s serialization and `JSON_FIELDS`.
+pub fn result_schema() -> serde_json::Value {
+    serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "og search results",
+        "description": "Output of og --json: results ranked by score. --batch prints an object mapping each query to such an array; --fields keeps only the listed keys.",
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": {
+                "file": {
+                    "type": "string",
+                    "description": "Path relative to the searched directory"
+                },
+                "type": {
+                    "type": "string",
+                    "description": "Block type: function, method, class, struct, section, commit, ..."
+                },
+                "name": { "type": "string", "description": "Block name" },
+                "line": { "type": "integer", "minimum": 0, "description": "Start line" },
+                "end_line": { "type": "integer", "minimum": 0, "description": "End line" },
+                "content": {
+                    "type": "string",
+                    "description": "Block source; absent with --no-content or past the --limit-bytes cap"
+                },
+                "score": { "type": "number", "description": "Relevance score, higher is better" },
+                "author": {
+                    "type": "string",
+                    "description": "Main blame author (index built with --with-git)"
+                },
+                "commit_time": {
+                    "type": "integer",
+                    "description": "Unix time of the newest commit touching the block (--with-git)"
+                },
+                "capture": { "type": "string", "description": "Tree-sitter node kind" },
+                "language": { "type": "string", "description": "Source language (rust, python, ...)" },
+                "content_hash": {
+                    "type": "string",
+                    "description": "Checksum of the content at index time"
+                },
+                "stale": {
+                    "type": "boolean",
+                    "const": true,
+                    "description": "Present when --verify could not find the block near its line"
+                }
+            },
+            "required": ["file", "type", "name", "line", "end_line", "score"],
+            "additionalProperties": false
+        }
+    })
+}
+
+thread_local! {
+    /// Whether text output is colored; off while rendering `--output FILE`.
+    static COLOR: Cell<bool> = const { Cell::new(true) };
+}
+
+/// Turns colors off until dropped.
+struct NoColor(bool);
+
+impl NoColor {
+    fn new() -> Self {
+        Self(COLOR.replace(false))
+    }
+}
+
+impl Drop for NoColor {
+    fn drop(&mut self) {
+        COLOR.set(self.0);
+    }
+}
+
+/// `text` in `style`, or plain when colors are off.
+fn paint(text: impl Display, style: Style) -> String {
+    if COLOR.get() {
+        text.style(style).to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Print search results in the specified format. `fields` limits the keys of
+/// JSON results (`--fields`); None keeps all.
 pub fn print_results(
     results: &[SearchResult],
     format: OutputFormat,
     show_score: bool,
     root: Option<&Path>,
     context_lines: usize,
-) {
-    let results: Vec<SearchResult> = results
+    fields: Option<&[String]>,
+) -> io::Result<()> {
+    write_results(
+        &mut io::stdout().lock(),
+        results,
+        format,
+        show_score,
+        root,
+        context_lines,
+        fields,
+    )
+}
+
+/// Write search results in the specified format to `out`.
+pub fn write_results(
+    out: &mut dyn Write,
+    results: &[SearchResult],
+    format: OutputFormat,
+    show_score: bool,
+    root: Option<&Path>,
+    context_lines: usize,
+    fields: Option<&[String]>,
+) -> io::Result<()> {
+    let results = relativize(results, root);
+
+    match format {
+        OutputFormat::FilesOnly => write_files_only(out, &results),
+        OutputFormat::Json => write_json(out, &results, false, fields),
+        OutputFormat::NoContent => write_json(out, &results, true, fields),
+        OutputFormat::Default => write_default(out, &results, show_score, context_lines),
+        OutputFormat::Grouped => write_grouped(out, &results, show_score, context_lines),
+        OutputFormat::Tree => write_tree(out, &results, show_score, context_lines),
+        OutputFormat::Summary => write_summary(out, &results),
+    }
+}
+
+/// `--output FILE`: render into memory without colors, then replace `path`
+/// through a temporary file in the same directory, so readers never see a
+/// partial file and a failed search leaves any previous file intact.
+pub fn write_file(
+    path: &Path,
+    render: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> Result<()> {
+    let mut buf = Vec::new();
+    {
+        let _plain = NoColor::new();
+        render(&mut buf)?;
+    }
+
+    let name = path
+        .file_name()
+        .with_context(|| format!("--output needs a file path, got {}", path.display()))?;
+    let tmp_path = path.with_file_name(format!(".{}.tmp", name.to_string_lossy()));
+    std::fs::write(&tmp_path, &buf)
+        .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+    if let Err(e) = std::fs::rename(&tmp_path, path) {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(e).with_context(|| format!("Failed to replace {}", path.display()));
+    }
+    Ok(())
+}
+
+/// Cap the total content bytes across results (`--limit-bytes`), in rank
+/// order: the block that crosses the cap is cut at a char boundary, and later
+/// results keep their metadata but lose their content.
+pub fn limit_content_bytes(results: &mut [SearchResult], limit: usize) {
+    let mut remaining = limit;
+    for r in results {
+        let Some(content) = r.content.as_mut() else {
+            continue;
+        };
+        if content.len() > remaining {
+            let mut end = remaining;
+            while !content.is_char_boundary(end) {
+                end -= 1;
+            }
+            content.truncate(end);
+        }
+        remaining -= content.len();
+        if content.is_empty() {
+            r.content = None;
+        }
+    }
+}
+
+/// Print grouped results for several queries (`--batch`).
+pub fn print_batch(
+    groups: &[(String, Vec<SearchResult>)],
+    format: OutputFormat,
+    root: Option<&Path>,
+    context_lines: usize,
+    fields: Option<&[String]>,
+) -> io::Result<()> {
+    write_batch(
+        &mut io::stdout().lock(),
+        groups,
+        format,
+        root,
+        context_lines,
+        fields,
+    )
+}
+
+/// Write grouped results for several queries (`--batch`) to `out`.
+///
+/// JSON formats emit one object keyed by query; text formats print a header per query.
+pub fn write_batch(
+    out: &mut dyn Write,
+    groups: &[(String, Vec<SearchResult>)],
+    format: OutputFormat,
+    root: Option<&Path>,
+    context_lines: usize,
+    fields: Option<&[String]>,
+) -> io::Result<()> {
+    if matches!(format, OutputFormat::Json | OutputFormat::NoContent) {
+        let compact = matches!(format, OutputFormat::NoContent);
+        let output: serde_json::Map<String, serde_json::Value> = groups
+            .iter()
+            .map(|(query, results)| {
+                (
+                    query.clone(),
+                    json_value(&relativize(results, root), compact, fields),
+                )
+            })
+            .collect();
+        writeln!(
+            out,
+            "{}",
+            serde_json::to_string_pretty(&output).unwrap_or_default()
+        )?;
+        return Ok(());
+    }
+
+    for (i, (query, results)) in groups.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        writeln!(
+            out,
+            "{}",
+            paint(format!("==> {query} <=="), Style::new().bold())
+        )?;
+        write_results(out, results, format, false, root, context_lines, fields)?;
+    }
+    Ok(())
+}
+
+/// Make result paths relative to `root` for display.
+fn relativize(results: &[SearchResult], root: Option<&Path>) -> Vec<SearchResult> {
+    results
         .iter()
         .map(|r| {
             let mut r = r.clone();
@@ -21,85 +276,491 @@ pub fn print_results(
             }
             r
         })
-        .collect();
-
-    match format {
-        OutputFormat::FilesOnly => print_files_only(&results),
-        OutputFormat::Json => print_json(&results, false),
-        OutputFormat::NoContent => print_json(&results, true),
-        OutputFormat::Default => print_default(&results, show_score, context_lines),
-    }
+        .collect()
 }
 
-fn print_files_only(results: &[SearchResult]) {
+fn write_files_only(out: &mut dyn Write, results: &[SearchResult]) -> io::Result<()> {
     let mut seen = std::collections::HashSet::new();
     for r in results {
         if seen.insert(&r.file) {
-            println!("{}", r.file);
+            writeln!(out, "{}", r.file)?;
         }
     }
+    Ok(())
 }
 
-fn print_json(results: &[SearchResult], compact: bool) {
-    if compact {
-        let output: Vec<serde_json::Value> = results
-            .iter()
-            .map(|r| {
-                let mut v = serde_json::to_value(r).unwrap_or_default();
-                if let Some(obj) = v.as_object_mut() {
-                    obj.remove("content");
-                }
-                v
-            })
-            .collect();
-        println!(
-            "{}",
-            serde_json::to_string_pretty(&output).unwrap_or_default()
-        );
-    } else {
-        println!(
-            "{}",
-            serde_json::to_string_pretty(results).unwrap_or_default()
-        );
+fn write_json(
+    out: &mut dyn Write,
+    results: &[SearchResult],
+    compact: bool,
+    fields: Option<&[String]>,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "{}",
+        serde_json::to_string_pretty(&json_value(results, compact, fields)).unwrap_or_default()
+    )?;
+    Ok(())
+}
+
+fn json_value(
+    results: &[SearchResult],
+    compact: bool,
+    fields: Option<&[String]>,
+) -> serde_json::Value {
+    let mut value = serde_json::to_value(results).unwrap_or_default();
+    if let Some(items) = value.as_array_mut() {
+        for item in items.iter_mut().filter_map(|v| v.as_object_mut()) {
+            if compact {
+                item.remove("content");
+            }
+            if let Some(fields) = fields {
+                item.retain(|key, _| fields.iter().any(|f| f == key));
+            }
+        }
+    }
+    value
+}
+
+fn write_summary(out: &mut dyn Write, results: &[SearchResult]) -> io::Result<()> {
+    for r in results {
+        writeln!(out, "{}", summary_line(r))?;
     }
+    Ok(())
 }
 
-fn print_default(results: &[SearchResult], show_score: bool, context_lines: usize) {
-    use owo_colors::OwoColorize;
+/// `score file:line type name`, uncolored for grep and fzf.
+fn summary_line(r: &SearchResult) -> String {
+    format!(
+        "{:.3} {}:{} {} {}",
+        r.score, r.file, r.line, r.block_type, r.name
+    )
+}
 
+fn write_default(
+    out: &mut dyn Write,
+    results: &[SearchResult],
+    show_score: bool,
+    context_lines: usize,
+) -> io::Result<()> {
     for r in results {
         let line_num = r.line.to_string();
+        let stale = stale_marker(r);
 
         if show_score {
-            println!(
-                "{}:{} {} {} (score: {:.3})",
-                r.file.cyan(),
-                line_num.yellow(),
-                r.block_type.dimmed(),
-                r.name.bold(),
+            writeln!(
+                out,
+                "{}:{} {} {} (score: {:.3}){stale}",
+                paint(&r.file, Style::new().cyan()),
+                paint(line_num, Style::new().yellow()),
+                paint(&r.block_type, Style::new().dimmed()),
+                paint(&r.name, Style::new().bold()),
                 r.score
-            );
+            )?;
         } else {
-            println!(
-                "{}:{} {} {}",
-                r.file.cyan(),
-                line_num.yellow(),
-                r.block_type.dimmed(),
-                r.name.bold()
-            );
-        }
-
-        if context_lines > 0 {
-            if let Some(content) = &r.content {
-                let preview_lines: Vec<&str> = content
-                    .lines()
-                    .filter(|l| !l.trim().is_empty())
-                    .take(context_lines)
-                    .collect();
-                for line in preview_lines {
-                    println!("  {}", line.dimmed());
+            writeln!(
+                out,
+                "{}:{} {} {}{stale}",
+                paint(&r.file, Style::new().cyan()),
+                paint(line_num, Style::new().yellow()),
+                paint(&r.block_type, Style::new().dimmed()),
+                paint(&r.name, Style::new().bold())
+            )?;
+        }
+
+        if context_lines > 0 && r.content.is_some() {
+            write_preview(out, r, context_lines, "  ")?;
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+/// `--group-by-file`: each file once (in rank order of its best result),
+/// then its blocks sorted by line.
+fn write_grouped(
+    out: &mut dyn Write,
+    results: &[SearchResult],
+    show_score: bool,
+    context_lines: usize,
+) -> io::Result<()> {
+    let mut groups: Vec<(&str, Vec<&SearchResult>)> = Vec::new();
+    for r in results {
+        match groups.iter_mut().find(|(file, _)| *file == r.file) {
+            Some((_, group)) => group.push(r),
+            None => groups.push((&r.file, vec![r])),
+        }
+    }
+
+    for (i, (file, mut group)) in groups.into_iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        writeln!(out, "{}", paint(file, Style::new().cyan()))?;
+        group.sort_by_key(|r| r.line);
+
+        for r in group {
+            let score = if show_score {
+                format!(" (score: {:.3})", r.score)
+            } else {
+                String::new()
+            };
+            writeln!(
+                out,
+                "  {} {} {}{score}{}",
+                paint(r.line.to_string(), Style::new().yellow()),
+                paint(&r.block_type, Style::new().dimmed()),
+                paint(&r.name, Style::new().bold()),
+                stale_marker(r)
+            )?;
+            if context_lines > 0 {
+                write_preview(out, r, context_lines, "    ")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Directory in the `--pretty` tree, with the number of results beneath it.
+#[derive(Default)]
+struct DirNode<'a> {
+    dirs: BTreeMap<&'a str, DirNode<'a>>,
+    files: BTreeMap<&'a str, Vec<&'a SearchResult>>,
+    count: usize,
+}
+
+impl<'a> DirNode<'a> {
+    fn build(results: &'a [SearchResult]) -> Self {
+        let mut root = Self::default();
+        for r in results {
+            let mut parts: Vec<&str> = r.file.split('/').filter(|p| !p.is_empty()).collect();
+            let file = parts.pop().unwrap_or(&r.file);
+            let mut node = &mut root;
+            node.count += 1;
+            for part in parts {
+                node = node.dirs.entry(part).or_default();
+                node.count += 1;
+            }
+            node.files.entry(file).or_default().push(r);
+        }
+        root
+    }
+}
+
+/// One line of the `--pretty` tree.
+enum TreeLine<'a> {
+    Dir {
+        depth: usize,
+        name: String,
+        count: usize,
+    },
+    File {
+        depth: usize,
+        name: &'a str,
+        count: usize,
+    },
+    Block {
+        depth: usize,
+        result: &'a SearchResult,
+    },
+}
+
+/// Flatten `node` into lines: directories then files, each busiest first
+/// (ties by name), blocks by line. A chain of directories holding only one
+/// subdirectory collapses into a single `a/b/` line.
+fn tree_lines<'a>(node: &DirNode<'a>, depth: usize, out: &mut Vec<TreeLine<'a>>) {
+    let mut dirs: Vec<_> = node.dirs.iter().collect();
+    dirs.sort_by(|a, b| b.1.count.cmp(&a.1.count));
+    for (name, mut child) in dirs {
+        let mut name = format!("{name}/");
+        while child.files.is_empty() && child.dirs.len() == 1 {
+            let Some((sub, next)) = child.dirs.iter().next() else {
+                break;
+            };
+            name.push_str(sub);
+            name.push('/');
+            child = next;
+        }
+        out.push(TreeLine::Dir {
+            depth,
+            name,
+            count: child.count,
+        });
+        tree_lines(child, depth + 1, out);
+    }
+
+    let mut files: Vec<_> = node.files.iter().collect();
+    files.sort_by(|a, b| b.1.len().cmp(&a.1.len()));
+    for (name, results) in files {
+        out.push(TreeLine::File {
+            depth,
+            name,
+            count: results.len(),
+        });
+        let mut results = results.clone();
+        results.sort_by_key(|r| r.line);
+        out.extend(results.into_iter().map(|result| TreeLine::Block {
+            depth: depth + 1,
+            result,
+        }));
+    }
+}
+
+/// `--pretty`: results as a directory tree, with match counts per directory
+/// and file, to show where matches cluster.
+fn write_tree(
+    out: &mut dyn Write,
+    results: &[SearchResult],
+    show_score: bool,
+    context_lines: usize,
+) -> io::Result<()> {
+    let mut lines = Vec::new();
+    tree_lines(&DirNode::build(results), 0, &mut lines);
+    for line in lines {
+        match line {
+            TreeLine::Dir { depth, name, count } => {
+                writeln!(
+                    out,
+                    "{}{} ({count})",
+                    "  ".repeat(depth),
+                    paint(name, Style::new().blue().bold())
+                )?;
+            }
+            TreeLine::File { depth, name, count } => {
+                writeln!(
+                    out,
+                    "{}{} ({count})",
+                    "  ".repeat(depth),
+                    paint(name, Style::new().cyan())
+                )?;
+            }
+            TreeLine::Block { depth, result: r } => {
+                let score = if show_score {
+                    format!(" (score: {:.3})", r.score)
+                } else {
+                    String::new()
+                };
+                writeln!(
+                    out,
+                    "{}{} {} {}{score}{}",
+                    "  ".repeat(depth),
+                    paint(r.line.to_string(), Style::new().yellow()),
+                    paint(&r.block_type, Style::new().dimmed()),
+                    paint(&r.name, Style::new().bold()),
+                    stale_marker(r)
+                )?;
+                if context_lines > 0 {
+                    write_preview(out, r, context_lines, &"  ".repeat(depth + 1))?;
                 }
-                println!();
+            }
+        }
+    }
+    Ok(())
+}
+
+fn stale_marker(r: &SearchResult) -> String {
+    if r.stale {
+        format!(" {}", paint("(possibly stale)", Style::new().red()))
+    } else {
+        String::new()
+    }
+}
+
+/// First `context_lines` non-blank lines of the block's content.
+fn write_preview(
+    out: &mut dyn Write,
+    r: &SearchResult,
+    context_lines: usize,
+    indent: &str,
+) -> io::Result<()> {
+    let Some(content) = &r.content else {
+        return Ok(());
+    };
+    for line in content
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .take(context_lines)
+    {
+        writeln!(out, "{indent}{}", paint(line, Style::new().dimmed()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(file: &str, line: usize) -> SearchResult {
+        SearchResult {
+            file: file.to_string(),
+            block_type: "function".to_string(),
+            name: "f".to_string(),
+            line,
+            end_line: line + 6,
+            content: Some("fn f() {}".to_string()),
+            score: 0.5,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tree_groups_by_directory() {
+        let results = [
+            result("README.md", 1),
+            result("src/cli/search.rs", 40),
+            result("src/cli/output.rs", 5),
+            result("src/cli/search.rs", 12),
+        ];
+        let mut lines = Vec::new();
+        tree_lines(&DirNode::build(&results), 0, &mut lines);
+        let summary: Vec<String> = lines
+            .iter()
+            .map(|line| match line {
+                TreeLine::Dir { depth, name, count } => format!("{depth} {name} {count}"),
+                TreeLine::File { depth, name, count } => format!("{depth} {name} {count}"),
+                TreeLine::Block { depth, result } => format!("{depth} :{}", result.line),
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                "0 src/cli/ 3",
+                "1 search.rs 2",
+                "2 :12",
+                "2 :40",
+                "1 output.rs 1",
+                "2 :5",
+                "0 README.md 1",
+                "1 :1",
+            ]
+        );
+    }
+
+    #[test]
+    fn json_fields_select_keys() {
+        let result = result("src/a.rs", 3);
+        let fields = ["file".to_string(), "line".to_string(), "score".to_string()];
+        let value = json_value(std::slice::from_ref(&result), false, Some(&fields));
+        assert_eq!(
+            value,
+            serde_json::json!([{ "file": "src/a.rs", "line": 3, "score": 0.5 }])
+        );
+
+        let all = json_value(&[result], true, None);
+        assert!(all[0].get("content").is_none());
+        assert_eq!(all[0]["type"], "function");
+    }
+
+    #[test]
+    fn limit_bytes_cuts_crossing_block_and_drops_rest() {
+        let mut results = vec![result("a.rs", 0), result("b.rs", 0), result("c.rs", 0)];
+        limit_content_bytes(&mut results, 12);
+        let contents: Vec<Option<&str>> = results.iter().map(|r| r.content.as_deref()).collect();
+        assert_eq!(contents, [Some("fn f() {}"), Some("fn "), None]);
+        assert_eq!(results[2].file, "c.rs");
+
+        let mut results = vec![SearchResult {
+            content: Some("é".to_string()),
+            ..result("a.rs", 0)
+        }];
+        limit_content_bytes(&mut results, 1);
+        assert_eq!(results[0].content, None);
+    }
+
+    #[test]
+    fn output_file_is_replaced_without_colors() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("results.txt");
+        std::fs::write(&path, "old").unwrap();
+        let render = |out: &mut dyn Write| {
+            write_results(
+                out,
+                &[result("a.rs", 3)],
+                OutputFormat::Default,
+                false,
+                None,
+                0,
+                None,
+            )
+        };
+        write_file(&path, render).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "a.rs:3 function f\n"
+        );
+        assert!(!tmp.path().join(".results.txt.tmp").exists());
+
+        // A failed render leaves the previous file in place
+        assert!(write_file(&path, |_| Err(io::Error::other("failed"))).is_err());
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "a.rs:3 function f\n"
+        );
+
+        // Escape sequences in the indexed source are kept; colors stay on after
+        let escaped = SearchResult {
+            content: Some("print(\"\x1b[31mred\x1b[0m\")".to_string()),
+            ..result("a.py", 1)
+        };
+        write_file(&path, |out| {
+            write_results(out, &[escaped], OutputFormat::Default, false, None, 1, None)
+        })
+        .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "a.py:1 function f\n  print(\"\x1b[31mred\x1b[0m\")\n\n"
+        );
+        assert!(COLOR.get());
+    }
+
+    #[test]
+    fn summary_is_one_plain_line() {
+        assert_eq!(
+            summary_line(&result("src/a.rs", 12)),
+            "0.500 src/a.rs:12 function f"
+        );
+    }
+
+    #[test]
+    fn schema_matches_serialized_results() {
+        let schema = result_schema();
+        let properties = schema["items"]["properties"].as_object().unwrap();
+        let mut keys: Vec<&str> = properties.keys().map(String::as_str).collect();
+        let mut fields = JSON_FIELDS.to_vec();
+        keys.sort();
+        fields.sort();
+        assert_eq!(keys, fields);
+
+        let full = SearchResult {
+            git: Some(crate::types::GitInfo {
+                author: "alice".to_string(),
+                commit_time: 1_700_000_000,
+            }),
+            capture: Some("function_item".to_string()),
+            language: Some("rust".to_string()),
+            content_hash: Some("abc".to_string()),
+            stale: true,
+            ..result("a.rs", 3)
+        };
+        let value = json_value(&[full, result("b.rs", 0)], false, None);
+        for item in value.as_array().unwrap() {
+            let item = item.as_object().unwrap();
+            for required in schema["items"]["required"].as_array().unwrap() {
+                assert!(item.contains_key(required.as_str().unwrap()));
+            }
+            for (key, v) in item {
+                let expected = properties[key]["type"].as_str().unwrap();
+                let actual = match v {
+                    serde_json::Value::String(_) => "string",
+                    serde_json::Value::Bool(_) => "boolean",
+                    serde_json::Value::Number(n) if n.is_u64() => "integer",
+                    serde_json::Value::Number(_) => "number",
+                    _ => "other",
+                };
+                assert!(
+                    actual == expected || (expected == "number" && actual == "integer"),
+                    "{key}: {actual} vs {expected}"
+                );
             }
         }
     }
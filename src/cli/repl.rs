@@ -0,0 +1,220 @@
+use std::io::{self, BufRead, Write};
+use std::time::Instant;
+
+use anyhow::Result;
+
+use crate::boost::{apply_path_weights, PathWeight, FEEDBACK_BOOST, FEEDBACK_DEMOTE};
+use crate::cli::output::{limit_content_bytes, print_results};
+use crate::cli::search::{self, SearchParams};
+use crate::types::{OutputFormat, EXIT_ERROR};
+
+const HELP: &str = "Enter a query to search. Commands:
+  :n N            set the number of results
+  :boost PREFIX   rank files under PREFIX higher for this session
+  :demote PREFIX  rank files under PREFIX lower for this session
+  :reset          clear boosts and demotions
+  :help           show this help
+  :q              quit (or Ctrl-D)";
+
+/// A line typed at the prompt.
+#[derive(Debug, PartialEq)]
+enum Input<'a> {
+    Query(&'a str),
+    Count(usize),
+    Weight(PathWeight),
+    Reset,
+    Help,
+    Quit,
+    Invalid(String),
+    Empty,
+}
+
+fn parse_input(line: &str) -> Input<'_> {
+    let line = line.trim();
+    let Some(command) = line.strip_prefix(':') else {
+        return if line.is_empty() {
+            Input::Empty
+        } else {
+            Input::Query(line)
+        };
+    };
+
+    let mut parts = command.split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some("n"), Some(n)) => match n.parse() {
+            Ok(n) if n > 0 => Input::Count(n),
+            _ => Input::Invalid(format!("Invalid result count: {n}")),
+        },
+        (Some(cmd @ ("boost" | "demote")), Some(prefix)) => Input::Weight(PathWeight {
+            prefix: prefix.trim_start_matches("./").to_string(),
+            weight: if cmd == "boost" {
+                FEEDBACK_BOOST
+            } else {
+                FEEDBACK_DEMOTE
+            },
+        }),
+        (Some("reset"), None) => Input::Reset,
+        (Some("help" | "h" | "?"), None) => Input::Help,
+        (Some("q" | "quit" | "exit"), None) => Input::Quit,
+        _ => Input::Invalid(format!("Unknown command: {line} (:help for commands)")),
+    }
+}
+
+/// `og --repl`: open the index, store and model once, then search each query
+/// read from stdin until EOF. Results go to stdout, the prompt to stderr.
+pub fn run(params: &SearchParams) -> Result<()> {
+    let path = params
+        .path
+        .canonicalize()
+        .unwrap_or_else(|_| params.path.to_path_buf());
+    if !path.exists() {
+        eprintln!("Path does not exist: {}", path.display());
+        std::process::exit(EXIT_ERROR);
+    }
+
+    if params.rebuild_if_stale {
+        search::rebuild_if_stale(&path, params.quiet)?;
+    }
+    let mut index = search::open_index(&path, params.quiet, params.no_index, None)?;
+    let owners = search::load_owners(&index, params.owner)?;
+    search::check_git_filters(&index, params)?;
+    search::configure_index(&mut index, &path, params)?;
+    let session = index.open_session()?;
+
+    if !params.quiet {
+        eprintln!("{HELP}");
+    }
+
+    let mut num_results = params.num_results;
+    let mut weights: Vec<PathWeight> = Vec::new();
+    let stdin = io::stdin();
+    let mut line = String::new();
+    loop {
+        eprint!("og> ");
+        io::stderr().flush()?;
+        line.clear();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            eprintln!();
+            break;
+        }
+
+        let query = match parse_input(&line) {
+            Input::Query(query) => query,
+            Input::Count(n) => {
+                num_results = n;
+                eprintln!("Showing {n} results");
+                continue;
+            }
+            Input::Weight(weight) => {
+                let verb = if weight.weight > 1.0 {
+                    "Boosting"
+                } else {
+                    "Demoting"
+                };
+                eprintln!("{verb} {}", weight.prefix);
+                // A later mark on the same prefix replaces the earlier one
+                weights.retain(|w| w.prefix != weight.prefix);
+                weights.push(weight);
+                continue;
+            }
+            Input::Reset => {
+                weights.clear();
+                eprintln!("Cleared boosts and demotions");
+                continue;
+            }
+            Input::Help => {
+                eprintln!("{HELP}");
+                continue;
+            }
+            Input::Quit => break,
+            Input::Invalid(message) => {
+                eprintln!("{message}");
+                continue;
+            }
+            Input::Empty => continue,
+        };
+
+        let params = SearchParams {
+            num_results,
+            ..*params
+        };
+        // Each query gets the full --timeout budget
+        index.set_deadline(search::deadline(params.timeout));
+        search::query_parts(&index, query, None)?;
+
+        // Over-fetch so boosted files beyond the top N can move up
+        let k = if weights.is_empty() {
+            search::fetch_k(&params, owners.as_ref())
+        } else {
+            num_results.saturating_mul(search::POST_FILTER_OVERFETCH)
+        };
+
+        let t0 = Instant::now();
+        let results = match search::expand_references(&index, query)
+            .and_then(|expanded| index.search_session(&session, &expanded, k))
+        {
+            Ok(results) => results,
+            Err(e) => {
+                eprintln!("Error: {e:#}");
+                continue;
+            }
+        };
+        let search_time = t0.elapsed();
+
+        let mut results = search::apply_filters(results, query, &params);
+        apply_path_weights(&mut results, &path, &weights);
+        search::filter_post(&mut results, owners.as_ref(), &params);
+        results.truncate(num_results);
+        if let Some(limit) = params.limit_bytes {
+            limit_content_bytes(&mut results, limit);
+        }
+        if results.is_empty() {
+            eprintln!("No results found");
+            continue;
+        }
+
+        print_results(
+            &results,
+            params.format,
+            false,
+            Some(&path),
+            params.context_lines,
+            params.fields,
+        )?;
+        io::stdout().flush()?;
+        if !params.quiet && !matches!(params.format, OutputFormat::Json | OutputFormat::FilesOnly) {
+            eprintln!(
+                "{} results ({:.2}s)",
+                results.len(),
+                search_time.as_secs_f64()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_queries_and_commands() {
+        assert_eq!(parse_input("  auth flow \n"), Input::Query("auth flow"));
+        assert_eq!(parse_input(":n 20"), Input::Count(20));
+        assert_eq!(parse_input(":q"), Input::Quit);
+        assert_eq!(parse_input(":help"), Input::Help);
+        assert_eq!(parse_input("   "), Input::Empty);
+        assert!(matches!(parse_input(":n 0"), Input::Invalid(_)));
+        assert!(matches!(parse_input(":boost"), Input::Invalid(_)));
+        assert!(matches!(parse_input(":frob x"), Input::Invalid(_)));
+        assert_eq!(
+            parse_input(":demote ./tests/"),
+            Input::Weight(PathWeight {
+                prefix: "tests/".to_string(),
+                weight: FEEDBACK_DEMOTE,
+            })
+        );
+        assert_eq!(parse_input(":reset"), Input::Reset);
+    }
+}
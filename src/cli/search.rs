@@ -1,47 +1,388 @@
-use std::path::Path;
-use std::time::Instant;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 
-use crate::boost::boost_results;
-use crate::cli::output::print_results;
+use crate::anchor;
+use crate::boost::{
+    apply_near_boost, apply_path_weights, apply_type_weights, boost_results, NearAnchor,
+    PathWeight, TypeWeight,
+};
+use crate::cli::output::{
+    limit_content_bytes, print_batch, print_results, write_batch, write_file, write_results,
+};
+use crate::cli::trace;
+use crate::codeowners::CodeOwners;
+use crate::embedder::{self, Precision};
+use crate::index::manifest::Manifest;
 use crate::index::{self, walker, SemanticIndex};
-use crate::types::{FileRef, OutputFormat, EXIT_ERROR, EXIT_MATCH, EXIT_NO_MATCH};
+use crate::testfiles::is_test_path;
+use crate::types::{
+    DocFilter, FileRef, IndexOptions, OutputFormat, SearchMode, SearchResult, TestFilter,
+    UniqueNames, EXIT_ERROR, EXIT_MATCH, EXIT_NO_MATCH,
+};
 
 pub struct SearchParams<'a> {
     pub query: Option<&'a str>,
     pub path: &'a Path,
     pub num_results: usize,
     pub threshold: f32,
+    /// Keep results at or above this score percentile of the set (`--min-score-percentile`).
+    pub min_score_percentile: Option<f32>,
     pub format: OutputFormat,
     pub quiet: bool,
     pub file_types: Option<&'a str>,
     pub exclude: &'a [String],
     pub code_only: bool,
     pub no_index: bool,
+    /// Rebuild an index from another og version or model (`--rebuild-if-stale`).
+    pub rebuild_if_stale: bool,
     pub context_lines: usize,
+    /// Cap on total content bytes across results (`--limit-bytes`).
+    pub limit_bytes: Option<usize>,
     pub regex: Option<&'a str>,
+    pub mode: SearchMode,
+    /// File with one query per line; searched in one session.
+    pub batch: Option<&'a Path>,
+    /// Explicit block-type multipliers (`--prefer-type`).
+    pub prefer_types: &'a [TypeWeight],
+    /// Path-prefix multipliers relative to `path` (`--path-boost`).
+    pub path_boosts: &'a [PathWeight],
+    /// Boost results by proximity to this location (`--near`).
+    pub near: Option<&'a NearAnchor>,
+    /// Print only the number of results.
+    pub count: bool,
+    /// Keep only files owned by this CODEOWNERS owner.
+    pub owner: Option<&'a str>,
+    /// Print semantic results first, then append the rest after BM25 merges.
+    pub stream: bool,
+    /// Semantic weight for normalized BM25/semantic fusion (`--alpha`).
+    pub alpha: Option<f32>,
+    /// Keep blocks whose dominant git author matches (`--author`).
+    pub author: Option<&'a str>,
+    /// Keep blocks last committed at or after this unix time (`--after`).
+    pub after: Option<u64>,
+    /// Re-anchor line numbers against the files on disk (`--verify`).
+    pub verify: bool,
+    /// Replace result content with the current file text (`--context-lines-from-disk`).
+    pub context_lines_from_disk: bool,
+    /// Overall time limit for auto-update and search (`--timeout`, `OG_TIMEOUT_MS`).
+    pub timeout: Option<Duration>,
+    /// Split queries longer than this many tokens into merged sub-queries.
+    pub max_tokens: Option<usize>,
+    /// Keep blocks with this raw tree-sitter node kind (`--capture`).
+    pub capture: Option<&'a str>,
+    /// Keep blocks in these languages (`--lang`).
+    pub langs: &'a [String],
+    /// Doc blocks in similar-code search for file references.
+    pub doc_filter: DocFilter,
+    /// Keep one result per normalized-content hash (`--dedup`).
+    pub dedup: bool,
+    /// Keep the best result per block name (`--unique-names`).
+    pub unique_names: UniqueNames,
+    /// Cap on results per block type (`--max-per-type`).
+    pub max_per_type: Option<usize>,
+    /// Merge nearby results from the same file into one (`--group-adjacent`).
+    pub group_adjacent: bool,
+    /// Print extra diagnostics to stderr (`--verbose`).
+    pub verbose: bool,
+    /// On zero results, print why (`--explain-empty`).
+    pub explain_empty: bool,
+    /// Blend this file's top block into query embeddings (`--context-file`).
+    pub context_file: Option<&'a Path>,
+    /// Keep only results also similar to this file's blocks (`--and-file`).
+    pub and_file: Option<&'a Path>,
+    /// Write the full scoring trace to this file (`--trace-json`).
+    pub trace_json: Option<&'a Path>,
+    /// Write results to this file instead of stdout (`--output`).
+    pub output: Option<&'a Path>,
+    /// Skip or keep only test files (`--no-tests`, `--tests-only`).
+    pub tests: TestFilter,
+    /// Keep files whose recorded mtime is at or after this unix time (`--changed-since`).
+    pub changed_since: Option<u64>,
+    /// Keys kept in JSON results (`--fields`); None keeps all.
+    pub fields: Option<&'a [String]>,
+    /// Re-rank by similarity to each block's signature (`--signatures`).
+    pub signatures: bool,
+    /// Drop stopwords from the BM25 query (`--strip-stopwords`).
+    pub strip_stopwords: bool,
+    /// Require `.og/acronyms.txt` for acronym expansion (`--expand-acronyms`).
+    pub expand_acronyms: bool,
 }
 
+impl<'a> SearchParams<'a> {
+    /// A search for `query` under `path` with every option at its CLI default.
+    pub fn new(query: Option<&'a str>, path: &'a Path) -> Self {
+        Self {
+            query,
+            path,
+            num_results: 10,
+            threshold: 0.0,
+            min_score_percentile: None,
+            format: OutputFormat::Default,
+            quiet: false,
+            file_types: None,
+            exclude: &[],
+            code_only: false,
+            no_index: false,
+            rebuild_if_stale: false,
+            context_lines: 0,
+            limit_bytes: None,
+            regex: None,
+            mode: SearchMode::default(),
+            batch: None,
+            prefer_types: &[],
+            path_boosts: &[],
+            near: None,
+            count: false,
+            owner: None,
+            stream: false,
+            alpha: None,
+            author: None,
+            after: None,
+            verify: false,
+            context_lines_from_disk: false,
+            timeout: None,
+            max_tokens: None,
+            capture: None,
+            langs: &[],
+            doc_filter: DocFilter::default(),
+            dedup: false,
+            unique_names: UniqueNames::default(),
+            max_per_type: None,
+            group_adjacent: false,
+            verbose: false,
+            explain_empty: false,
+            context_file: None,
+            and_file: None,
+            trace_json: None,
+            output: None,
+            tests: TestFilter::default(),
+            changed_since: None,
+            fields: None,
+            signatures: false,
+            strip_stopwords: false,
+            expand_acronyms: false,
+        }
+    }
+}
+
+/// Over-fetch factor when post-filters (`--owner`, `--author`, `--capture`, ...) will discard results.
+pub(crate) const POST_FILTER_OVERFETCH: usize = 5;
+
 pub fn run(params: &SearchParams) -> Result<()> {
+    if let Some(batch) = params.batch {
+        return run_batch(params, batch);
+    }
+
     let query = match params.query {
         Some(q) => q,
         None => {
             bail!("No query provided. Run 'og --help' for usage.");
         }
     };
+    index::check_query(query)?;
 
     // Check if query is a file reference
     if let Some(file_ref) = parse_file_reference(query) {
-        return run_similar_search(
-            file_ref,
-            params.num_results,
+        return run_similar_search(file_ref, params);
+    }
+
+    let path = params
+        .path
+        .canonicalize()
+        .unwrap_or_else(|_| params.path.to_path_buf());
+    if !path.exists() {
+        eprintln!("Path does not exist: {}", path.display());
+        std::process::exit(EXIT_ERROR);
+    }
+
+    if params.rebuild_if_stale {
+        rebuild_if_stale(&path, params.quiet)?;
+    }
+    let mut index = open_index(
+        &path,
+        params.quiet,
+        params.no_index,
+        deadline(params.timeout),
+    )?;
+    let owners = load_owners(&index, params.owner)?;
+    check_git_filters(&index, params)?;
+    configure_index(&mut index, &path, params)?;
+    // Boosts and filters see the query as typed, retrieval the expanded one
+    let expanded = expand_references(&index, query)?;
+
+    let parts = query_parts(&index, &expanded, params.max_tokens)?;
+
+    if params.stream {
+        return run_stream(&index, &expanded, &path, params, owners.as_ref());
+    }
+
+    // Run search
+    if !params.quiet {
+        eprint!("Searching...");
+    }
+    let t0 = Instant::now();
+    let k = fetch_k(params, owners.as_ref());
+    let mut results = if parts.len() > 1 {
+        merge_results(index.search_batch(&parts, k)?, k)
+    } else {
+        index.search(&expanded, k)?
+    };
+    let search_time = t0.elapsed();
+    if !params.quiet {
+        eprintln!("\r              \r");
+    }
+
+    if results.is_empty() && !params.count && !params.explain_empty {
+        trace::write(&index, query, &expanded, k, params, &path, &results)?;
+        if !matches!(params.format, OutputFormat::Json) {
+            eprintln!("No results found");
+        }
+        std::process::exit(EXIT_NO_MATCH);
+    }
+
+    results = apply_filters(results, query, params);
+    filter_post(&mut results, owners.as_ref(), params);
+
+    trace::write(&index, query, &expanded, k, params, &path, &results)?;
+    if let Some(limit) = params.limit_bytes {
+        limit_content_bytes(&mut results, limit);
+    }
+
+    if results.is_empty() && params.explain_empty {
+        explain_empty(&index, query, k, params, owners.as_ref())?;
+        if !params.count {
+            std::process::exit(EXIT_NO_MATCH);
+        }
+    }
+
+    if params.count {
+        println!("{}", results.len());
+        std::process::exit(if results.is_empty() {
+            EXIT_NO_MATCH
+        } else {
+            EXIT_MATCH
+        });
+    }
+
+    match params.output {
+        Some(file) => write_file(file, |out| {
+            write_results(
+                out,
+                &results,
+                params.format,
+                false,
+                Some(&path),
+                params.context_lines,
+                params.fields,
+            )
+        })?,
+        None => print_results(
+            &results,
             params.format,
-            params.quiet,
+            false,
+            Some(&path),
             params.context_lines,
+            params.fields,
+        )?,
+    }
+
+    if !params.quiet && !matches!(params.format, OutputFormat::Json | OutputFormat::FilesOnly) {
+        let result_word = if results.len() == 1 {
+            "result"
+        } else {
+            "results"
+        };
+        eprintln!(
+            "{} {} ({:.2}s)",
+            results.len(),
+            result_word,
+            search_time.as_secs_f64()
         );
     }
 
+    std::process::exit(if results.is_empty() {
+        EXIT_NO_MATCH
+    } else {
+        EXIT_MATCH
+    });
+}
+
+/// Candidate scores shown by `--explain-empty`.
+const EXPLAIN_TOP: usize = 5;
+
+/// Print why a search came back empty (`--explain-empty`): index size, what
+/// each retrieval path returned, how many candidates the scope and filters
+/// dropped, the best raw scores, and the BM25 terms.
+fn explain_empty(
+    index: &SemanticIndex,
+    query: &str,
+    k: usize,
+    params: &SearchParams,
+    owners: Option<&CodeOwners>,
+) -> Result<()> {
+    let d = index.diagnose(query, k)?;
+    eprintln!("No results found. Diagnostics:");
+    eprintln!("  index       {} blocks", d.blocks);
+    if d.blocks == 0 {
+        eprintln!("              the index is empty; run 'og build'");
+        return Ok(());
+    }
+    eprintln!(
+        "  candidates  {} semantic, {} BM25, {} merged",
+        d.semantic, d.bm25, d.merged
+    );
+    if let Some(scope) = &d.scope {
+        eprintln!(
+            "  scope       {} of {} candidates outside {scope}/",
+            d.out_of_scope, d.merged
+        );
+    }
+
+    let mut kept = apply_filters(d.top.clone(), query, params);
+    filter_post(&mut kept, owners, params);
+    eprintln!(
+        "  filters     {} of {} in-scope candidates removed (type/exclude/threshold/regex/post filters)",
+        d.top.len() - kept.len(),
+        d.top.len()
+    );
+    if params.threshold != 0.0 {
+        eprintln!("  threshold   {}", params.threshold);
+    }
+
+    for (i, r) in d.top.iter().take(EXPLAIN_TOP).enumerate() {
+        let label = if i == 0 { "top scores" } else { "" };
+        let file = Path::new(&r.file);
+        let rel = file.strip_prefix(index.root()).unwrap_or(file);
+        eprintln!(
+            "  {label:<10}  {:.4}  {}:{} {}",
+            r.score,
+            rel.display(),
+            r.line,
+            r.name
+        );
+    }
+    eprintln!("  bm25 terms  {}", d.bm25_terms.join(" "));
+    Ok(())
+}
+
+/// Search every query in `batch_file` with a single index and embedder.
+fn run_batch(params: &SearchParams, batch_file: &Path) -> Result<()> {
+    let text = std::fs::read_to_string(batch_file)
+        .with_context(|| format!("Failed to read {}", batch_file.display()))?;
+    let queries: Vec<String> = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect();
+    if queries.is_empty() {
+        bail!("No queries in {}", batch_file.display());
+    }
+
     let path = params
         .path
         .canonicalize()
@@ -51,8 +392,123 @@ pub fn run(params: &SearchParams) -> Result<()> {
         std::process::exit(EXIT_ERROR);
     }
 
+    if params.rebuild_if_stale {
+        rebuild_if_stale(&path, params.quiet)?;
+    }
+    let mut index = open_index(
+        &path,
+        params.quiet,
+        params.no_index,
+        deadline(params.timeout),
+    )?;
+    for query in &queries {
+        query_parts(&index, query, None)?;
+    }
+
+    if !params.quiet {
+        eprint!("Searching {} queries...", queries.len());
+    }
+    let owners = load_owners(&index, params.owner)?;
+    check_git_filters(&index, params)?;
+
+    let t0 = Instant::now();
+    configure_index(&mut index, &path, params)?;
+    let expanded = queries
+        .iter()
+        .map(|query| expand_references(&index, query))
+        .collect::<Result<Vec<_>>>()?;
+    let batches = index.search_batch(&expanded, fetch_k(params, owners.as_ref()))?;
+    let search_time = t0.elapsed();
+    if !params.quiet {
+        eprintln!("\r                              \r");
+    }
+
+    let groups: Vec<(String, Vec<SearchResult>)> = queries
+        .into_iter()
+        .zip(batches)
+        .map(|(query, results)| {
+            let mut results = apply_filters(results, &query, params);
+            filter_post(&mut results, owners.as_ref(), params);
+            if let Some(limit) = params.limit_bytes {
+                limit_content_bytes(&mut results, limit);
+            }
+            (query, results)
+        })
+        .collect();
+
+    match params.output {
+        Some(file) => write_file(file, |out| {
+            write_batch(
+                out,
+                &groups,
+                params.format,
+                Some(&path),
+                params.context_lines,
+                params.fields,
+            )
+        })?,
+        None => print_batch(
+            &groups,
+            params.format,
+            Some(&path),
+            params.context_lines,
+            params.fields,
+        )?,
+    }
+
+    let total: usize = groups.iter().map(|(_, r)| r.len()).sum();
+    if !params.quiet && !matches!(params.format, OutputFormat::Json | OutputFormat::NoContent) {
+        eprintln!(
+            "{} queries, {} results ({:.2}s)",
+            groups.len(),
+            total,
+            search_time.as_secs_f64()
+        );
+    }
+
+    std::process::exit(if total == 0 {
+        EXIT_NO_MATCH
+    } else {
+        EXIT_MATCH
+    });
+}
+
+/// Apply the search options that live on the index: scope, retrieval mode
+/// and fusion, query context, and the file-level filters.
+pub(crate) fn configure_index(
+    index: &mut SemanticIndex,
+    path: &Path,
+    params: &SearchParams,
+) -> Result<()> {
+    index.set_search_scope(Some(path));
+    index.set_search_mode(params.mode);
+    index.set_alpha(params.alpha);
+    if let Some(file) = params.context_file {
+        index.set_context_file(file)?;
+    }
+    index.set_changed_since(params.changed_since)?;
+    index.set_signatures(params.signatures);
+    index.set_strip_stopwords(params.strip_stopwords);
+    index.set_expand_acronyms(params.expand_acronyms)?;
+    index.set_and_file(params.and_file)
+}
+
+/// Deadline for an operation starting now.
+pub(crate) fn deadline(timeout: Option<Duration>) -> Option<Instant> {
+    timeout.map(|t| Instant::now() + t)
+}
+
+/// Find the index covering `path` (auto-building it when `OG_AUTO_BUILD` is set)
+/// and bring stale files up to date unless `no_index` is set.
+/// Exits with `EXIT_ERROR` when no index exists.
+pub(crate) fn open_index(
+    path: &Path,
+    quiet: bool,
+    no_index: bool,
+    deadline: Option<Instant>,
+) -> Result<SemanticIndex> {
     // Walk up to find existing index
-    let (index_root, existing_index) = index::find_index_root(&path);
+    let (index_root, existing_index) = index::find_index_root(path);
 
     if existing_index.is_none() {
         // Check for auto-build
@@ -60,10 +516,17 @@ pub fn run(params: &SearchParams) -> Result<()> {
             .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
             .unwrap_or(false)
         {
-            if !params.quiet {
+            if !quiet {
                 eprintln!("Building index (OG_AUTO_BUILD=1)...");
             }
-            super::build::build_index(&path, params.quiet)?;
+            // index_root is the workspace base when an og.workspace applies
+            super::build::build_index(
+                &index_root,
+                quiet,
+                false,
+                &IndexOptions::default(),
+                Precision::default(),
+            )?;
         } else {
             eprintln!("No index found. Run 'og build' first.");
             eprintln!("Tip: Set OG_AUTO_BUILD=1 for auto-indexing");
@@ -71,24 +534,21 @@ pub fn run(params: &SearchParams) -> Result<()> {
         }
     }
 
-    let index_root = if existing_index.is_some() {
-        index_root
-    } else {
-        path.clone()
-    };
-
+    warn_if_nested(path, quiet);
     let mut index = SemanticIndex::new(&index_root, None)?;
+    index.set_deadline(deadline);
 
-    if !params.no_index {
+    if !no_index {
         // Auto-update stale files using metadata-only scan (no content reads)
-        if !params.quiet && index_root != path {
+        if !quiet && index_root != path {
             eprintln!("Using index at {}", index_root.display());
         }
 
-        let metadata = walker::scan_metadata(&index_root)?;
+        let metadata = walker::scan_metadata(&index_root, &index.options().unwrap_or_default())?;
+        warn_if_diverged(&index, Some(metadata.len()), quiet);
         let (stale_count, stats) = index.check_and_update(&metadata)?;
 
-        if stale_count > 0 && !params.quiet {
+        if stale_count > 0 && !quiet {
             if let Some(stats) = &stats {
                 if stats.blocks > 0 {
                     eprintln!(
@@ -100,42 +560,168 @@ pub fn run(params: &SearchParams) -> Result<()> {
                 }
             }
         }
+    } else {
+        warn_if_diverged(&index, None, quiet);
     }
 
-    // Run search
-    if !params.quiet {
-        eprint!("Searching...");
+    Ok(index)
+}
+
+/// Splice the content of `@file#name`, `@file:line` and `@file` references
+/// into a query (`"similar to @src/auth.rs#login"`), so retrieval embeds the
+/// referenced code along with the text. Other words are kept as-is.
+pub(crate) fn expand_references(index: &SemanticIndex, query: &str) -> Result<String> {
+    if !query.contains('@') {
+        return Ok(query.to_string());
     }
-    let t0 = Instant::now();
-    index.set_search_scope(Some(&path));
-    let mut results = index.search(query, params.num_results)?;
-    let search_time = t0.elapsed();
-    if !params.quiet {
-        eprintln!("\r              \r");
+    let mut words = Vec::new();
+    for word in query.split_whitespace() {
+        let file_ref = word
+            .strip_prefix('@')
+            .map(|r| r.trim_end_matches([',', ';', '?', '!', ')']))
+            .and_then(parse_file_reference);
+        match file_ref {
+            Some(file_ref) => words.push(
+                index
+                    .reference_content(&file_ref)
+                    .with_context(|| format!("Could not resolve {word}"))?,
+            ),
+            None => words.push(word.to_string()),
+        }
     }
+    Ok(words.join(" "))
+}
 
-    if results.is_empty() {
-        if !matches!(params.format, OutputFormat::Json) {
-            eprintln!("No results found");
-        }
-        std::process::exit(EXIT_NO_MATCH);
+/// `--rebuild-if-stale`: when the index covering `path` was built by another
+/// og version or embedding model, clear it and rebuild with default options
+/// instead of failing with "run og build --force".
+pub(crate) fn rebuild_if_stale(path: &Path, quiet: bool) -> Result<()> {
+    let (index_root, Some(index_dir)) = index::find_index_root(path) else {
+        return Ok(());
+    };
+    let Ok(info) = Manifest::info(&index_dir) else {
+        return Ok(());
+    };
+    if !info.needs_rebuild() {
+        return Ok(());
+    }
+
+    if !quiet {
+        eprintln!("Rebuilding index (built by another og version or model)...");
+    }
+    crate::index::clear_index_dir(&index_dir)?;
+    super::build::build_index(
+        &index_root,
+        quiet,
+        false,
+        &IndexOptions::default(),
+        info.precision().unwrap_or_default(),
+    )
+}
+
+/// Warn when several indexes lie along the path to the root: the nearest one
+/// is used, so a stale index in a subdirectory silently shadows its parent.
+fn warn_if_nested(path: &Path, quiet: bool) {
+    if quiet {
+        return;
+    }
+    if let Some(warning) = nested_indexes_warning(&index::find_index_roots(path)) {
+        eprintln!("Warning: {warning}");
+    }
+}
+
+/// Warning listing nested index roots (nearest first); None for fewer than two.
+fn nested_indexes_warning(roots: &[PathBuf]) -> Option<String> {
+    let [nearest, .., outermost] = roots else {
+        return None;
+    };
+    let mut message = format!(
+        "{} indexes along this path; searching the nearest, {}:",
+        roots.len(),
+        nearest.display()
+    );
+    for root in roots {
+        message.push_str(&format!("\n  {}", root.join(index::INDEX_DIR).display()));
     }
+    message.push_str(&format!(
+        "\nDelete the stale one with 'og clean <dir>' ('og clean -r {}' deletes all of them).",
+        outermost.display()
+    ));
+    Some(message)
+}
+
+/// Warn when the index seems to belong to a different tree (see `fingerprint_warning`).
+fn warn_if_diverged(index: &SemanticIndex, disk_files: Option<usize>, quiet: bool) {
+    if quiet {
+        return;
+    }
+    if let Ok(Some(warning)) = index.fingerprint_warning(disk_files) {
+        eprintln!("Warning: {warning}");
+    }
+}
 
-    // Filter results
-    results = filter_results(results, params.file_types, params.exclude, params.code_only);
+/// Apply type/exclude filters, code-aware boosts, type, path and `--near` weights, threshold, score
+/// percentile, and regex filter, then re-anchor line numbers when `--verify` is set and
+/// reload content with `--context-lines-from-disk`.
+pub(crate) fn apply_filters(
+    results: Vec<SearchResult>,
+    query: &str,
+    params: &SearchParams,
+) -> Vec<SearchResult> {
+    let mut results = filter_results(results, params.file_types, params.exclude, params.code_only);
     boost_results(&mut results, query);
+    apply_type_weights(&mut results, params.prefer_types);
+    if !params.path_boosts.is_empty() || params.tests != TestFilter::All {
+        let root = params
+            .path
+            .canonicalize()
+            .unwrap_or_else(|_| params.path.to_path_buf());
+        let root = if root.is_file() {
+            root.parent().unwrap_or(&root).to_path_buf()
+        } else {
+            root
+        };
+        apply_path_weights(&mut results, &root, params.path_boosts);
+        results.retain(|r| {
+            let file = Path::new(&r.file);
+            let rel = file.strip_prefix(&root).unwrap_or(file);
+            params.tests.keeps(is_test_path(rel))
+        });
+    }
+    if let Some(anchor) = params.near {
+        apply_near_boost(&mut results, anchor);
+    }
+
+    if params.dedup {
+        let collapsed = dedup_results(&mut results);
+        if params.verbose && collapsed > 0 {
+            let word = if collapsed == 1 { "result" } else { "results" };
+            eprintln!("Collapsed {collapsed} duplicate {word}");
+        }
+    }
+    if params.unique_names != UniqueNames::Off {
+        unique_names(&mut results, params.unique_names);
+    }
+    if let Some(max) = params.max_per_type {
+        cap_per_type(&mut results, max);
+    }
 
     // Filter by threshold
     if params.threshold != 0.0 {
         results.retain(|r| r.score >= params.threshold);
     }
 
+    // Relative threshold over the boosted scores, before truncation to -n
+    if let Some(percentile) = params.min_score_percentile {
+        retain_percentile(&mut results, percentile);
+    }
+
     // Regex filter
     if let Some(pattern) = params.regex {
         match regex::Regex::new(pattern) {
             Ok(re) => {
                 results.retain(|r| {
-                    r.content.as_deref().map_or(false, |c| re.is_match(c)) || re.is_match(&r.name)
+                    r.content.as_deref().is_some_and(|c| re.is_match(c)) || re.is_match(&r.name)
                 });
             }
             Err(e) => {
@@ -145,42 +731,371 @@ pub fn run(params: &SearchParams) -> Result<()> {
         }
     }
 
+    if params.verify {
+        anchor::verify(&mut results);
+    }
+    if params.context_lines_from_disk {
+        anchor::reload_content(&mut results);
+    }
+
+    if params.group_adjacent {
+        results = group_adjacent(results);
+    }
+
+    results
+}
+
+/// Lines allowed between two blocks merged by `--group-adjacent`.
+const ADJACENT_GAP: usize = 3;
+
+/// Merge results from the same file whose line ranges overlap or lie within
+/// `ADJACENT_GAP` lines of each other into one result spanning the region:
+/// contents joined in line order, names joined, the best block's score, type
+/// and ID. Results are returned sorted by score.
+fn group_adjacent(results: Vec<SearchResult>) -> Vec<SearchResult> {
+    let mut by_file: Vec<(String, Vec<SearchResult>)> = Vec::new();
+    for r in results {
+        match by_file.iter_mut().find(|(file, _)| *file == r.file) {
+            Some((_, group)) => group.push(r),
+            None => by_file.push((r.file.clone(), vec![r])),
+        }
+    }
+
+    let mut grouped = Vec::new();
+    for (_, mut group) in by_file {
+        group.sort_by_key(|r| r.line);
+        let mut region: Vec<SearchResult> = Vec::new();
+        for r in group {
+            let end = region.iter().map(|b| b.end_line).max();
+            if end.is_some_and(|end| r.line > end + ADJACENT_GAP) {
+                grouped.push(merge_region(std::mem::take(&mut region)));
+            }
+            region.push(r);
+        }
+        grouped.push(merge_region(region));
+    }
+    grouped.sort_by(|a, b| b.score.total_cmp(&a.score));
+    grouped
+}
+
+/// One result for blocks sorted by line, based on the best-scored of them.
+fn merge_region(region: Vec<SearchResult>) -> SearchResult {
+    if region.len() == 1 {
+        return region.into_iter().next().unwrap();
+    }
+    let best = region
+        .iter()
+        .max_by(|a, b| a.score.total_cmp(&b.score))
+        .unwrap();
+    let mut names: Vec<&str> = Vec::new();
+    for r in &region {
+        if !names.contains(&r.name.as_str()) {
+            names.push(&r.name);
+        }
+    }
+    let contents: Vec<&str> = region.iter().filter_map(|r| r.content.as_deref()).collect();
+    SearchResult {
+        name: names.join(", "),
+        line: region[0].line,
+        end_line: region
+            .iter()
+            .map(|r| r.end_line)
+            .max()
+            .unwrap_or(best.end_line),
+        content: (!contents.is_empty()).then(|| contents.join("\n")),
+        content_hash: None,
+        stale: region.iter().any(|r| r.stale),
+        ..best.clone()
+    }
+}
+
+/// Keep the highest-scored result per block name, or per (file, name) with
+/// `PerFile`. Expects results sorted by score.
+fn unique_names(results: &mut Vec<SearchResult>, scope: UniqueNames) {
+    let mut seen = HashSet::new();
+    results.retain(|r| {
+        let file = match scope {
+            UniqueNames::PerFile => r.file.as_str(),
+            _ => "",
+        };
+        seen.insert((file.to_string(), r.name.clone()))
+    });
+}
+
+/// Keep the `max` highest-scored results of each block type, so one type
+/// can't crowd out the rest. Expects results sorted by score.
+fn cap_per_type(results: &mut Vec<SearchResult>, max: usize) {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    results.retain(|r| {
+        let count = counts.entry(r.block_type.clone()).or_default();
+        *count += 1;
+        *count <= max
+    });
+}
+
+/// Keep results scoring at or above the nearest-rank `percentile` (0-100) of
+/// the set's scores. Ties at the cutoff are kept, so at least one result remains.
+fn retain_percentile(results: &mut Vec<SearchResult>, percentile: f32) {
+    if results.is_empty() {
+        return;
+    }
+    let mut scores: Vec<f32> = results.iter().map(|r| r.score).collect();
+    scores.sort_by(f32::total_cmp);
+    let rank = (percentile / 100.0 * scores.len() as f32).ceil() as usize;
+    let cutoff = scores[rank.clamp(1, scores.len()) - 1];
+    results.retain(|r| r.score >= cutoff);
+}
+
+/// Keep the highest-scored result of each group with identical content after
+/// whitespace is collapsed (cross-file clones). Expects results sorted by score.
+/// Returns how many results were dropped.
+fn dedup_results(results: &mut Vec<SearchResult>) -> usize {
+    let before = results.len();
+    let mut seen = HashSet::new();
+    results.retain(|r| match r.content.as_deref() {
+        Some(content) => {
+            let normalized = content.split_whitespace().collect::<Vec<_>>().join(" ");
+            seen.insert(blake3::hash(normalized.as_bytes()))
+        }
+        None => true,
+    });
+    before - results.len()
+}
+
+/// `--stream`: print the semantic results as soon as they arrive, then append
+/// results the BM25 merge adds. Printed order is approximate.
+fn run_stream(
+    index: &SemanticIndex,
+    query: &str,
+    path: &Path,
+    params: &SearchParams,
+    owners: Option<&CodeOwners>,
+) -> Result<()> {
+    let key = |r: &SearchResult| match params.format {
+        OutputFormat::FilesOnly => (r.file.clone(), 0),
+        _ => (r.file.clone(), r.line),
+    };
+
+    let mut printed: HashSet<(String, usize)> = HashSet::new();
+    let mut partial_count = 0;
+    // The callback can't return an error; a failed write is raised after the search
+    let mut written = Ok(());
+    let results = index.search_streaming(query, fetch_k(params, owners), &mut |partial| {
+        let mut partial = apply_filters(partial, query, params);
+        filter_post(&mut partial, owners, params);
+        if written.is_ok() {
+            written = print_results(
+                &partial,
+                params.format,
+                false,
+                Some(path),
+                params.context_lines,
+                params.fields,
+            );
+        }
+        partial_count = partial.len();
+        printed.extend(partial.iter().map(key));
+    })?;
+    written?;
+
+    let mut results = apply_filters(results, query, params);
+    filter_post(&mut results, owners, params);
+    let added: Vec<SearchResult> = results
+        .iter()
+        .filter(|r| !printed.contains(&key(r)))
+        .cloned()
+        .collect();
     print_results(
-        &results,
+        &added,
         params.format,
         false,
-        Some(&path),
+        Some(path),
         params.context_lines,
-    );
+        params.fields,
+    )?;
 
-    if !params.quiet && !matches!(params.format, OutputFormat::Json | OutputFormat::FilesOnly) {
-        let result_word = if results.len() == 1 {
-            "result"
-        } else {
-            "results"
-        };
+    let total = partial_count + added.len();
+    if !params.quiet {
         eprintln!(
-            "{} {} ({:.2}s)",
-            results.len(),
-            result_word,
-            search_time.as_secs_f64()
+            "{total} results ({partial_count} semantic, {} added by BM25; streamed order is approximate)",
+            added.len()
         );
     }
 
-    std::process::exit(if results.is_empty() {
+    std::process::exit(if total == 0 {
         EXIT_NO_MATCH
     } else {
         EXIT_MATCH
     });
 }
 
-fn run_similar_search(
-    file_ref: FileRef,
-    num_results: usize,
-    format: OutputFormat,
-    quiet: bool,
-    context_lines: usize,
-) -> Result<()> {
+/// Sub-queries to run for `query`. With `--max-tokens`, a query over the budget is
+/// split at word boundaries; otherwise an over-long query is searched as-is with a
+/// warning, since the tokenizer keeps only the first `query_max_length` tokens.
+pub(crate) fn query_parts(
+    index: &SemanticIndex,
+    query: &str,
+    max_tokens: Option<usize>,
+) -> Result<Vec<String>> {
+    let limit = embedder::MODEL.query_max_length;
+    let budget = max_tokens.unwrap_or(limit).clamp(1, limit);
+    let count = index.query_token_count(query)?;
+    if count <= budget {
+        return Ok(vec![query.to_string()]);
+    }
+
+    if max_tokens.is_some() {
+        let parts = index.split_query(query, budget)?;
+        eprintln!(
+            "Query is {count} tokens; searching {} parts of up to {budget}",
+            parts.len()
+        );
+        return Ok(parts);
+    }
+
+    eprintln!(
+        "Warning: query is {count} tokens; only the first {limit} are used (--max-tokens N splits it)"
+    );
+    Ok(vec![query.to_string()])
+}
+
+/// Merge sub-query results by block, keeping each block's best score.
+fn merge_results(batches: Vec<Vec<SearchResult>>, k: usize) -> Vec<SearchResult> {
+    let mut best: HashMap<(String, usize), SearchResult> = HashMap::new();
+    for r in batches.into_iter().flatten() {
+        match best.entry((r.file.clone(), r.line)) {
+            Entry::Occupied(mut e) => {
+                if r.score > e.get().score {
+                    e.insert(r);
+                }
+            }
+            Entry::Vacant(e) => {
+                e.insert(r);
+            }
+        }
+    }
+    let mut merged: Vec<SearchResult> = best.into_values().collect();
+    merged.sort_by(|a, b| b.score.total_cmp(&a.score));
+    merged.truncate(k);
+    merged
+}
+
+/// Load CODEOWNERS from the index root when `--owner` is set.
+pub(crate) fn load_owners(
+    index: &SemanticIndex,
+    owner: Option<&str>,
+) -> Result<Option<CodeOwners>> {
+    if owner.is_none() {
+        return Ok(None);
+    }
+    match CodeOwners::find(index.root())? {
+        Some(owners) => Ok(Some(owners)),
+        None => bail!(
+            "--owner requires a CODEOWNERS file in {}",
+            index.root().display()
+        ),
+    }
+}
+
+/// Seconds per `--changed-since` duration unit.
+const DURATION_UNITS: &[(char, u64)] = &[
+    ('s', 1),
+    ('m', 60),
+    ('h', 3_600),
+    ('d', 86_400),
+    ('w', 604_800),
+];
+
+/// Cutoff for `--changed-since`: a duration before `now` (`30m`, `12h`, `3d`,
+/// `2w`) or a date (`YYYY-MM-DD` or unix time).
+pub(crate) fn parse_changed_since(s: &str, now: u64) -> Option<u64> {
+    let s = s.trim();
+    if let Some((unit, secs)) = DURATION_UNITS.iter().find(|(u, _)| s.ends_with(*u)) {
+        let n: u64 = s[..s.len() - unit.len_utf8()].parse().ok()?;
+        return Some(now.saturating_sub(n.saturating_mul(*secs)));
+    }
+    index::git::parse_date(s)
+}
+
+/// `--author`/`--after` need blame data recorded at index time.
+pub(crate) fn check_git_filters(index: &SemanticIndex, params: &SearchParams) -> Result<()> {
+    if (params.author.is_some() || params.after.is_some()) && !index.options()?.with_git {
+        bail!("--author/--after need git info; rebuild with 'og build --with-git'");
+    }
+    Ok(())
+}
+
+fn has_post_filters(params: &SearchParams, owners: Option<&CodeOwners>) -> bool {
+    owners.is_some()
+        || params.author.is_some()
+        || params.after.is_some()
+        || params.capture.is_some()
+        || !params.langs.is_empty()
+        || params.dedup
+        || params.unique_names != UniqueNames::Off
+        || params.max_per_type.is_some()
+        || params.group_adjacent
+        // Over-fetch so boosted files beyond the top N can move up
+        || !params.path_boosts.is_empty()
+        || params.near.is_some()
+        || params.tests != TestFilter::All
+}
+
+/// Number of candidates to fetch, over-fetching when results will be post-filtered.
+pub(crate) fn fetch_k(params: &SearchParams, owners: Option<&CodeOwners>) -> usize {
+    if has_post_filters(params, owners) {
+        params.num_results.saturating_mul(POST_FILTER_OVERFETCH)
+    } else {
+        params.num_results
+    }
+}
+
+/// Apply `--owner`, `--author`, `--after`, `--capture` and `--lang`, trimmed back to the requested count.
+pub(crate) fn filter_post(
+    results: &mut Vec<SearchResult>,
+    owners: Option<&CodeOwners>,
+    params: &SearchParams,
+) {
+    if !has_post_filters(params, owners) {
+        return;
+    }
+    if let (Some(owners), Some(owner)) = (owners, params.owner) {
+        results.retain(|r| owners.is_owned_by(&r.file, owner));
+    }
+    if let Some(author) = params.author {
+        let author = author.to_lowercase();
+        results.retain(|r| {
+            r.git
+                .as_ref()
+                .is_some_and(|g| g.author.to_lowercase().contains(&author))
+        });
+    }
+    if let Some(after) = params.after {
+        results.retain(|r| r.git.as_ref().is_some_and(|g| g.commit_time >= after));
+    }
+    if let Some(capture) = params.capture {
+        results.retain(|r| r.capture.as_deref() == Some(capture));
+    }
+    if !params.langs.is_empty() {
+        results.retain(|r| {
+            r.language
+                .as_deref()
+                .is_some_and(|lang| params.langs.iter().any(|l| l.eq_ignore_ascii_case(lang)))
+        });
+    }
+    results.truncate(params.num_results);
+}
+
+fn run_similar_search(file_ref: FileRef, params: &SearchParams) -> Result<()> {
+    let SearchParams {
+        num_results,
+        format,
+        quiet,
+        context_lines,
+        doc_filter,
+        fields,
+        ..
+    } = *params;
     let (file_path, line, name) = match &file_ref {
         FileRef::ByName { path, name } => (path.as_str(), None, Some(name.as_str())),
         FileRef::ByLine { path, line } => (path.as_str(), Some(*line), None),
@@ -231,7 +1146,8 @@ fn run_similar_search(
         .unwrap_or_else(|_| file_path.into());
     let abs_str = abs_path.to_string_lossy();
 
-    let index = SemanticIndex::new(&index_root, None)?;
+    let mut index = SemanticIndex::new(&index_root, None)?;
+    index.set_doc_filter(doc_filter);
     let mut results = index.find_similar(&abs_str, line, name, num_results)?;
 
     if !quiet {
@@ -251,7 +1167,27 @@ fn run_similar_search(
         std::process::exit(EXIT_NO_MATCH);
     }
 
-    print_results(&results, format, true, Some(&index_root), context_lines);
+    match params.output {
+        Some(file) => write_file(file, |out| {
+            write_results(
+                out,
+                &results,
+                format,
+                true,
+                Some(&index_root),
+                context_lines,
+                fields,
+            )
+        })?,
+        None => print_results(
+            &results,
+            format,
+            true,
+            Some(&index_root),
+            context_lines,
+            fields,
+        )?,
+    }
 
     if !quiet && !matches!(format, OutputFormat::Json) {
         let result_word = if results.len() == 1 {
@@ -266,7 +1202,7 @@ fn run_similar_search(
 }
 
 /// Parse query as file reference: file#name, file:line, or existing file.
-fn parse_file_reference(query: &str) -> Option<FileRef> {
+pub(crate) fn parse_file_reference(query: &str) -> Option<FileRef> {
     if query.is_empty() {
         return None;
     }
@@ -315,11 +1251,11 @@ fn parse_file_reference(query: &str) -> Option<FileRef> {
 
 /// Filter results by file type and exclude patterns.
 fn filter_results(
-    mut results: Vec<crate::types::SearchResult>,
+    mut results: Vec<SearchResult>,
     file_types: Option<&str>,
     exclude: &[String],
     code_only: bool,
-) -> Vec<crate::types::SearchResult> {
+) -> Vec<SearchResult> {
     // Build exclude list
     let mut exclude_patterns: Vec<String> = exclude.to_vec();
     if code_only {
@@ -369,16 +1305,20 @@ fn filter_results(
             }
         }
 
-        results.retain(|r| allowed_exts.iter().any(|ext| r.file.ends_with(ext)));
+        // Extensions compare case-insensitively (Foo.PY, README.MD)
+        results.retain(|r| {
+            let file = r.file.to_lowercase();
+            allowed_exts.iter().any(|ext| file.ends_with(ext))
+        });
     }
 
     // Exclude pattern filtering (simple glob matching)
     if !exclude_patterns.is_empty() {
         results.retain(|r| {
             !exclude_patterns.iter().any(|pattern| {
-                // Simple glob: *.ext matching
+                // Simple glob: *.ext matching, case-insensitive like the type filter
                 if let Some(ext) = pattern.strip_prefix('*') {
-                    r.file.ends_with(ext)
+                    r.file.to_lowercase().ends_with(&ext.to_lowercase())
                 } else {
                     r.file.contains(pattern)
                 }
@@ -388,3 +1328,204 @@ fn filter_results(
 
     results
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_indexes_listed_nearest_first() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let outer = tmp.path().canonicalize().unwrap();
+        let inner = outer.join("pkg");
+        let search = inner.join("src");
+        std::fs::create_dir_all(&search).unwrap();
+        assert!(nested_indexes_warning(&index::find_index_roots(&search)).is_none());
+
+        for root in [&outer, &inner] {
+            Manifest::default()
+                .save(&root.join(index::INDEX_DIR))
+                .unwrap();
+        }
+        let roots = index::find_index_roots(&search);
+        assert_eq!(&roots[..2], [inner.clone(), outer.clone()]);
+        let warning = nested_indexes_warning(&roots[..2]).unwrap();
+        assert!(warning.starts_with(&format!(
+            "2 indexes along this path; searching the nearest, {}:",
+            inner.display()
+        )));
+        assert!(warning.ends_with(&format!(
+            "'og clean -r {}' deletes all of them).",
+            outer.display()
+        )));
+        assert!(nested_indexes_warning(&roots[..1]).is_none());
+    }
+
+    fn result(file: &str) -> SearchResult {
+        SearchResult {
+            file: file.to_string(),
+            block_type: "function".to_string(),
+            name: "f".to_string(),
+            line: 0,
+            end_line: 0,
+            content: None,
+            score: 1.0,
+            ..Default::default()
+        }
+    }
+
+    fn files(results: &[SearchResult]) -> Vec<&str> {
+        results.iter().map(|r| r.file.as_str()).collect()
+    }
+
+    #[test]
+    fn changed_since_durations_and_dates() {
+        let now = 1_000_000;
+        assert_eq!(parse_changed_since("30m", now), Some(now - 1_800));
+        assert_eq!(parse_changed_since("2d", now), Some(now - 172_800));
+        assert_eq!(parse_changed_since("1970-01-02", now), Some(86_400));
+        assert_eq!(parse_changed_since("soon", now), None);
+        assert_eq!(parse_changed_since("xh", now), None);
+    }
+
+    #[test]
+    fn merge_keeps_best_score_per_block() {
+        let mut a = result("a.rs");
+        a.score = 0.5;
+        let mut a_better = result("a.rs");
+        a_better.score = 0.9;
+        let b = result("b.rs");
+        let merged = merge_results(vec![vec![a, b], vec![a_better]], 10);
+        assert_eq!(files(&merged), ["b.rs", "a.rs"]);
+        assert_eq!(merged[1].score, 0.9);
+    }
+
+    #[test]
+    fn unique_names_keeps_best_per_name() {
+        let named = |file: &str, name: &str| SearchResult {
+            name: name.to_string(),
+            ..result(file)
+        };
+        let all = vec![
+            named("A.java", "parse"),
+            named("A.java", "parse"),
+            named("B.java", "parse"),
+            named("B.java", "write"),
+        ];
+
+        let mut results = all.clone();
+        unique_names(&mut results, UniqueNames::Global);
+        assert_eq!(files(&results), ["A.java", "B.java"]);
+        assert_eq!(results[1].name, "write");
+
+        let mut results = all;
+        unique_names(&mut results, UniqueNames::PerFile);
+        assert_eq!(files(&results), ["A.java", "B.java", "B.java"]);
+    }
+
+    #[test]
+    fn percentile_keeps_top_of_set() {
+        let scored = |scores: &[f32]| -> Vec<SearchResult> {
+            scores
+                .iter()
+                .map(|&score| SearchResult {
+                    score,
+                    ..result("a.rs")
+                })
+                .collect()
+        };
+        let scores = |results: &[SearchResult]| results.iter().map(|r| r.score).collect::<Vec<_>>();
+
+        let mut results = scored(&[0.9, 0.7, 0.5, 0.3]);
+        retain_percentile(&mut results, 50.0);
+        assert_eq!(scores(&results), [0.9, 0.7, 0.5]);
+
+        let mut results = scored(&[0.9, 0.7, 0.5, 0.3]);
+        retain_percentile(&mut results, 100.0);
+        assert_eq!(scores(&results), [0.9]);
+
+        let mut results = scored(&[0.4, 0.4]);
+        retain_percentile(&mut results, 90.0);
+        assert_eq!(results.len(), 2);
+    }
+
+    #[test]
+    fn cap_per_type_balances_types() {
+        let typed = |file: &str, block_type: &str| SearchResult {
+            block_type: block_type.to_string(),
+            ..result(file)
+        };
+        let mut results = vec![
+            typed("a.rs", "function"),
+            typed("b.rs", "function"),
+            typed("c.rs", "function"),
+            typed("d.rs", "struct"),
+            typed("e.md", "section"),
+            typed("f.rs", "struct"),
+        ];
+        cap_per_type(&mut results, 1);
+        assert_eq!(files(&results), ["a.rs", "d.rs", "e.md"]);
+    }
+
+    #[test]
+    fn group_adjacent_merges_nearby_blocks() {
+        let block =
+            |file: &str, name: &str, line: usize, end_line: usize, score: f32| SearchResult {
+                name: name.to_string(),
+                line,
+                end_line,
+                content: Some(format!("fn {name}() {{}}")),
+                score,
+                ..result(file)
+            };
+        let results = vec![
+            block("a.rs", "parse", 10, 20, 0.9),
+            block("b.rs", "parse", 12, 20, 0.8),
+            block("a.rs", "lex", 0, 8, 0.7),
+            block("a.rs", "emit", 40, 50, 0.6),
+            block("a.rs", "check", 22, 30, 0.5),
+        ];
+
+        let grouped = group_adjacent(results);
+        assert_eq!(files(&grouped), ["a.rs", "b.rs", "a.rs"]);
+        let region = &grouped[0];
+        assert_eq!((region.line, region.end_line), (0, 30));
+        assert_eq!(region.name, "lex, parse, check");
+        assert_eq!(region.score, 0.9);
+        assert_eq!(
+            region.content.as_deref(),
+            Some("fn lex() {}\nfn parse() {}\nfn check() {}")
+        );
+        assert_eq!(grouped[2].name, "emit");
+    }
+
+    #[test]
+    fn dedup_keeps_first_of_each_clone() {
+        let with = |file: &str, content: &str| SearchResult {
+            content: Some(content.to_string()),
+            ..result(file)
+        };
+        let mut results = vec![
+            with("a.rs", "fn f() {\n    1\n}"),
+            with("b.rs", "fn g() {}"),
+            with("c.rs", "fn f() {   1 }"),
+            result("d.rs"),
+        ];
+        assert_eq!(dedup_results(&mut results), 1);
+        assert_eq!(files(&results), ["a.rs", "b.rs", "d.rs"]);
+    }
+
+    #[test]
+    fn type_filter_ignores_extension_case() {
+        let results = vec![result("src/App.PY"), result("lib.Rs"), result("main.go")];
+        let kept = filter_results(results, Some("py,rs"), &[], false);
+        assert_eq!(files(&kept), ["src/App.PY", "lib.Rs"]);
+    }
+
+    #[test]
+    fn code_only_excludes_uppercase_docs() {
+        let results = vec![result("README.MD"), result("NOTES.Txt"), result("Foo.Java")];
+        let kept = filter_results(results, None, &[], true);
+        assert_eq!(files(&kept), ["Foo.Java"]);
+    }
+}
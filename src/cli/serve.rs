@@ -0,0 +1,307 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+
+use crate::cli::mcp;
+
+/// Largest request body accepted (bytes).
+const MAX_BODY: usize = 1 << 20;
+
+/// Longest request or header line accepted (bytes).
+const MAX_LINE: usize = 8 << 10;
+
+/// Most header lines accepted in one request.
+const MAX_HEADERS: usize = 100;
+
+/// How long a client may stall while sending a request or reading the
+/// response. Connections are served one at a time, so a silent client would
+/// otherwise block every later request.
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Request {
+    method: String,
+    path: String,
+    query: String,
+    body: Vec<u8>,
+}
+
+/// Serve the MCP tool handlers as a small HTTP JSON API on localhost.
+///
+/// Requests are handled one at a time; each search auto-updates the index the
+/// same way the MCP tools do and honors `OG_TIMEOUT_MS`.
+pub fn run(port: u16) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .with_context(|| format!("Failed to bind 127.0.0.1:{port}"))?;
+    eprintln!("Listening on http://127.0.0.1:{port}");
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Connection failed: {e}");
+                continue;
+            }
+        };
+        if let Err(e) = handle_connection(stream) {
+            eprintln!("Request failed: {e}");
+        }
+    }
+    Ok(())
+}
+
+fn handle_connection(mut stream: TcpStream) -> Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let (status, body) = match read_request(&mut reader) {
+        Ok(Some(request)) => route(request),
+        Ok(None) => return Ok(()),
+        Err(e) => read_error(&e),
+    };
+    write_response(&mut stream, status, &body)
+}
+
+/// Response to a request that could not be read: 408 when the client stalled
+/// past the socket timeout (`WouldBlock` on Unix, `TimedOut` on Windows),
+/// otherwise 400.
+fn read_error(e: &anyhow::Error) -> (u16, Value) {
+    let timed_out = e.downcast_ref::<io::Error>().is_some_and(|e| {
+        matches!(
+            e.kind(),
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+        )
+    });
+    if timed_out {
+        (408, json!({ "error": "Request timed out" }))
+    } else {
+        (400, json!({ "error": e.to_string() }))
+    }
+}
+
+fn route(request: Request) -> (u16, Value) {
+    let result = match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/search") => parse_body(&request.body).and_then(|args| {
+            mcp::with_timeout(move |deadline| mcp::search(&args, deadline))
+                .map(|results| json!({ "results": results }))
+        }),
+        ("POST", "/similar") => parse_body(&request.body).and_then(|args| {
+            mcp::with_timeout(move |deadline| mcp::similar(&args, deadline))
+                .map(|results| json!({ "results": results }))
+        }),
+        ("GET", "/status") => mcp::status(&query_args(&request.query)).map(|status| match status {
+            Some(status) => json!({ "indexed": true, "status": status }),
+            None => json!({ "indexed": false }),
+        }),
+        (_, "/search" | "/similar" | "/status") => {
+            return (405, json!({ "error": "Method not allowed" }))
+        }
+        _ => return (404, json!({ "error": "Not found" })),
+    };
+
+    match result {
+        Ok(value) => (200, value),
+        Err(error) => (
+            http_status(error["code"].as_i64().unwrap_or(-32603)),
+            json!({ "error": error["message"] }),
+        ),
+    }
+}
+
+/// Map a JSON-RPC error code from the shared handlers to an HTTP status.
+fn http_status(code: i64) -> u16 {
+    match code {
+        -32700 | -32602 => 400,
+        mcp::TIMEOUT_ERROR => 504,
+        _ => 500,
+    }
+}
+
+/// Parse a JSON object body, accepting `k` as an alias for `num_results`.
+fn parse_body(body: &[u8]) -> Result<Value, Value> {
+    let mut args: Value = if body.is_empty() {
+        json!({})
+    } else {
+        serde_json::from_slice(body)
+            .map_err(|_| mcp::json_rpc_error(-32700, "Invalid JSON body"))?
+    };
+    let Some(obj) = args.as_object_mut() else {
+        return Err(mcp::json_rpc_error(-32602, "Body must be a JSON object"));
+    };
+    if let Some(k) = obj.remove("k") {
+        obj.insert("num_results".to_string(), k);
+    }
+    Ok(args)
+}
+
+/// `?path=...` query string for `GET /status`.
+fn query_args(query: &str) -> Value {
+    let mut args = serde_json::Map::new();
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        args.insert(key.to_string(), Value::String(percent_decode(value)));
+    }
+    Value::Object(args)
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+            (b'+', _) => out.push(b' '),
+            (b, _) => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Read one line into `line`, failing past `MAX_LINE` bytes.
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> Result<usize> {
+    let n = reader.take(MAX_LINE as u64 + 1).read_line(line)?;
+    if n > MAX_LINE {
+        anyhow::bail!("Request line too long");
+    }
+    Ok(n)
+}
+
+/// Read one HTTP/1.1 request. `None` if the client closed without sending one.
+fn read_request(reader: &mut impl BufRead) -> Result<Option<Request>> {
+    let mut line = String::new();
+    if read_line(reader, &mut line)? == 0 {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        anyhow::bail!("Malformed request line");
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut request = Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query: query.to_string(),
+        body: Vec::new(),
+    };
+
+    let mut content_length = 0;
+    for headers in 0.. {
+        if headers == MAX_HEADERS {
+            anyhow::bail!("Too many headers");
+        }
+        line.clear();
+        if read_line(reader, &mut line)? == 0 {
+            break;
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().context("Invalid Content-Length")?;
+            }
+        }
+    }
+    if content_length > MAX_BODY {
+        anyhow::bail!("Request body too large");
+    }
+
+    request.body.resize(content_length, 0);
+    reader.read_exact(&mut request.body)?;
+    Ok(Some(request))
+}
+
+fn write_response(stream: &mut impl Write, status: u16, body: &Value) -> Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        504 => "Gateway Timeout",
+        _ => "Internal Server Error",
+    };
+    let body = serde_json::to_string(body)?;
+    write!(
+        stream,
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_request_with_body() {
+        let raw = "POST /search?x=1 HTTP/1.1\r\nHost: localhost\r\nContent-Length: 15\r\n\r\n{\"query\":\"io\"}\n";
+        let request = read_request(&mut raw.as_bytes()).unwrap().unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/search");
+        assert_eq!(request.query, "x=1");
+        assert_eq!(parse_body(&request.body).unwrap()["query"], "io");
+    }
+
+    #[test]
+    fn rejects_oversized_headers() {
+        let long = format!(
+            "GET /status HTTP/1.1\r\nX: {}\r\n\r\n",
+            "a".repeat(MAX_LINE)
+        );
+        let err = read_request(&mut long.as_bytes()).err().unwrap();
+        assert!(err.to_string().contains("too long"), "{err}");
+
+        let many = format!(
+            "GET /status HTTP/1.1\r\n{}\r\n",
+            "X: 1\r\n".repeat(MAX_HEADERS)
+        );
+        assert!(read_request(&mut many.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn stalled_client_gets_408() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        // Promises a body it never sends
+        client
+            .write_all(b"POST /search HTTP/1.1\r\nContent-Length: 10\r\n\r\n")
+            .unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_millis(50)))
+            .unwrap();
+
+        let err = read_request(&mut BufReader::new(stream)).err().unwrap();
+        assert_eq!(read_error(&err).0, 408);
+        assert_eq!(
+            read_error(&anyhow::anyhow!("Malformed request line")).0,
+            400
+        );
+    }
+
+    #[test]
+    fn parses_args_and_errors() {
+        assert_eq!(parse_body(br#"{"k": 3}"#).unwrap()["num_results"], 3);
+        assert!(parse_body(b"[1]").is_err());
+        assert_eq!(query_args("path=%2Ftmp%2Fa+b")["path"], "/tmp/a b");
+        assert_eq!(http_status(-32602), 400);
+        assert_eq!(http_status(mcp::TIMEOUT_ERROR), 504);
+        assert_eq!(http_status(-32000), 500);
+    }
+}
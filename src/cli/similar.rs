@@ -0,0 +1,77 @@
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+use crate::cli::output::print_results;
+use crate::cli::search;
+use crate::index;
+use crate::types::{DocFilter, OutputFormat, EXIT_ERROR, EXIT_NO_MATCH};
+
+/// Find indexed code similar to a snippet given via `--from-text` or stdin.
+pub fn run(
+    from_text: Option<&str>,
+    path: &Path,
+    num_results: usize,
+    format: OutputFormat,
+    quiet: bool,
+    context_lines: usize,
+    doc_filter: DocFilter,
+) -> Result<()> {
+    let text = match from_text {
+        Some(text) => text.to_string(),
+        None => {
+            let mut buf = String::new();
+            std::io::stdin()
+                .read_to_string(&mut buf)
+                .context("Failed to read snippet from stdin")?;
+            buf
+        }
+    };
+    if text.trim().is_empty() {
+        bail!("No snippet provided. Pass --from-text or pipe code on stdin.");
+    }
+
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if !path.exists() {
+        eprintln!("Path does not exist: {}", path.display());
+        std::process::exit(EXIT_ERROR);
+    }
+
+    let mut index = search::open_index(
+        &path,
+        quiet,
+        false,
+        search::deadline(index::timeout_from_env()),
+    )?;
+    index.set_search_scope(Some(&path));
+    index.set_doc_filter(doc_filter);
+
+    if !quiet {
+        eprint!("Finding similar code...");
+    }
+    let results = index.find_similar_text(&text, num_results)?;
+    if !quiet {
+        eprintln!("\r                       \r");
+    }
+
+    if results.is_empty() {
+        if !matches!(format, OutputFormat::Json) {
+            eprintln!("No similar code found");
+        }
+        std::process::exit(EXIT_NO_MATCH);
+    }
+
+    print_results(&results, format, true, Some(&path), context_lines, None)?;
+
+    if !quiet && !matches!(format, OutputFormat::Json) {
+        let result_word = if results.len() == 1 {
+            "result"
+        } else {
+            "results"
+        };
+        eprintln!("{} similar {}", results.len(), result_word);
+    }
+
+    Ok(())
+}
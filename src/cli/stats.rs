@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Result;
+use owo_colors::OwoColorize;
+
+use crate::cli::model::{dir_size, format_size};
+use crate::cli::outline::{get_blocks, OutlineEntry};
+use crate::index::{find_index_root, manifest::Manifest, store::Store, VECTORS_DIR};
+use crate::types::EXIT_ERROR;
+
+/// Files listed under "Largest files".
+const TOP_FILES: usize = 10;
+
+/// Corpus totals for `og stats`.
+#[derive(Debug)]
+struct Stats {
+    files: usize,
+    blocks: usize,
+    /// Block count per block type, most first.
+    by_type: Vec<(String, usize)>,
+    /// Block count per file extension, most first.
+    by_extension: Vec<(String, usize)>,
+    /// Mean block length in lines.
+    avg_lines: f64,
+    /// Files with the most blocks, most first.
+    largest_files: Vec<(String, usize)>,
+}
+
+/// `og stats`: block counts by type and extension, average block size,
+/// largest files and index size, from the manifest and store metadata.
+pub fn run(path: &Path, json: bool) -> Result<()> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let (index_root, index_dir) = find_index_root(&path);
+
+    let Some(index_dir) = index_dir else {
+        eprintln!("No index found. Run 'og build' to create.");
+        std::process::exit(EXIT_ERROR);
+    };
+
+    let manifest = match Manifest::load(&index_dir) {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(EXIT_ERROR);
+        }
+    };
+
+    let store = match Store::open(&index_dir.join(VECTORS_DIR), manifest.options.shard, None) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Failed to open index: {e}");
+            std::process::exit(EXIT_ERROR);
+        }
+    };
+
+    let scope_prefix = path
+        .strip_prefix(&index_root)
+        .ok()
+        .map(|p| p.to_string_lossy().into_owned())
+        .filter(|s| !s.is_empty());
+
+    let files: Vec<(&str, Vec<OutlineEntry>)> = manifest
+        .files
+        .iter()
+        .filter(|(rel_path, _)| match &scope_prefix {
+            Some(prefix) => {
+                rel_path.as_str() == prefix.as_str() || rel_path.starts_with(&format!("{prefix}/"))
+            }
+            None => true,
+        })
+        .map(|(rel_path, entry)| (rel_path.as_str(), get_blocks(&entry.blocks, &store)))
+        .collect();
+
+    let stats = collect(&files);
+    let index_bytes = dir_size(&index_dir);
+
+    if json {
+        let counts = |counts: &[(String, usize)]| -> serde_json::Map<String, serde_json::Value> {
+            counts
+                .iter()
+                .map(|(key, n)| (key.clone(), serde_json::json!(n)))
+                .collect()
+        };
+        let output = serde_json::json!({
+            "files": stats.files,
+            "blocks": stats.blocks,
+            "by_type": counts(&stats.by_type),
+            "by_extension": counts(&stats.by_extension),
+            "avg_lines": (stats.avg_lines * 10.0).round() / 10.0,
+            "largest_files": stats
+                .largest_files
+                .iter()
+                .map(|(file, blocks)| serde_json::json!({ "file": file, "blocks": blocks }))
+                .collect::<Vec<_>>(),
+            "index_bytes": index_bytes,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    println!("Files:      {}", stats.files);
+    println!(
+        "Blocks:     {} (avg {:.1} lines)",
+        stats.blocks, stats.avg_lines
+    );
+    println!("Index size: {}", format_size(index_bytes));
+    for (title, counts) in [
+        ("By type", &stats.by_type),
+        ("By extension", &stats.by_extension),
+        ("Largest files (blocks)", &stats.largest_files),
+    ] {
+        println!("\n{}", title.bold());
+        let width = counts.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+        for (key, n) in counts {
+            println!("  {key:<width$}  {n}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Tally blocks per type, extension and file.
+fn collect(files: &[(&str, Vec<OutlineEntry>)]) -> Stats {
+    let mut by_type: HashMap<&str, usize> = HashMap::new();
+    let mut by_extension: HashMap<String, usize> = HashMap::new();
+    let mut lines = 0;
+    let mut blocks = 0;
+
+    for (file, entries) in files {
+        let extension = Path::new(file).extension().map_or_else(
+            || "(none)".to_string(),
+            |e| e.to_string_lossy().into_owned(),
+        );
+        *by_extension.entry(extension).or_default() += entries.len();
+        for entry in entries {
+            *by_type.entry(&entry.block_type).or_default() += 1;
+            lines += entry.end_line.saturating_sub(entry.start_line) + 1;
+        }
+        blocks += entries.len();
+    }
+
+    let mut largest_files: Vec<(String, usize)> = files
+        .iter()
+        .map(|(file, entries)| (file.to_string(), entries.len()))
+        .collect();
+    sort_counts(&mut largest_files);
+    largest_files.truncate(TOP_FILES);
+
+    Stats {
+        files: files.len(),
+        blocks,
+        by_type: sorted(by_type.into_iter().map(|(k, n)| (k.to_string(), n))),
+        by_extension: sorted(by_extension.into_iter()),
+        avg_lines: if blocks == 0 {
+            0.0
+        } else {
+            lines as f64 / blocks as f64
+        },
+        largest_files,
+    }
+}
+
+fn sorted(counts: impl Iterator<Item = (String, usize)>) -> Vec<(String, usize)> {
+    let mut counts: Vec<_> = counts.collect();
+    sort_counts(&mut counts);
+    counts
+}
+
+/// Most first, ties by name.
+fn sort_counts(counts: &mut [(String, usize)]) {
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(block_type: &str, start_line: usize, end_line: usize) -> OutlineEntry {
+        OutlineEntry {
+            name: "x".to_string(),
+            block_type: block_type.to_string(),
+            start_line,
+            end_line,
+        }
+    }
+
+    #[test]
+    fn tallies_types_extensions_and_files() {
+        let files = vec![
+            (
+                "src/a.rs",
+                vec![entry("function", 0, 9), entry("struct", 10, 13)],
+            ),
+            ("docs/guide.md", vec![entry("section", 0, 1)]),
+            ("Makefile", vec![]),
+        ];
+        let stats = collect(&files);
+        assert_eq!(stats.files, 3);
+        assert_eq!(stats.blocks, 3);
+        assert_eq!(stats.avg_lines, 16.0 / 3.0);
+        assert_eq!(
+            stats.by_extension,
+            [
+                ("rs".to_string(), 2),
+                ("md".to_string(), 1),
+                ("(none)".to_string(), 0)
+            ]
+        );
+        assert_eq!(stats.by_type[0], ("function".to_string(), 1));
+        assert_eq!(stats.largest_files[0], ("src/a.rs".to_string(), 2));
+    }
+}
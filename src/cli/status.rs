@@ -2,13 +2,14 @@ use std::path::Path;
 
 use anyhow::Result;
 
+use crate::index::manifest::Manifest;
 use crate::index::{walker, SemanticIndex, INDEX_DIR};
 use crate::types::EXIT_ERROR;
 
 pub fn run(path: &Path) -> Result<()> {
     let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
 
-    if !path.join(INDEX_DIR).join("manifest.json").exists() {
+    if !Manifest::exists(&path.join(INDEX_DIR)) {
         eprintln!("No index. Run 'og build' to create.");
         return Ok(());
     }
@@ -27,7 +28,7 @@ pub fn run(path: &Path) -> Result<()> {
     };
 
     let block_count = index.count()?;
-    let files = walker::scan(&path)?;
+    let files = walker::scan(&path, &index.options()?)?;
     let file_count = files.len();
 
     let stale_result = index.get_stale_files(&files);
@@ -0,0 +1,155 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::cli::outline::get_blocks;
+use crate::index::{find_index_root, manifest::Manifest, store::Store, VECTORS_DIR};
+use crate::types::EXIT_ERROR;
+
+/// An indexed block name and where it starts.
+struct Tag {
+    name: String,
+    file: String,
+    /// 1-based.
+    line: usize,
+    block_type: String,
+}
+
+/// `og tags`: every block name under `path` with its file and line, read
+/// from the manifest and store metadata. Paths are relative to the index root.
+pub fn run(path: &Path, format: &str) -> Result<()> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let (index_root, index_dir) = find_index_root(&path);
+
+    let Some(index_dir) = index_dir else {
+        eprintln!("No index found. Run 'og build' to create.");
+        std::process::exit(EXIT_ERROR);
+    };
+
+    let manifest = match Manifest::load(&index_dir) {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(EXIT_ERROR);
+        }
+    };
+
+    let store = match Store::open(&index_dir.join(VECTORS_DIR), manifest.options.shard, None) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Failed to open index: {e}");
+            std::process::exit(EXIT_ERROR);
+        }
+    };
+
+    let scope_prefix = path
+        .strip_prefix(&index_root)
+        .ok()
+        .map(|p| p.to_string_lossy().into_owned())
+        .filter(|s| !s.is_empty());
+
+    let mut files: Vec<(&String, &[String])> = manifest
+        .files
+        .iter()
+        .filter(|(rel_path, _)| match &scope_prefix {
+            Some(prefix) => {
+                rel_path.as_str() == prefix.as_str() || rel_path.starts_with(&format!("{prefix}/"))
+            }
+            None => true,
+        })
+        .map(|(rel_path, entry)| (rel_path, entry.blocks.as_slice()))
+        .collect();
+    files.sort_by_key(|(path, _)| *path);
+
+    let tags: Vec<Tag> = files
+        .into_iter()
+        .flat_map(|(file, block_ids)| {
+            get_blocks(block_ids, &store)
+                .into_iter()
+                .filter(|e| !e.name.is_empty())
+                .map(|e| Tag {
+                    name: e.name,
+                    file: file.clone(),
+                    line: e.start_line + 1,
+                    block_type: e.block_type,
+                })
+        })
+        .collect();
+
+    match format {
+        "json" => {
+            let output: Vec<serde_json::Value> = tags
+                .iter()
+                .map(|t| {
+                    serde_json::json!({
+                        "name": t.name,
+                        "file": t.file,
+                        "line": t.line,
+                        "type": t.block_type,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        "ctags" => print!("{}", ctags(tags)),
+        _ => {
+            for t in &tags {
+                println!("{}\t{}:{}\t{}", t.name, t.file, t.line, t.block_type);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A `tags` file in the format editors read: sorted by name, one
+/// `name<TAB>file<TAB>line;"<TAB>kind` line per tag.
+fn ctags(mut tags: Vec<Tag>) -> String {
+    tags.sort_by(|a, b| (&a.name, &a.file, a.line).cmp(&(&b.name, &b.file, b.line)));
+    let mut out = String::from(
+        "!_TAG_FILE_FORMAT\t2\t/extended format/\n!_TAG_FILE_SORTED\t1\t/0=unsorted, 1=sorted/\n!_TAG_PROGRAM_NAME\tog\t//\n",
+    );
+    for t in tags {
+        // Names with whitespace (e.g. doc headings) can't be tag names
+        if t.name.contains(char::is_whitespace) {
+            continue;
+        }
+        out.push_str(&format!(
+            "{}\t{}\t{};\"\t{}\n",
+            t.name, t.file, t.line, t.block_type
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(name: &str, file: &str, line: usize) -> Tag {
+        Tag {
+            name: name.to_string(),
+            file: file.to_string(),
+            line,
+            block_type: "function".to_string(),
+        }
+    }
+
+    #[test]
+    fn ctags_sorted_by_name() {
+        let out = ctags(vec![
+            tag("parse", "src/b.rs", 10),
+            tag("Config", "src/a.rs", 3),
+            tag("Getting Started", "README.md", 1),
+        ]);
+        let lines: Vec<&str> = out.lines().filter(|l| !l.starts_with("!_")).collect();
+        assert_eq!(
+            lines,
+            [
+                "Config\tsrc/a.rs\t3;\"\tfunction",
+                "parse\tsrc/b.rs\t10;\"\tfunction",
+            ]
+        );
+        assert!(out.starts_with("!_TAG_FILE_FORMAT\t2"));
+    }
+}
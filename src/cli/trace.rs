@@ -0,0 +1,104 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::boost::{boost_factors, near_boost, scale_score, total_boost, BoostQuery, MAX_BOOST};
+use crate::cli::search::SearchParams;
+use crate::index::SemanticIndex;
+use crate::types::SearchResult;
+
+/// `--trace-json <file>`, a no-op without it: write every candidate of each retrieval path, the
+/// merged scores, the boosts applied to each, and the final ranking, for
+/// regression-testing ranking changes against a fixed query set. Retrieval
+/// uses `expanded` (with `@file` references spliced in), boosts `query`.
+pub fn write(
+    index: &SemanticIndex,
+    query: &str,
+    expanded: &str,
+    k: usize,
+    params: &SearchParams,
+    root: &Path,
+    results: &[SearchResult],
+) -> Result<()> {
+    let Some(file) = params.trace_json else {
+        return Ok(());
+    };
+    let trace = index.search_trace(expanded, k)?;
+    let ids = |candidates: &[(String, f32)]| -> Vec<serde_json::Value> {
+        candidates
+            .iter()
+            .map(|(id, score)| serde_json::json!({ "id": id, "score": score }))
+            .collect()
+    };
+
+    let boost_query = BoostQuery::new(query);
+    let mut merged: Vec<(serde_json::Value, f32)> = trace
+        .merged
+        .iter()
+        .map(|c| {
+            let r = &c.result;
+            let mut factors = boost_factors(r, &boost_query);
+            let mut boosted = scale_score(r.score, total_boost(&factors));
+            let block_type = r.block_type.to_lowercase();
+            if let Some(w) = params
+                .prefer_types
+                .iter()
+                .find(|w| w.block_type == block_type)
+            {
+                factors.push(("prefer-type", w.weight as f64));
+                boosted = scale_score(boosted, w.weight as f64);
+            }
+            let rel = Path::new(&r.file)
+                .strip_prefix(root)
+                .unwrap_or(Path::new(&r.file));
+            let rel = rel.to_string_lossy();
+            let mut path_weights = params
+                .path_boosts
+                .iter()
+                .filter(|w| rel.starts_with(w.prefix.as_str()))
+                .peekable();
+            if path_weights.peek().is_some() {
+                let weight = path_weights
+                    .map(|w| w.weight as f64)
+                    .product::<f64>()
+                    .clamp(1.0 / MAX_BOOST, MAX_BOOST);
+                factors.push(("path-boost", weight));
+                boosted = scale_score(boosted, weight);
+            }
+            if let Some(anchor) = params.near {
+                let weight = near_boost(r, anchor);
+                factors.push(("near", weight));
+                boosted = scale_score(boosted, weight);
+            }
+
+            let boosts: serde_json::Map<String, serde_json::Value> = factors
+                .into_iter()
+                .map(|(name, factor)| (name.to_string(), serde_json::json!(factor)))
+                .collect();
+            let value = serde_json::json!({
+                "id": r.id,
+                "semantic": c.semantic,
+                "bm25": c.bm25,
+                "merged": r.score,
+                "boosts": boosts,
+                "boosted": boosted,
+            });
+            (value, boosted)
+        })
+        .collect();
+    merged.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let output = serde_json::json!({
+        "query": query,
+        "k": k,
+        "semantic": ids(&trace.semantic),
+        "bm25": ids(&trace.bm25),
+        "merged": merged.into_iter().map(|(value, _)| value).collect::<Vec<_>>(),
+        "final": results
+            .iter()
+            .map(|r| serde_json::json!({ "id": r.id, "score": r.score }))
+            .collect::<Vec<_>>(),
+    });
+    std::fs::write(file, serde_json::to_string_pretty(&output)?)
+        .with_context(|| format!("Failed to write trace to {}", file.display()))
+}
@@ -0,0 +1,33 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::index::manifest::Manifest;
+use crate::index::{SemanticIndex, INDEX_DIR};
+
+pub fn run(path: &Path) -> Result<()> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+    if !Manifest::exists(&path.join(INDEX_DIR)) {
+        eprintln!("No index. Run 'og build' to create.");
+        return Ok(());
+    }
+
+    eprint!("Vacuuming...");
+    let index = SemanticIndex::new(&path, None)?;
+    let stats = index.vacuum()?;
+    eprintln!("\r            \r");
+
+    println!(
+        "Reclaimed {} orphaned blocks ({} kept)",
+        stats.deleted, stats.blocks
+    );
+    if stats.errors > 0 {
+        eprintln!(
+            "{} indexed blocks missing from the store. Run 'og build --force' to rebuild.",
+            stats.errors
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,140 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::embedder::MODEL;
+use crate::index::manifest::{Manifest, ManifestInfo, MANIFEST_VERSION};
+use crate::index::{find_index_root, store::Store, VECTORS_DIR};
+use crate::types::EXIT_ERROR;
+
+/// Missing block IDs listed in the report; the rest are counted.
+const SHOWN_MISSING: usize = 5;
+
+/// `og validate`: check that the manifest parses and matches this og's format
+/// and model, that every manifest block is in the store, and that the store
+/// holds no orphaned blocks. Prints a report and exits non-zero on any problem,
+/// so a corrupt cached index fails CI instead of returning wrong results.
+pub fn run(path: &Path) -> Result<()> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let (_, index_dir) = find_index_root(&path);
+    let Some(index_dir) = index_dir else {
+        eprintln!("No index found. Run 'og build' to create.");
+        std::process::exit(EXIT_ERROR);
+    };
+
+    let info = match Manifest::info(&index_dir) {
+        Ok(info) => info,
+        Err(e) => fail(&[format!("Manifest is unreadable: {e}")]),
+    };
+    let mut found = compatibility_problems(&info);
+    if !found.is_empty() {
+        fail(&found);
+    }
+    let manifest = match Manifest::load(&index_dir) {
+        Ok(m) => m,
+        Err(e) => fail(&[format!("Manifest does not parse: {e}")]),
+    };
+
+    let store = match Store::open(&index_dir.join(VECTORS_DIR), manifest.options.shard, None) {
+        Ok(s) => s,
+        Err(e) => fail(&[format!("Store does not open: {e}")]),
+    };
+
+    let mut blocks = 0;
+    let mut missing: Vec<&str> = Vec::new();
+    for id in manifest.files.values().flat_map(|e| &e.blocks) {
+        blocks += 1;
+        if store.get_metadata_by_id(id).is_none() {
+            missing.push(id);
+        }
+    }
+    let orphans = store.len().saturating_sub(blocks - missing.len());
+    found.extend(store_problems(&missing, orphans));
+    if !found.is_empty() {
+        fail(&found);
+    }
+
+    println!(
+        "OK: {} files, {blocks} blocks, model {}",
+        manifest.files.len(),
+        info.model
+    );
+    Ok(())
+}
+
+/// Format or model mismatches with this og.
+fn compatibility_problems(info: &ManifestInfo) -> Vec<String> {
+    let mut found = Vec::new();
+    if info.version != MANIFEST_VERSION {
+        found.push(format!(
+            "Manifest format is version {} (this og uses {MANIFEST_VERSION})",
+            info.version
+        ));
+    }
+    if info.precision().is_none() {
+        found.push(format!(
+            "Index was built with model '{}' (this og uses '{}')",
+            info.model, MODEL.version
+        ));
+    }
+    found
+}
+
+/// Manifest blocks absent from the store, and store blocks no manifest entry lists.
+fn store_problems(missing: &[&str], orphans: usize) -> Vec<String> {
+    let mut found = Vec::new();
+    if !missing.is_empty() {
+        let mut shown = missing[..missing.len().min(SHOWN_MISSING)].join(", ");
+        if missing.len() > SHOWN_MISSING {
+            shown.push_str(&format!(", ... ({} more)", missing.len() - SHOWN_MISSING));
+        }
+        found.push(format!(
+            "{} manifest blocks missing from the store: {shown}",
+            missing.len()
+        ));
+    }
+    if orphans > 0 {
+        found.push(format!(
+            "{orphans} orphaned blocks in the store (run 'og vacuum')"
+        ));
+    }
+    found
+}
+
+fn fail(found: &[String]) -> ! {
+    for problem in found {
+        eprintln!("{problem}");
+    }
+    eprintln!("Index is invalid. Run 'og build --force' to rebuild.");
+    std::process::exit(EXIT_ERROR);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_mismatches_missing_and_orphans() {
+        let info = ManifestInfo {
+            version: MANIFEST_VERSION,
+            model: MODEL.version.to_string(),
+            blocks: 3,
+        };
+        assert!(compatibility_problems(&info).is_empty());
+        let old = ManifestInfo {
+            version: MANIFEST_VERSION - 1,
+            model: "other-model-v2".to_string(),
+            ..info
+        };
+        assert_eq!(compatibility_problems(&old).len(), 2);
+
+        assert!(store_problems(&[], 0).is_empty());
+        let missing: Vec<String> = (0..7).map(|i| format!("a.rs:f{i}")).collect();
+        let missing: Vec<&str> = missing.iter().map(String::as_str).collect();
+        let found = store_problems(&missing, 2);
+        assert_eq!(found.len(), 2);
+        assert!(found[0].starts_with("7 manifest blocks missing"));
+        assert!(found[0].ends_with("a.rs:f4, ... (2 more)"));
+        assert!(found[1].starts_with("2 orphaned"));
+    }
+}
@@ -0,0 +1,128 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+
+use crate::boost::{boost_factors, scale_score, total_boost, BoostQuery, MAX_BOOST};
+use crate::cli::search;
+use crate::index::{self, CandidateScores};
+use crate::types::{FileRef, EXIT_NO_MATCH};
+
+/// Candidates searched for the target block, well past a typical `-n`.
+const WHY_CANDIDATES: usize = 200;
+
+/// A candidate's score after `boost_results`, with the multipliers applied.
+struct Ranked {
+    candidate: CandidateScores,
+    /// Position by merged score, before boosts.
+    merged_rank: usize,
+    factors: Vec<(&'static str, f64)>,
+    boosted: f32,
+}
+
+/// `og why <query> <file:line|file#name>`: show how one block was scored.
+pub fn run(query: &str, target: &str, path: &Path) -> Result<()> {
+    let (file, line, name) = match search::parse_file_reference(target) {
+        Some(FileRef::ByLine { path, line }) => (path, Some(line), None),
+        Some(FileRef::ByName { path, name }) => (path, None, Some(name)),
+        _ => bail!("Expected an existing file:line or file#name, got '{target}'"),
+    };
+    let file = Path::new(&file).canonicalize()?;
+    let file = file.to_string_lossy();
+
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let mut index = search::open_index(
+        &path,
+        false,
+        false,
+        search::deadline(index::timeout_from_env()),
+    )?;
+    index.set_search_scope(Some(&path));
+
+    let candidates = index.search_with_scores(query, WHY_CANDIDATES)?;
+    let total = candidates.len();
+    let boost_query = BoostQuery::new(query);
+    let mut ranked: Vec<Ranked> = candidates
+        .into_iter()
+        .enumerate()
+        .map(|(merged_rank, candidate)| {
+            let factors = boost_factors(&candidate.result, &boost_query);
+            let boosted = scale_score(candidate.result.score, total_boost(&factors));
+            Ranked {
+                candidate,
+                merged_rank,
+                factors,
+                boosted,
+            }
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.boosted
+            .partial_cmp(&a.boosted)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let found = ranked
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| r.candidate.result.file == file)
+        .filter(|(_, r)| match (line, &name) {
+            (Some(line), _) => {
+                (r.candidate.result.line..=r.candidate.result.end_line).contains(&line)
+            }
+            (None, Some(name)) => &r.candidate.result.name == name,
+            (None, None) => false,
+        })
+        // Innermost block containing the line
+        .min_by_key(|(_, r)| r.candidate.result.end_line - r.candidate.result.line);
+
+    let Some((rank, r)) = found else {
+        eprintln!("{target} is not among the top {WHY_CANDIDATES} candidates for \"{query}\"");
+        std::process::exit(EXIT_NO_MATCH);
+    };
+
+    let result = &r.candidate.result;
+    let display = Path::new(&result.file)
+        .strip_prefix(&path)
+        .unwrap_or(Path::new(&result.file))
+        .display();
+    let score = |s: Option<f32>| match s {
+        Some(s) => format!("{s:.4}"),
+        None => "- (not a candidate)".to_string(),
+    };
+
+    println!(
+        "{display}:{} {} ({})",
+        result.line, result.name, result.block_type
+    );
+    println!(
+        "  rank      {} of {total} ({} before boosts)",
+        rank + 1,
+        r.merged_rank + 1
+    );
+    println!("  semantic  {}", score(r.candidate.semantic));
+    println!("  bm25      {}", score(r.candidate.bm25));
+    println!("  merged    {:.4}", result.score);
+    if r.factors.is_empty() {
+        println!("  boosts    none");
+    } else {
+        let factors: Vec<String> = r
+            .factors
+            .iter()
+            .map(|(label, f)| format!("{label} x{f:.2}"))
+            .collect();
+        let product: f64 = r.factors.iter().map(|(_, f)| f).product();
+        let capped = if product > MAX_BOOST {
+            format!(" (capped at x{MAX_BOOST:.1})")
+        } else {
+            String::new()
+        };
+        println!(
+            "  boosts    {} = x{:.2}{capped}",
+            factors.join(", "),
+            total_boost(&r.factors)
+        );
+    }
+    println!("  final     {:.4}", r.boosted);
+
+    Ok(())
+}
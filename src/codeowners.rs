@@ -0,0 +1,121 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+/// Locations checked for a CODEOWNERS file, relative to the index root.
+const CODEOWNERS_PATHS: &[&str] = &["CODEOWNERS", ".github/CODEOWNERS", "docs/CODEOWNERS"];
+
+struct Rule {
+    matcher: Gitignore,
+    owners: Vec<String>,
+}
+
+/// CODEOWNERS rules for `--owner` filtering.
+///
+/// Patterns use gitignore syntax; the last matching rule wins, as on GitHub/GitLab.
+pub struct CodeOwners {
+    root: PathBuf,
+    rules: Vec<Rule>,
+}
+
+impl CodeOwners {
+    /// Load the first CODEOWNERS file found under `root`, if any.
+    pub fn find(root: &Path) -> Result<Option<Self>> {
+        for rel in CODEOWNERS_PATHS {
+            let path = root.join(rel);
+            if path.is_file() {
+                let content = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                return Ok(Some(Self::parse(root, &content)));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn parse(root: &Path, content: &str) -> Self {
+        let rules = content
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let pattern = parts.next()?;
+                let mut builder = GitignoreBuilder::new(root);
+                builder.add_line(None, pattern).ok()?;
+                Some(Rule {
+                    matcher: builder.build().ok()?,
+                    owners: parts.map(String::from).collect(),
+                })
+            })
+            .collect();
+        Self {
+            root: root.to_path_buf(),
+            rules,
+        }
+    }
+
+    /// Owners of `file` (absolute or root-relative) from the last matching rule.
+    pub fn owners_of(&self, file: &str) -> &[String] {
+        let path = Path::new(file);
+        let rel = path.strip_prefix(&self.root).unwrap_or(path);
+        if rel.has_root() {
+            return &[];
+        }
+        self.rules
+            .iter()
+            .rev()
+            .find(|r| {
+                r.matcher
+                    .matched_path_or_any_parents(rel, false)
+                    .is_ignore()
+            })
+            .map(|r| r.owners.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Whether `owner` (with or without a leading '@', case-insensitive) owns `file`.
+    pub fn is_owned_by(&self, file: &str, owner: &str) -> bool {
+        let want = owner.trim_start_matches('@');
+        self.owners_of(file)
+            .iter()
+            .any(|o| o.trim_start_matches('@').eq_ignore_ascii_case(want))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODEOWNERS: &str = "\
+# Default owners
+*           @org/everyone
+/src/api/   @org/backend @alice
+*.ts        @org/frontend
+docs/**     docs@example.com
+";
+
+    fn owners() -> CodeOwners {
+        CodeOwners::parse(Path::new("/repo"), CODEOWNERS)
+    }
+
+    #[test]
+    fn last_matching_rule_wins() {
+        let co = owners();
+        assert_eq!(
+            co.owners_of("/repo/src/api/handler.rs"),
+            ["@org/backend", "@alice"]
+        );
+        assert_eq!(co.owners_of("/repo/src/api/client.ts"), ["@org/frontend"]);
+        assert_eq!(co.owners_of("README.md"), ["@org/everyone"]);
+    }
+
+    #[test]
+    fn owner_match_ignores_at_and_case() {
+        let co = owners();
+        assert!(co.is_owned_by("src/api/handler.rs", "ALICE"));
+        assert!(co.is_owned_by("src/api/handler.rs", "@org/backend"));
+        assert!(!co.is_owned_by("src/lib.rs", "org/backend"));
+        assert!(co.is_owned_by("docs/guide/intro.md", "docs@example.com"));
+    }
+}
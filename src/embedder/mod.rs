@@ -1,13 +1,19 @@
 pub mod onnx;
 pub mod tokenizer;
 
+use std::path::PathBuf;
+use std::time::Duration;
+
 use anyhow::{Context, Result};
+use hf_hub::api::sync::{ApiError, ApiRepo};
 use ndarray::Array2;
 
 /// Configuration for an embedding model.
 pub struct ModelConfig {
     pub repo: &'static str,
     pub model_file: &'static str,
+    /// Optional full-precision variant in the same repo (`--model-fp32`).
+    pub fp32_model_file: Option<&'static str>,
     pub tokenizer_file: &'static str,
     pub token_dim: usize,
     pub doc_max_length: usize,
@@ -26,6 +32,7 @@ pub const MAX_STORED_TOKENS: usize = 512;
 pub const MODEL: &ModelConfig = &ModelConfig {
     repo: "lightonai/LateOn-Code-edge",
     model_file: "model.onnx",
+    fp32_model_file: Some("model_fp32.onnx"),
     tokenizer_file: "tokenizer.json",
     token_dim: 48,
     doc_max_length: 1024,
@@ -34,6 +41,57 @@ pub const MODEL: &ModelConfig = &ModelConfig {
     batch_size: 64,
 };
 
+/// Model weight precision. Recorded in the manifest's model string so
+/// indexes built at different precisions are never mixed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Precision {
+    #[default]
+    Int8,
+    Fp32,
+}
+
+impl Precision {
+    pub fn from_flags(fp32: bool) -> Self {
+        if fp32 {
+            Self::Fp32
+        } else {
+            Self::Int8
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Int8 => "int8",
+            Self::Fp32 => "fp32",
+        }
+    }
+}
+
+impl ModelConfig {
+    /// Model file for `precision`, if the repo provides one.
+    pub fn model_file_for(&self, precision: Precision) -> Option<&'static str> {
+        match precision {
+            Precision::Int8 => Some(self.model_file),
+            Precision::Fp32 => self.fp32_model_file,
+        }
+    }
+
+    /// Manifest model string; the default INT8 model keeps the bare version.
+    pub fn version_for(&self, precision: Precision) -> String {
+        match precision {
+            Precision::Int8 => self.version.to_string(),
+            Precision::Fp32 => format!("{}-fp32", self.version),
+        }
+    }
+
+    /// Precision of an index whose manifest records `model`; None for other models.
+    pub fn precision_of(&self, model: &str) -> Option<Precision> {
+        [Precision::Int8, Precision::Fp32]
+            .into_iter()
+            .find(|p| self.version_for(*p) == model)
+    }
+}
+
 /// Embedding output: variable-length token embeddings per document.
 /// Each document produces (num_tokens, token_dim) embeddings.
 pub struct TokenEmbeddings {
@@ -48,11 +106,26 @@ pub trait Embedder: Send + Sync {
 
     /// Embed a query, returning token embeddings.
     fn embed_query(&self, text: &str) -> Result<Array2<f32>>;
+
+    /// Embed several queries, in order. Backends override this to share one
+    /// model run across the batch.
+    fn embed_queries(&self, texts: &[&str]) -> Result<Vec<Array2<f32>>> {
+        texts.iter().map(|t| self.embed_query(t)).collect()
+    }
+
+    /// Query length in tokens before truncation to `query_max_length`.
+    fn count_query_tokens(&self, text: &str) -> Result<usize>;
+
+    /// Batches `embed_documents` can run at once; callers pass this many
+    /// batches per call to keep them busy.
+    fn concurrency(&self) -> usize {
+        1
+    }
 }
 
 /// Create the embedder, downloading model files if needed.
-pub fn create_embedder() -> Result<Box<dyn Embedder>> {
-    let (model_path, tokenizer_path) = download_model_files(MODEL)?;
+pub fn create_embedder(precision: Precision) -> Result<Box<dyn Embedder>> {
+    let (model_path, tokenizer_path) = download_model_files(MODEL, precision)?;
     Ok(Box::new(onnx::OnnxEmbedder::new(
         &model_path,
         &tokenizer_path,
@@ -61,18 +134,21 @@ pub fn create_embedder() -> Result<Box<dyn Embedder>> {
 }
 
 /// Download both model and tokenizer files, returning their local paths.
-fn download_model_files(config: &ModelConfig) -> Result<(String, String)> {
+fn download_model_files(config: &ModelConfig, precision: Precision) -> Result<(String, String)> {
+    let model_file = config
+        .model_file_for(precision)
+        .with_context(|| format!("{} has no {} model variant", config.repo, precision.name()))?;
     let api = hf_hub::api::sync::Api::new().context("Failed to create HF Hub API")?;
     let repo = api.model(config.repo.to_string());
 
-    let model_path = repo.get(config.model_file).with_context(|| {
+    let model_path = download(&repo, model_file).with_context(|| {
         format!(
-            "Failed to download model from {}. Run 'og model install' while online.",
+            "Failed to download {model_file} from {}. Run 'og model install' while online.",
             config.repo
         )
     })?;
 
-    let tokenizer_path = repo.get(config.tokenizer_file).with_context(|| {
+    let tokenizer_path = download(&repo, config.tokenizer_file).with_context(|| {
         format!(
             "Failed to download tokenizer from {}. Run 'og model install' while online.",
             config.repo
@@ -84,3 +160,93 @@ fn download_model_files(config: &ModelConfig) -> Result<(String, String)> {
         tokenizer_path.to_string_lossy().into_owned(),
     ))
 }
+
+/// Env var overriding the number of download attempts per file.
+pub const DOWNLOAD_ATTEMPTS_ENV: &str = "OG_DOWNLOAD_ATTEMPTS";
+const DEFAULT_DOWNLOAD_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry; doubled for each later one.
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Fetch `filename` from the HF Hub (or its cache), retrying transient
+/// network failures with exponential backoff. Retries are reported on stderr.
+pub fn download(repo: &ApiRepo, filename: &str) -> Result<PathBuf, ApiError> {
+    let attempts = std::env::var(DOWNLOAD_ATTEMPTS_ENV)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_DOWNLOAD_ATTEMPTS)
+        .max(1);
+    let mut attempt = 1;
+    loop {
+        match repo.get(filename) {
+            Ok(path) => return Ok(path),
+            Err(e) if attempt < attempts && is_transient(&e) => {
+                let delay = retry_delay(attempt);
+                eprintln!(
+                    "Downloading {filename} failed ({e}); retrying in {}s (attempt {}/{attempts})",
+                    delay.as_secs(),
+                    attempt + 1
+                );
+                std::thread::sleep(delay);
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn retry_delay(attempt: u32) -> Duration {
+    RETRY_BASE_DELAY * 2u32.saturating_pow(attempt - 1)
+}
+
+/// Network and server-side failures are worth retrying; 4xx responses
+/// (missing file, auth) and malformed requests are not.
+fn is_transient(error: &ApiError) -> bool {
+    match error {
+        ApiError::RequestError(e) => match e.as_ref() {
+            ureq::Error::Status(code, _) => *code == 429 || *code >= 500,
+            ureq::Error::Transport(t) => !matches!(
+                t.kind(),
+                ureq::ErrorKind::InvalidUrl
+                    | ureq::ErrorKind::UnknownScheme
+                    | ureq::ErrorKind::InsecureRequestHttpsOnly
+            ),
+        },
+        ApiError::IoError(_) => true,
+        ApiError::TooManyRetries(inner) => is_transient(inner),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status_error(code: u16) -> ApiError {
+        let response = ureq::Response::new(code, "status", "").unwrap();
+        ApiError::RequestError(Box::new(ureq::Error::Status(code, response)))
+    }
+
+    #[test]
+    fn only_transient_errors_retry() {
+        assert!(!is_transient(&status_error(404)));
+        assert!(!is_transient(&status_error(401)));
+        assert!(is_transient(&status_error(503)));
+        assert!(is_transient(&status_error(429)));
+        assert!(is_transient(&ApiError::IoError(std::io::Error::other(
+            "connection reset"
+        ))));
+        assert_eq!(retry_delay(1), Duration::from_secs(1));
+        assert_eq!(retry_delay(3), Duration::from_secs(4));
+    }
+
+    #[test]
+    fn precision_round_trips_through_model_string() {
+        for precision in [Precision::Int8, Precision::Fp32] {
+            let version = MODEL.version_for(precision);
+            assert_eq!(MODEL.precision_of(&version), Some(precision));
+        }
+        assert_eq!(MODEL.version_for(Precision::Int8), MODEL.version);
+        assert_eq!(MODEL.precision_of("other-model-v2"), None);
+    }
+}
@@ -1,35 +1,71 @@
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
 
 use anyhow::{Context, Result};
 use ndarray::Array2;
+use ort::session::Session;
 use ort::value::TensorRef;
+use rayon::prelude::*;
 
 use super::tokenizer::TokenizerWrapper;
 use super::{Embedder, ModelConfig, TokenEmbeddings};
 use tokenizers::Encoding;
 
+/// Env var setting how many ONNX sessions embed document batches concurrently.
+pub const SESSIONS_ENV: &str = "OG_EMBED_SESSIONS";
+
 /// ONNX-based embedder for LateOn-Code models.
+///
+/// A session runs one batch at a time, but already spreads it over every core
+/// (intra-op threads), so one session is the default. With `OG_EMBED_SESSIONS=n`
+/// the cores are split across `n` sessions that embed batches in parallel,
+/// which helps when small batches leave cores idle. Each session loads its
+/// own copy of the model, so resident memory for the model grows n-fold.
 pub struct OnnxEmbedder {
-    session: Mutex<ort::session::Session>,
+    sessions: Vec<Mutex<Session>>,
+    /// Round-robin pick when every session is busy.
+    next: AtomicUsize,
     tokenizer: TokenizerWrapper,
     batch_size: usize,
 }
 
 impl OnnxEmbedder {
     pub fn new(model_path: &str, tokenizer_path: &str, config: &ModelConfig) -> Result<Self> {
-        let session = ort::session::Session::builder()?
-            .with_optimization_level(ort::session::builder::GraphOptimizationLevel::Level3)?
-            .with_intra_threads(num_cpus())?
-            .commit_from_file(model_path)
-            .context("Failed to load ONNX model")?;
+        let cpus = num_cpus();
+        let count = session_count(std::env::var(SESSIONS_ENV).ok().as_deref(), cpus);
+        let sessions = (0..count)
+            .map(|_| {
+                let session = Session::builder()?
+                    .with_optimization_level(ort::session::builder::GraphOptimizationLevel::Level3)?
+                    .with_intra_threads((cpus / count).max(1))?
+                    .commit_from_file(model_path)
+                    .context("Failed to load ONNX model")?;
+                Ok(Mutex::new(session))
+            })
+            .collect::<Result<Vec<_>>>()?;
         let tokenizer = TokenizerWrapper::new(tokenizer_path, config)?;
         Ok(Self {
-            session: Mutex::new(session),
+            sessions,
+            next: AtomicUsize::new(0),
             tokenizer,
             batch_size: config.batch_size,
         })
     }
 
+    /// A free session, or the next one round-robin when all are busy.
+    fn session(&self) -> Result<MutexGuard<'_, Session>> {
+        if let Some(session) = self.sessions.iter().find_map(|s| s.try_lock().ok()) {
+            return Ok(session);
+        }
+        let i = self.next.fetch_add(1, Ordering::Relaxed) % self.sessions.len();
+        self.sessions[i].lock().map_err(|e| anyhow::anyhow!("{e}"))
+    }
+
+    fn embed_document_batch(&self, texts: &[&str]) -> Result<Vec<Array2<f32>>> {
+        let encodings = self.tokenizer.encode_documents(texts)?;
+        Ok(self.embed_batch(encodings)?.embeddings)
+    }
+
     fn embed_batch(&self, encodings: Vec<Encoding>) -> Result<TokenEmbeddings> {
         let batch_size = encodings.len();
         let seq_len = encodings
@@ -58,7 +94,7 @@ impl OnnxEmbedder {
         // Run inference
         let input_ids_tensor = TensorRef::from_array_view(&input_ids)?;
         let attention_mask_tensor = TensorRef::from_array_view(&attention_mask)?;
-        let mut session = self.session.lock().map_err(|e| anyhow::anyhow!("{e}"))?;
+        let mut session = self.session()?;
         let outputs = session.run(ort::inputs![
             "input_ids" => input_ids_tensor,
             "attention_mask" => attention_mask_tensor,
@@ -92,19 +128,27 @@ impl OnnxEmbedder {
 
 impl Embedder for OnnxEmbedder {
     fn embed_documents(&self, texts: &[&str]) -> Result<TokenEmbeddings> {
-        let mut all_embeddings = Vec::with_capacity(texts.len());
-
-        for chunk in texts.chunks(self.batch_size) {
-            let encodings = self.tokenizer.encode_documents(chunk)?;
-            let batch_result = self.embed_batch(encodings)?;
-            all_embeddings.extend(batch_result.embeddings);
-        }
+        let batches: Vec<Vec<Array2<f32>>> = if self.sessions.len() > 1 {
+            texts
+                .par_chunks(self.batch_size)
+                .map(|chunk| self.embed_document_batch(chunk))
+                .collect::<Result<_>>()?
+        } else {
+            texts
+                .chunks(self.batch_size)
+                .map(|chunk| self.embed_document_batch(chunk))
+                .collect::<Result<_>>()?
+        };
 
         Ok(TokenEmbeddings {
-            embeddings: all_embeddings,
+            embeddings: batches.into_iter().flatten().collect(),
         })
     }
 
+    fn concurrency(&self) -> usize {
+        self.sessions.len()
+    }
+
     fn embed_query(&self, text: &str) -> Result<Array2<f32>> {
         let encoding = self.tokenizer.encode_query(text)?;
         let result = self.embed_batch(vec![encoding])?;
@@ -114,6 +158,19 @@ impl Embedder for OnnxEmbedder {
             .next()
             .context("No embedding produced for query")
     }
+
+    fn embed_queries(&self, texts: &[&str]) -> Result<Vec<Array2<f32>>> {
+        let mut all_embeddings = Vec::with_capacity(texts.len());
+        for chunk in texts.chunks(self.batch_size) {
+            let encodings = self.tokenizer.encode_queries(chunk)?;
+            all_embeddings.extend(self.embed_batch(encodings)?.embeddings);
+        }
+        Ok(all_embeddings)
+    }
+
+    fn count_query_tokens(&self, text: &str) -> Result<usize> {
+        self.tokenizer.count_query_tokens(text)
+    }
 }
 
 fn num_cpus() -> usize {
@@ -121,3 +178,26 @@ fn num_cpus() -> usize {
         .map(|n| n.get())
         .unwrap_or(4)
 }
+
+/// Sessions for an `OG_EMBED_SESSIONS` value: 1 when unset or invalid, at
+/// most one per core.
+fn session_count(value: Option<&str>, cpus: usize) -> usize {
+    value
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .unwrap_or(1)
+        .clamp(1, cpus.max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_count_defaults_and_caps() {
+        assert_eq!(session_count(None, 8), 1);
+        assert_eq!(session_count(Some("4"), 8), 4);
+        assert_eq!(session_count(Some("32"), 8), 8);
+        assert_eq!(session_count(Some("0"), 8), 1);
+        assert_eq!(session_count(Some("many"), 8), 1);
+    }
+}
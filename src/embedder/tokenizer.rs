@@ -8,6 +8,8 @@ use super::ModelConfig;
 pub struct TokenizerWrapper {
     doc_tokenizer: Tokenizer,
     query_tokenizer: Tokenizer,
+    /// No truncation or padding, for measuring input length.
+    base: Tokenizer,
 }
 
 impl TokenizerWrapper {
@@ -23,7 +25,7 @@ impl TokenizerWrapper {
             .map_err(|e| anyhow::anyhow!("{e}"))?;
         doc_tokenizer.with_padding(Some(tokenizers::PaddingParams::default()));
 
-        let mut query_tokenizer = base;
+        let mut query_tokenizer = base.clone();
         query_tokenizer
             .with_truncation(Some(tokenizers::TruncationParams {
                 max_length: config.query_max_length,
@@ -35,6 +37,7 @@ impl TokenizerWrapper {
         Ok(Self {
             doc_tokenizer,
             query_tokenizer,
+            base,
         })
     }
 
@@ -49,10 +52,29 @@ impl TokenizerWrapper {
             .map_err(|e| anyhow::anyhow!("{e}"))
     }
 
+    /// Untruncated token count of a query, including special tokens.
+    pub fn count_query_tokens(&self, text: &str) -> Result<usize> {
+        self.base
+            .encode(text, true)
+            .map(|e| e.len())
+            .map_err(|e| anyhow::anyhow!("{e}"))
+    }
+
     /// Encode a query (shorter max length).
     pub fn encode_query(&self, text: &str) -> Result<tokenizers::Encoding> {
         self.query_tokenizer
             .encode(text, true)
             .map_err(|e| anyhow::anyhow!("{e}"))
     }
+
+    /// Encode several queries at once.
+    pub fn encode_queries(&self, texts: &[&str]) -> Result<Vec<tokenizers::Encoding>> {
+        let inputs: Vec<tokenizers::EncodeInput> = texts
+            .iter()
+            .map(|t| tokenizers::EncodeInput::Single((*t).into()))
+            .collect();
+        self.query_tokenizer
+            .encode_batch(inputs, true)
+            .map_err(|e| anyhow::anyhow!("{e}"))
+    }
 }
@@ -0,0 +1,89 @@
+use tree_sitter::Node;
+
+/// Node kinds that make a nested function a method.
+const METHOD_PARENTS: &[&str] = &[
+    "class_definition",
+    "class_declaration",
+    "class_specifier",
+    "class_body",
+    "class",
+    "impl_item",
+    "trait_item",
+    "interface_declaration",
+    "protocol_declaration",
+    "object_declaration",
+    "struct_specifier",
+    "struct_declaration",
+    "trait_declaration",
+];
+
+/// Map a capture to a canonical block type (`function`, `method`, `class`,
+/// `struct`, `interface`, `trait`, `enum`) from the node kind, so output types
+/// are consistent across languages. Unknown kinds keep the capture name.
+pub fn normalize<'a>(capture: &'a str, node: &Node) -> &'a str {
+    match node.kind() {
+        "method_declaration" | "constructor_declaration" | "method" | "singleton_method" => {
+            "method"
+        }
+        "function_definition"
+        | "function_declaration"
+        | "function_item"
+        | "arrow_function"
+        | "decorated_definition" => {
+            if enclosed_by_type(node) {
+                "method"
+            } else {
+                "function"
+            }
+        }
+        "class_definition" | "class_declaration" | "class_specifier" | "class"
+        | "object_declaration" | "impl_item" => "class",
+        "struct_item" | "struct_specifier" | "struct_declaration" | "struct_definition" => "struct",
+        "interface_declaration" | "protocol_declaration" => "interface",
+        "trait_item" | "trait_declaration" => "trait",
+        "enum_item" | "enum_specifier" | "enum_declaration" => "enum",
+        "type_declaration" => go_type_kind(node),
+        _ => capture,
+    }
+}
+
+/// Go `type Foo struct {}` / `type Foo interface {}`.
+fn go_type_kind(node: &Node) -> &'static str {
+    let mut cursor = node.walk();
+    for spec in node.named_children(&mut cursor) {
+        if let Some(ty) = spec.child_by_field_name("type") {
+            match ty.kind() {
+                "struct_type" => return "struct",
+                "interface_type" => return "interface",
+                _ => {}
+            }
+        }
+    }
+    "class"
+}
+
+/// Node kinds whose bodies start a new function scope.
+const FUNCTION_KINDS: &[&str] = &[
+    "function_definition",
+    "function_declaration",
+    "function_item",
+    "arrow_function",
+    "method_declaration",
+    "method",
+];
+
+/// Whether the nearest enclosing function-or-type ancestor is a type
+/// (so a closure inside a method stays a function).
+fn enclosed_by_type(node: &Node) -> bool {
+    let mut current = node.parent();
+    while let Some(n) = current {
+        if METHOD_PARENTS.contains(&n.kind()) {
+            return true;
+        }
+        if FUNCTION_KINDS.contains(&n.kind()) {
+            return false;
+        }
+        current = n.parent();
+    }
+    false
+}
@@ -0,0 +1,118 @@
+use crate::types::Block;
+
+use super::CONTAINER_TYPES;
+
+/// Line prefixes that mark an import/include statement.
+const IMPORT_PREFIXES: &[&str] = &[
+    "import ", "from ", "use ", "pub use ", "#include", "using ", "require ", "package ",
+];
+
+/// Only scan the top of the file for imports.
+const IMPORT_SCAN_LINES: usize = 100;
+
+/// Cap on import lines added to each block's context.
+const MAX_IMPORT_LINES: usize = 10;
+
+/// Import lines from the top of `content`, in file order.
+pub fn leading_imports(content: &str) -> Vec<&str> {
+    content
+        .lines()
+        .take(IMPORT_SCAN_LINES)
+        .map(str::trim)
+        .filter(|l| IMPORT_PREFIXES.iter().any(|p| l.starts_with(p)) || l.contains("require("))
+        .take(MAX_IMPORT_LINES)
+        .collect()
+}
+
+/// Set `context` on each non-container block: the file's leading imports plus
+/// the innermost enclosing container (e.g. "in class UserService").
+/// Must run before nested containers are removed.
+pub fn annotate(blocks: &mut [Block], content: &str) {
+    let imports = leading_imports(content).join("\n");
+
+    let parents: Vec<Option<String>> = blocks
+        .iter()
+        .map(|block| {
+            if CONTAINER_TYPES.contains(&block.block_type.as_str()) {
+                return None;
+            }
+            blocks
+                .iter()
+                .filter(|p| {
+                    CONTAINER_TYPES.contains(&p.block_type.as_str())
+                        && p.start_line <= block.start_line
+                        && p.end_line >= block.end_line
+                        && (p.start_line, p.end_line) != (block.start_line, block.end_line)
+                })
+                .min_by_key(|p| p.end_line - p.start_line)
+                .map(|p| format!("in {} {}", p.block_type, p.name))
+        })
+        .collect();
+
+    for (block, parent) in blocks.iter_mut().zip(parents) {
+        if CONTAINER_TYPES.contains(&block.block_type.as_str()) {
+            continue;
+        }
+        let parts: Vec<&str> = [imports.as_str(), parent.as_deref().unwrap_or("")]
+            .into_iter()
+            .filter(|s| !s.is_empty())
+            .collect();
+        if !parts.is_empty() {
+            block.context = Some(parts.join("\n"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(block_type: &str, name: &str, start_line: usize, end_line: usize) -> Block {
+        Block {
+            id: Block::make_id("a.py", name, ""),
+            file: "a.py".to_string(),
+            block_type: block_type.to_string(),
+            name: name.to_string(),
+            start_line,
+            end_line,
+            content: String::new(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn imports_from_top_of_file() {
+        let src = "\"\"\"Doc.\"\"\"\nimport os\nfrom typing import List\n\nx = 1\n";
+        assert_eq!(
+            leading_imports(src),
+            ["import os", "from typing import List"]
+        );
+    }
+
+    #[test]
+    fn js_require_counts_as_import() {
+        let src = "const fs = require('fs');\nfunction f() {}\n";
+        assert_eq!(leading_imports(src), ["const fs = require('fs');"]);
+    }
+
+    #[test]
+    fn method_gets_enclosing_class() {
+        let mut blocks = vec![
+            block("class", "UserService", 2, 10),
+            block("function", "get_user", 3, 5),
+        ];
+        annotate(&mut blocks, "import db\n\nclass UserService:\n");
+        assert_eq!(blocks[0].context, None);
+        assert_eq!(
+            blocks[1].context.as_deref(),
+            Some("import db\nin class UserService")
+        );
+    }
+
+    #[test]
+    fn no_context_when_nothing_found() {
+        let mut blocks = vec![block("function", "main", 0, 2)];
+        annotate(&mut blocks, "def main():\n    pass\n");
+        assert_eq!(blocks[0].context, None);
+    }
+}
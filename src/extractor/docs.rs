@@ -0,0 +1,149 @@
+use crate::types::Block;
+
+/// Cap on doc comment lines added to a block's context.
+const MAX_DOC_LINES: usize = 20;
+
+/// Python docstring delimiters.
+const DOCSTRING_QUOTES: &[&str] = &["\"\"\"", "'''"];
+
+/// Prepend each block's doc comment to its `context` so it leads the
+/// embedding text. `content` is the parsed source; `line_offset` is where it
+/// starts in the file (non-zero for single-file component regions).
+/// Must run after `context::annotate`, which replaces `context`.
+pub fn attach(blocks: &mut [Block], content: &str, line_offset: usize) {
+    let lines: Vec<&str> = content.lines().collect();
+    for block in blocks {
+        let doc = leading_doc(&lines, block.start_line.saturating_sub(line_offset))
+            .or_else(|| docstring(&block.content));
+        let Some(doc) = doc else { continue };
+        block.context = Some(match block.context.take() {
+            Some(context) => format!("{doc}\n{context}"),
+            None => doc,
+        });
+    }
+}
+
+/// `///` or `/** */` comment lines directly above `start_line`, skipping
+/// attributes and decorators in between.
+fn leading_doc(lines: &[&str], start_line: usize) -> Option<String> {
+    let mut doc: Vec<&str> = Vec::new();
+    let mut in_block = false;
+    for line in lines[..start_line.min(lines.len())].iter().rev() {
+        let line = line.trim();
+        if in_block {
+            let text = line
+                .trim_start_matches("/**")
+                .trim_start_matches('*')
+                .trim();
+            if !text.is_empty() {
+                doc.push(text);
+            }
+            if line.starts_with("/**") {
+                in_block = false;
+                continue;
+            }
+            if line.starts_with("/*") {
+                // A plain block comment, not a doc comment
+                return None;
+            }
+        } else if let Some(text) = line.strip_prefix("///") {
+            doc.push(text.trim());
+        } else if line.ends_with("*/") {
+            let text = line.trim_end_matches("*/").trim();
+            if let Some(text) = text.strip_prefix("/**") {
+                doc.push(text.trim());
+            } else if text.starts_with("/*") {
+                break;
+            } else {
+                in_block = true;
+                let text = text.trim_start_matches('*').trim();
+                if !text.is_empty() {
+                    doc.push(text);
+                }
+            }
+        } else if doc.is_empty() && (line.starts_with("#[") || line.starts_with('@')) {
+            continue;
+        } else {
+            break;
+        }
+    }
+    finish(doc.into_iter().rev())
+}
+
+/// A Python docstring: the first statement after the `def`/`class` line.
+fn docstring(content: &str) -> Option<String> {
+    let mut lines = content
+        .lines()
+        .skip_while(|l| !l.trim_end().ends_with(':'))
+        .skip(1)
+        .map(str::trim)
+        .skip_while(|l| l.is_empty());
+    let first = lines.next()?;
+    let quote = DOCSTRING_QUOTES.iter().find(|q| first.starts_with(**q))?;
+    let first = &first[quote.len()..];
+    if let Some(end) = first.find(quote) {
+        return finish(std::iter::once(&first[..end]));
+    }
+
+    let mut doc = vec![first];
+    for line in lines {
+        if let Some(end) = line.find(quote) {
+            doc.push(&line[..end]);
+            break;
+        }
+        doc.push(line);
+    }
+    finish(doc.into_iter())
+}
+
+fn finish<'a>(lines: impl Iterator<Item = &'a str>) -> Option<String> {
+    let doc: Vec<&str> = lines
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .take(MAX_DOC_LINES)
+        .collect();
+    (!doc.is_empty()).then(|| doc.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rust_doc_above_attributes() {
+        let src =
+            "/// Parse the config.\n/// Returns defaults on error.\n#[inline]\nfn parse() {}\n";
+        let lines: Vec<&str> = src.lines().collect();
+        assert_eq!(
+            leading_doc(&lines, 3).as_deref(),
+            Some("Parse the config.\nReturns defaults on error.")
+        );
+    }
+
+    #[test]
+    fn jsdoc_block() {
+        let src = "/**\n * Fetch a user.\n * @param id user id\n */\nfunction getUser(id) {}\n";
+        let lines: Vec<&str> = src.lines().collect();
+        assert_eq!(
+            leading_doc(&lines, 4).as_deref(),
+            Some("Fetch a user.\n@param id user id")
+        );
+
+        let plain = "/* license */\nfunction f() {}\n";
+        let lines: Vec<&str> = plain.lines().collect();
+        assert_eq!(leading_doc(&lines, 1), None);
+    }
+
+    #[test]
+    fn python_docstring() {
+        assert_eq!(
+            docstring("def f():\n    \"\"\"Load the cache.\"\"\"\n    pass\n").as_deref(),
+            Some("Load the cache.")
+        );
+        assert_eq!(
+            docstring("def f(\n    x,\n):\n    '''\n    Multi\n    line.\n    '''\n").as_deref(),
+            Some("Multi\nline.")
+        );
+        assert_eq!(docstring("def f():\n    return 1\n"), None);
+    }
+}
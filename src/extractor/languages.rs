@@ -27,6 +27,57 @@ pub fn get_language(ext: &str) -> Option<Language> {
         ".html" | ".htm" => Some(tree_sitter_html::LANGUAGE.into()),
         ".css" => Some(tree_sitter_css::LANGUAGE.into()),
         ".hcl" | ".tf" => Some(tree_sitter_hcl::LANGUAGE.into()),
+        ".ml" => Some(tree_sitter_ocaml::LANGUAGE_OCAML.into()),
+        ".mli" => Some(tree_sitter_ocaml::LANGUAGE_OCAML_INTERFACE.into()),
+        ".hs" => Some(tree_sitter_haskell::LANGUAGE.into()),
+        ".erl" | ".hrl" => Some(tree_sitter_erlang::LANGUAGE.into()),
+        ".proto" => Some(tree_sitter_proto::LANGUAGE.into()),
         _ => None,
     }
 }
+
+/// Extension implied by a script's shebang line (`#!/usr/bin/env python3`,
+/// `#!/bin/bash`), for extensionless or unknown-extension files. Only the
+/// first line is read.
+pub fn shebang_ext(content: &str) -> Option<&'static str> {
+    let line = content.lines().next()?.strip_prefix("#!")?;
+    let mut args = line.split_whitespace();
+    let mut interpreter = args.next()?.rsplit('/').next()?;
+    if interpreter == "env" {
+        // Skip env flags such as `-S`
+        interpreter = args.find(|a| !a.starts_with('-'))?;
+    }
+    // python3.12 -> python
+    let name = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    match name {
+        "python" | "pypy" => Some(".py"),
+        "sh" | "bash" | "zsh" | "dash" | "ksh" | "ash" => Some(".sh"),
+        "node" | "nodejs" | "deno" | "bun" => Some(".js"),
+        "ts-node" | "tsx" => Some(".ts"),
+        "ruby" => Some(".rb"),
+        "php" => Some(".php"),
+        "lua" | "luajit" => Some(".lua"),
+        "elixir" => Some(".exs"),
+        "ocaml" => Some(".ml"),
+        "runghc" | "runhaskell" => Some(".hs"),
+        "escript" => Some(".erl"),
+        _ => None,
+    }
+}
+
+/// Language recorded on a file's blocks (`--lang`): the query language name
+/// (`rust`, `python`, `csharp`, ...) with OCaml interfaces folded into `ocaml`,
+/// plus `markdown`, `text` and `rst` docs and `vue`/`svelte` components.
+pub fn language_tag(ext: &str) -> Option<&'static str> {
+    Some(match ext {
+        ".md" | ".mdx" | ".markdown" => "markdown",
+        ".txt" => "text",
+        ".rst" => "rst",
+        ".vue" => "vue",
+        ".svelte" => "svelte",
+        _ => match super::queries::language_name(ext)? {
+            "ocaml_interface" => "ocaml",
+            name => name,
+        },
+    })
+}
@@ -1,22 +1,39 @@
+pub mod block_types;
+pub mod context;
+pub mod docs;
 pub mod languages;
 pub mod queries;
+pub mod sfc;
 pub mod text;
 
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Arc;
 
 use anyhow::Result;
 use tree_sitter::{Language, Parser, Query, StreamingIterator};
 
-use crate::types::Block;
+use crate::types::{Block, IndexOptions};
 
 use languages::get_language;
 use queries::get_query_source;
+use sfc::SFC_EXTENSIONS;
 use text::TEXT_EXTENSIONS;
 
 /// Extracts code blocks from source files using tree-sitter.
 pub struct Extractor {
     /// Cached parsers per extension.
-    parsers: std::collections::HashMap<String, (Parser, Language, Option<Query>)>,
+    parsers: HashMap<String, (Parser, Language, Option<Query>)>,
+    /// Lines per block for the line-based fallback.
+    fallback_lines: usize,
+    /// Attach import/enclosing-type context to code blocks.
+    embed_context: bool,
+    /// Prepend leading doc comments and docstrings to the embedding text.
+    embed_docs: bool,
+    /// Drop top-level constant/variable captures.
+    skip_constants: bool,
+    /// Project query overrides by language name (`.og/queries/<lang>.scm`).
+    custom_queries: Arc<HashMap<String, String>>,
 }
 
 impl Default for Extractor {
@@ -27,14 +44,29 @@ impl Default for Extractor {
 
 impl Extractor {
     pub fn new() -> Self {
+        Self::with_options(&IndexOptions::default())
+    }
+
+    pub fn with_options(options: &IndexOptions) -> Self {
         Self {
-            parsers: std::collections::HashMap::new(),
+            parsers: HashMap::new(),
+            fallback_lines: options.fallback_lines.max(1),
+            embed_context: options.embed_context,
+            embed_docs: options.embed_docs,
+            skip_constants: options.skip_constants,
+            custom_queries: Arc::default(),
         }
     }
 
+    /// Use these queries (by language name) in place of the built-ins.
+    pub fn with_custom_queries(mut self, queries: Arc<HashMap<String, String>>) -> Self {
+        self.custom_queries = queries;
+        self
+    }
+
     /// Extract blocks from a file.
     pub fn extract(&mut self, file_path: &str, content: &str) -> Result<Vec<Block>> {
-        let ext = Path::new(file_path)
+        let mut ext = Path::new(file_path)
             .extension()
             .and_then(|e| e.to_str())
             .map(|e| format!(".{}", e.to_lowercase()))
@@ -42,32 +74,71 @@ impl Extractor {
 
         let rel_path = file_path;
 
-        // Text/doc files: use chunk-based extraction
-        if TEXT_EXTENSIONS.contains(&ext.as_str()) {
-            return Ok(text::extract_text_blocks(file_path, content));
+        let mut blocks = if TEXT_EXTENSIONS.contains(&ext.as_str()) {
+            // Text/doc files: use chunk-based extraction
+            text::extract_text_blocks(file_path, content)
+        } else if SFC_EXTENSIONS.contains(&ext.as_str()) {
+            // Single-file components: split into script/template/style regions
+            self.extract_sfc(rel_path, content)?
+        } else {
+            // Extensionless scripts (bin/deploy): detect the language from the shebang
+            if get_language(&ext).is_none() {
+                if let Some(script_ext) = languages::shebang_ext(content) {
+                    ext = script_ext.to_string();
+                }
+            }
+            match self.extract_tree_sitter(rel_path, &ext, content, 0)? {
+                Some(blocks) => blocks,
+                None => fallback_chunks(rel_path, content, self.fallback_lines),
+            }
+        };
+
+        if let Some(language) = languages::language_tag(&ext) {
+            for block in &mut blocks {
+                block.language = Some(language.to_string());
+            }
         }
+        Block::assign_ids(&mut blocks);
+        Ok(blocks)
+    }
 
+    /// Run the tree-sitter query for `ext` over `content`.
+    /// `line_offset` shifts reported lines when `content` is a region of a larger file.
+    /// Returns None when the language is unsupported or nothing was captured.
+    fn extract_tree_sitter(
+        &mut self,
+        rel_path: &str,
+        ext: &str,
+        content: &str,
+        line_offset: usize,
+    ) -> Result<Option<Vec<Block>>> {
         // Ensure parser is initialized for this extension
-        if !self.parsers.contains_key(&ext) {
-            if let Some(language) = get_language(&ext) {
+        if !self.parsers.contains_key(ext) {
+            if let Some(language) = get_language(ext) {
                 let mut parser = Parser::new();
                 parser.set_language(&language)?;
-                let query = get_query_source(&ext).and_then(|qs| Query::new(&language, qs).ok());
-                self.parsers.insert(ext.clone(), (parser, language, query));
+                let custom = queries::language_name(ext)
+                    .and_then(|lang| self.custom_queries.get(lang))
+                    .and_then(|qs| Query::new(&language, qs).ok());
+                let query = custom.or_else(|| {
+                    get_query_source(ext).and_then(|qs| Query::new(&language, qs).ok())
+                });
+                self.parsers
+                    .insert(ext.to_string(), (parser, language, query));
             }
         }
 
-        let Some((parser, _language, query)) = self.parsers.get_mut(&ext) else {
-            return Ok(fallback_head(rel_path, content));
+        let Some((parser, _language, query)) = self.parsers.get_mut(ext) else {
+            return Ok(None);
         };
 
         let Some(query) = query else {
-            return Ok(fallback_head(rel_path, content));
+            return Ok(None);
         };
 
         let content_bytes = content.as_bytes();
         let Some(tree) = parser.parse(content_bytes, None) else {
-            return Ok(fallback_head(rel_path, content));
+            return Ok(None);
         };
 
         let mut cursor = tree_sitter::QueryCursor::new();
@@ -78,6 +149,11 @@ impl Extractor {
 
         while let Some(m) = matches.next() {
             for capture in m.captures {
+                let capture_name = query.capture_names()[capture.index as usize];
+                if self.skip_constants && capture_name == CONSTANT_TYPE {
+                    continue;
+                }
+
                 let node = capture.node;
                 let range = (node.start_byte(), node.end_byte());
                 if !seen_ranges.insert(range) {
@@ -88,32 +164,92 @@ impl Extractor {
                 let node_content = &content_bytes[node.start_byte()..node.end_byte()];
                 let node_text = String::from_utf8_lossy(node_content).into_owned();
 
-                let capture_name = query.capture_names()[capture.index as usize];
-                let block_type = capture_name;
+                let block_type = block_types::normalize(capture_name, &node);
 
-                let start_line = node.start_position().row;
-                let end_line = node.end_position().row;
+                let start_line = node.start_position().row + line_offset;
+                let end_line = node.end_position().row + line_offset;
 
                 blocks.push(Block {
-                    id: Block::make_id(rel_path, start_line, &name),
+                    id: Block::make_id(rel_path, &name, &node_text),
                     file: rel_path.to_string(),
                     block_type: block_type.to_string(),
                     name,
                     start_line,
                     end_line,
                     content: node_text,
+                    capture: Some(node.kind().to_string()),
+                    ..Default::default()
                 });
             }
         }
 
         if blocks.is_empty() {
-            return Ok(fallback_head(rel_path, content));
+            return Ok(None);
+        }
+
+        // Annotate before nesting removal so enclosing containers are still present
+        if self.embed_context {
+            context::annotate(&mut blocks, content);
+        }
+        if self.embed_docs {
+            docs::attach(&mut blocks, content, line_offset);
         }
 
         // Remove outer blocks whose content is fully covered by inner blocks.
         // E.g., a class block contains all its method blocks — keep methods, drop class.
-        blocks = remove_nested_blocks(blocks);
+        Ok(Some(remove_nested_blocks(blocks)))
+    }
+
+    /// Extract blocks from a Vue/Svelte single-file component.
+    /// Script regions go through the JS/TS query; template and style regions
+    /// become one block each. Every block records its source region.
+    fn extract_sfc(&mut self, rel_path: &str, content: &str) -> Result<Vec<Block>> {
+        let component = Path::new(rel_path)
+            .file_stem()
+            .and_then(|n| n.to_str())
+            .unwrap_or("component");
+
+        let mut blocks = Vec::new();
+        for region in sfc::split_regions(content) {
+            if region.kind == "script" {
+                let ext = region.script_ext();
+                if let Some(script_blocks) =
+                    self.extract_tree_sitter(rel_path, ext, region.content, region.start_line)?
+                {
+                    blocks.extend(script_blocks.into_iter().map(|mut b| {
+                        b.region = Some(region.kind.to_string());
+                        b
+                    }));
+                    continue;
+                }
+            }
+
+            let text = region.content.trim();
+            if text.is_empty() {
+                continue;
+            }
+            // Skip leading blank lines so start_line points at real content
+            let start_line = region.start_line
+                + region.content[..region.content.find(text).unwrap_or(0)]
+                    .matches('\n')
+                    .count();
+
+            blocks.push(Block {
+                id: Block::make_id(rel_path, component, text),
+                file: rel_path.to_string(),
+                block_type: region.kind.to_string(),
+                name: component.to_string(),
+                start_line,
+                end_line: region.end_line,
+                content: text.to_string(),
+                region: Some(region.kind.to_string()),
+                ..Default::default()
+            });
+        }
 
+        if blocks.is_empty() {
+            return Ok(fallback_chunks(rel_path, content, self.fallback_lines));
+        }
         Ok(blocks)
     }
 }
@@ -121,7 +257,10 @@ impl Extractor {
 /// Container block types that should be removed when they have children.
 /// Functions/methods are NOT containers — a decorated_definition wrapping
 /// a function_definition should keep the outer (decorated) block.
-const CONTAINER_TYPES: &[&str] = &[
+/// Block type for top-level constant/variable declarations.
+const CONSTANT_TYPE: &str = "constant";
+
+pub(crate) const CONTAINER_TYPES: &[&str] = &[
     "class",
     "struct",
     "module",
@@ -153,8 +292,10 @@ fn remove_nested_blocks(mut blocks: Vec<Block>) -> Vec<Block> {
         if !keep[i] {
             continue;
         }
-        // Only container types get dropped when they have children
-        if !CONTAINER_TYPES.contains(&blocks[i].block_type.as_str()) {
+        // Only container types get dropped when they have children; a constant is
+        // dropped when its value is a captured block (`const f = () => {}`)
+        let is_constant = blocks[i].block_type == CONSTANT_TYPE;
+        if !is_constant && !CONTAINER_TYPES.contains(&blocks[i].block_type.as_str()) {
             continue;
         }
         for j in (i + 1)..blocks.len() {
@@ -164,9 +305,13 @@ fn remove_nested_blocks(mut blocks: Vec<Block>) -> Vec<Block> {
             if !keep[j] {
                 continue;
             }
+            if is_constant && blocks[j].start_line != blocks[i].start_line {
+                continue;
+            }
             if blocks[j].start_line >= blocks[i].start_line
                 && blocks[j].end_line <= blocks[i].end_line
-                && (blocks[j].start_line != blocks[i].start_line
+                && (is_constant
+                    || blocks[j].start_line != blocks[i].start_line
                     || blocks[j].end_line != blocks[i].end_line)
             {
                 keep[i] = false;
@@ -182,8 +327,50 @@ fn remove_nested_blocks(mut blocks: Vec<Block>) -> Vec<Block> {
         .collect()
 }
 
+/// Line prefixes treated as comments when counting a block's tokens.
+const COMMENT_PREFIXES: &[&str] = &["//", "#", "/*", "*", "--"];
+
+/// Words in `content` outside comment lines.
+fn code_tokens(content: &str) -> usize {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !COMMENT_PREFIXES.iter().any(|p| l.starts_with(p)))
+        .flat_map(|l| l.split(|c: char| !c.is_alphanumeric() && c != '_'))
+        .filter(|w| !w.is_empty())
+        .count()
+}
+
+/// Drop blocks with fewer than `min_tokens` code tokens (empty `impl`s, stub
+/// functions), keeping at least one block so the file stays indexed.
+/// Returns the number of blocks dropped.
+pub fn prune_trivial_blocks(blocks: &mut Vec<Block>, min_tokens: usize) -> usize {
+    let before = blocks.len();
+    let mut kept: Vec<Block> = Vec::with_capacity(before);
+    let mut largest: Option<Block> = None;
+    for block in blocks.drain(..) {
+        if code_tokens(&block.content) >= min_tokens {
+            kept.push(block);
+        } else if largest
+            .as_ref()
+            .is_none_or(|l| block.content.len() > l.content.len())
+        {
+            largest = Some(block);
+        }
+    }
+    if kept.is_empty() {
+        kept.extend(largest);
+    }
+    *blocks = kept;
+    before - blocks.len()
+}
+
 /// Extract the name identifier from a tree-sitter node.
 fn extract_name(node: &tree_sitter::Node, source: &[u8]) -> String {
+    if let Some(name) = declaration_name(node, source) {
+        return name;
+    }
+
     let name_types = [
         "identifier",
         "name",
@@ -223,22 +410,370 @@ fn extract_name(node: &tree_sitter::Node, source: &[u8]) -> String {
     "anonymous".to_string()
 }
 
-/// Fallback: return first 50 lines as a single block.
-fn fallback_head(file_path: &str, content: &str) -> Vec<Block> {
-    let lines: Vec<&str> = content.lines().take(50).collect();
-    let end_line = lines.len().saturating_sub(1);
+/// Names of OCaml, Haskell, Erlang and protobuf declarations, which sit under
+/// a binding node or in node kinds the generic search in `extract_name` doesn't know.
+fn declaration_name(node: &tree_sitter::Node, source: &[u8]) -> Option<String> {
+    let name = match node.kind() {
+        // OCaml `let f x = ...`
+        "let_binding" => node.child_by_field_name("pattern"),
+        // OCaml interface `val f : ...`
+        "value_specification" => node.named_child(0),
+        // OCaml `type t = ...` / `module M = ...` wrap a type_binding / module_binding
+        "type_definition" | "module_definition" => {
+            let mut cursor = node.walk();
+            let binding = node
+                .named_children(&mut cursor)
+                .find(|c| matches!(c.kind(), "type_binding" | "module_binding"))?;
+            binding
+                .child_by_field_name("name")
+                .or_else(|| binding.named_child(0))
+        }
+        // Erlang `fun_decl` -> `function_clause` -> name atom
+        "fun_decl" => node.named_child(0)?.child_by_field_name("name"),
+        // Haskell `f x = ...`, `data T = ...`
+        "function" | "bind" | "data_type" | "newtype" | "type_synomym" => {
+            node.child_by_field_name("name")
+        }
+        // Protobuf `message Foo {}`, `rpc Get(...)`: a `<kind>_name` child
+        "message" | "enum" | "service" | "rpc" => {
+            let kind = format!("{}_name", node.kind());
+            let mut cursor = node.walk();
+            let name = node.named_children(&mut cursor).find(|c| c.kind() == kind);
+            name
+        }
+        _ => None,
+    }?;
+    name.utf8_text(source).ok().map(str::to_string)
+}
+
+/// Fallback: split the file into sequential blocks of `lines_per_block` lines,
+/// so large unsupported files stay searchable beyond their head.
+fn fallback_chunks(file_path: &str, content: &str, lines_per_block: usize) -> Vec<Block> {
+    let lines: Vec<&str> = content.lines().collect();
     let name = Path::new(file_path)
         .file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("unknown");
 
-    vec![Block {
-        id: Block::make_id(file_path, 0, name),
-        file: file_path.to_string(),
-        block_type: "file".to_string(),
-        name: name.to_string(),
-        start_line: 0,
-        end_line,
-        content: lines.join("\n"),
-    }]
+    if lines.is_empty() {
+        return vec![Block {
+            id: Block::make_id(file_path, name, ""),
+            file: file_path.to_string(),
+            block_type: "file".to_string(),
+            name: name.to_string(),
+            start_line: 0,
+            end_line: 0,
+            content: String::new(),
+            ..Default::default()
+        }];
+    }
+
+    lines
+        .chunks(lines_per_block)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let start_line = i * lines_per_block;
+            let content = chunk.join("\n");
+            Block {
+                id: Block::make_id(file_path, name, &content),
+                file: file_path.to_string(),
+                block_type: "file".to_string(),
+                name: name.to_string(),
+                start_line,
+                end_line: start_line + chunk.len() - 1,
+                content,
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fallback_splits_long_files() {
+        let content: String = (0..120).map(|i| format!("key_{i} = {i}\n")).collect();
+        let blocks = fallback_chunks("config.ini", &content, 50);
+        assert_eq!(blocks.len(), 3);
+        assert_eq!((blocks[0].start_line, blocks[0].end_line), (0, 49));
+        assert_eq!((blocks[2].start_line, blocks[2].end_line), (100, 119));
+        assert!(blocks[2].content.starts_with("key_100 = 100"));
+    }
+
+    #[test]
+    fn fallback_short_file_single_block() {
+        let blocks = fallback_chunks("notes.cfg", "a = 1\nb = 2\n", 50);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].end_line, 1);
+    }
+
+    #[test]
+    fn custom_query_overrides_builtin() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("rust.scm"), "(macro_definition) @macro").unwrap();
+        let custom = queries::load_custom(tmp.path()).unwrap();
+        let src = "macro_rules! retry { () => {} }\n\nfn main() {}\n";
+        let blocks = Extractor::new()
+            .with_custom_queries(Arc::new(custom))
+            .extract("a.rs", src)
+            .unwrap();
+        let blocks: Vec<(&str, &str)> = blocks
+            .iter()
+            .map(|b| (b.name.as_str(), b.block_type.as_str()))
+            .collect();
+        assert_eq!(blocks, [("retry", "macro")]);
+        // Other languages keep their built-in queries
+        assert!(!Extractor::new()
+            .extract("a.py", "def f():\n    pass\n")
+            .unwrap()
+            .is_empty());
+
+        std::fs::write(tmp.path().join("klingon.scm"), "(x) @function").unwrap();
+        assert!(queries::load_custom(tmp.path()).is_err());
+    }
+
+    fn types(path: &str, content: &str) -> Vec<(String, String)> {
+        let mut blocks = Extractor::new().extract(path, content).unwrap();
+        blocks.sort_by_key(|b| b.start_line);
+        blocks.into_iter().map(|b| (b.name, b.block_type)).collect()
+    }
+
+    #[test]
+    fn python_methods_normalized() {
+        let src = "class Repo:\n    def get(self):\n        pass\n\ndef main():\n    pass\n";
+        let types = types("a.py", src);
+        assert!(types.contains(&("get".into(), "method".into())));
+        assert!(types.contains(&("main".into(), "function".into())));
+    }
+
+    #[test]
+    fn rust_types_normalized() {
+        let src = "struct Point { x: i32 }\ntrait Shape { fn area(&self) -> f64; }\nenum Color { Red }\nimpl Point {\n    fn new() -> Self { Point { x: 0 } }\n}\nfn main() {}\n";
+        let types = types("a.rs", src);
+        assert!(types.contains(&("Point".into(), "struct".into())));
+        assert!(types.contains(&("Shape".into(), "trait".into())));
+        assert!(types.contains(&("Color".into(), "enum".into())));
+        assert!(types.contains(&("new".into(), "method".into())));
+        assert!(types.contains(&("main".into(), "function".into())));
+    }
+
+    #[test]
+    fn raw_node_kind_kept() {
+        let src = "trait Shape { fn area(&self) -> f64; }\nstruct Point { x: i32 }\n";
+        let blocks = Extractor::new().extract("a.rs", src).unwrap();
+        let shape = blocks.iter().find(|b| b.name == "Shape").unwrap();
+        assert_eq!(shape.block_type, "trait");
+        assert_eq!(shape.capture.as_deref(), Some("trait_item"));
+    }
+
+    #[test]
+    fn go_type_declarations_normalized() {
+        let src = "package main\n\ntype Server struct {\n\tport int\n}\n\ntype Handler interface {\n\tServe()\n}\n";
+        let types = types("a.go", src);
+        assert!(types.contains(&("Server".into(), "struct".into())));
+        assert!(types.contains(&("Handler".into(), "interface".into())));
+    }
+
+    #[test]
+    fn top_level_constants_extracted() {
+        let py = types(
+            "a.py",
+            "DEFAULT_TIMEOUT = 30
+
+def f():
+    local = 1
+",
+        );
+        assert!(py.contains(&("DEFAULT_TIMEOUT".into(), "constant".into())));
+        assert!(!py.iter().any(|(name, _)| name == "local"));
+
+        let rs = types(
+            "a.rs",
+            "const MAX: usize = 5;
+static NAME: &str = \"x\";
+fn f() {}
+",
+        );
+        assert!(rs.contains(&("MAX".into(), "constant".into())));
+        assert!(rs.contains(&("NAME".into(), "constant".into())));
+
+        let go = types(
+            "a.go",
+            "package main
+
+const Port = 8080
+",
+        );
+        assert!(go.contains(&("Port".into(), "constant".into())));
+    }
+
+    #[test]
+    fn function_valued_constants_not_duplicated() {
+        let src = "export const API_BASE_URL = \"https://x\";
+export const handler = () => {
+  return 1;
+};
+";
+        let ts = types("a.ts", src);
+        assert!(ts.contains(&("API_BASE_URL".into(), "constant".into())));
+        assert!(!ts.contains(&("handler".into(), "constant".into())));
+        assert_eq!(ts.len(), 2);
+    }
+
+    #[test]
+    fn c_macros_are_blocks() {
+        let src = "#ifndef CONFIG_H
+#define CONFIG_H
+#define MAX_RETRIES 5
+#define MIN(a, b) ((a) < (b) ? (a) : (b))
+int f(void) { return MAX_RETRIES; }
+#endif
+";
+        for file in ["config.h", "config.hpp"] {
+            let blocks = types(file, src);
+            assert!(blocks.contains(&("MAX_RETRIES".into(), "macro".into())));
+            assert!(blocks.contains(&("MIN".into(), "macro".into())));
+            // Include guards carry no value and are not blocks
+            assert!(!blocks.iter().any(|(name, _)| name == "CONFIG_H"));
+        }
+    }
+
+    #[test]
+    fn constants_can_be_skipped() {
+        let options = IndexOptions {
+            skip_constants: true,
+            ..IndexOptions::default()
+        };
+        let blocks = Extractor::with_options(&options)
+            .extract(
+                "a.rs",
+                "const MAX: usize = 5;
+fn f() {}
+",
+            )
+            .unwrap();
+        assert!(blocks.iter().all(|b| b.block_type != "constant"));
+    }
+
+    #[test]
+    fn trivial_blocks_pruned() {
+        let src = "impl Point {}\n\n// Build a point.\nfn new() -> Point { Point { x: 0 } }\nfn stub() {}\n";
+        let mut blocks = Extractor::new().extract("a.rs", src).unwrap();
+        assert_eq!(prune_trivial_blocks(&mut blocks, 3), 2);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].name, "new");
+
+        // The file keeps one block even when every block is trivial
+        let mut blocks = Extractor::new().extract("b.rs", "fn a() {}\n").unwrap();
+        assert_eq!(prune_trivial_blocks(&mut blocks, 3), 0);
+        assert_eq!(blocks.len(), 1);
+    }
+
+    #[test]
+    fn shebang_picks_language_for_extensionless_scripts() {
+        let bash = types(
+            "bin/deploy",
+            "#!/bin/bash
+deploy() {
+  echo hi
+}
+",
+        );
+        assert!(bash.contains(&("deploy".into(), "function".into())));
+
+        let py = types(
+            "scripts/release",
+            "#!/usr/bin/env -S python3.12 -u
+def main():
+    pass
+",
+        );
+        assert!(py.contains(&("main".into(), "function".into())));
+
+        assert_eq!(
+            languages::shebang_ext(
+                "#!/usr/bin/env node
+"
+            ),
+            Some(".js")
+        );
+        assert_eq!(
+            languages::shebang_ext("#!/usr/bin/env escript\n"),
+            Some(".erl")
+        );
+        assert_eq!(
+            languages::shebang_ext(
+                "#!/usr/bin/perl
+"
+            ),
+            None
+        );
+        assert_eq!(
+            languages::shebang_ext(
+                "echo hi
+#!/bin/sh
+"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn uppercase_extensions_route_like_lowercase() {
+        let py = types("MAIN.PY", "def main():\n    pass\n");
+        assert!(py.contains(&("main".into(), "function".into())));
+
+        let java = types("Foo.Java", "class Foo {\n    void run() {}\n}\n");
+        assert!(java.contains(&("run".into(), "method".into())));
+
+        let prose = "Run the installer with default options, then restart the shell. ".repeat(4);
+        let md = Extractor::new()
+            .extract("README.MD", &format!("# Install\n\n{prose}\n"))
+            .unwrap();
+        assert!(!md.is_empty());
+        assert!(md.iter().all(|b| b.block_type == "section"));
+
+        let vue = Extractor::new()
+            .extract(
+                "App.VUE",
+                "<script lang=\"TS\">\nfunction greet(): string { return 'hi'; }\n</script>\n",
+            )
+            .unwrap();
+        assert!(vue.iter().any(|b| b.name == "greet"));
+    }
+
+    #[test]
+    fn blocks_record_language() {
+        let languages = |path: &str, content: &str| -> Vec<Option<String>> {
+            Extractor::new()
+                .extract(path, content)
+                .unwrap()
+                .into_iter()
+                .map(|b| b.language)
+                .collect()
+        };
+        let rust = Some("rust".to_string());
+        assert_eq!(languages("src/lib.rs", "fn main() {}\n"), [rust]);
+        let script = languages(
+            "bin/release",
+            "#!/usr/bin/env python3\ndef main():\n    pass\n",
+        );
+        assert!(script.iter().all(|l| l.as_deref() == Some("python")));
+        let notes = languages("notes.unknown", &"x\n".repeat(5));
+        assert!(!notes.is_empty() && notes.iter().all(Option::is_none));
+        assert_eq!(languages::language_tag(".markdown"), Some("markdown"));
+        assert_eq!(languages::language_tag(".hpp"), Some("cpp"));
+        assert_eq!(languages::language_tag(".mli"), Some("ocaml"));
+    }
+
+    #[test]
+    fn fallback_unique_ids() {
+        let content = "x\n".repeat(30);
+        let mut blocks = fallback_chunks("data.conf", &content, 10);
+        Block::assign_ids(&mut blocks);
+        let ids: std::collections::HashSet<&str> = blocks.iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(ids.len(), blocks.len());
+    }
 }
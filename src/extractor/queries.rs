@@ -1,7 +1,52 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+use tree_sitter::Query;
+
+use super::languages::get_language;
+
+/// Languages with built-in queries, each with one extension it parses as.
+const QUERY_LANGUAGES: &[(&str, &str)] = &[
+    ("python", ".py"),
+    ("javascript", ".js"),
+    ("typescript", ".ts"),
+    ("rust", ".rs"),
+    ("go", ".go"),
+    ("c", ".c"),
+    ("cpp", ".cpp"),
+    ("java", ".java"),
+    ("ruby", ".rb"),
+    ("csharp", ".cs"),
+    ("bash", ".sh"),
+    ("php", ".php"),
+    ("kotlin", ".kt"),
+    ("lua", ".lua"),
+    ("swift", ".swift"),
+    ("elixir", ".ex"),
+    ("zig", ".zig"),
+    ("yaml", ".yaml"),
+    ("toml", ".toml"),
+    ("json", ".json"),
+    ("html", ".html"),
+    ("css", ".css"),
+    ("hcl", ".hcl"),
+    ("julia", ".jl"),
+    ("ocaml", ".ml"),
+    ("ocaml_interface", ".mli"),
+    ("haskell", ".hs"),
+    ("erlang", ".erl"),
+    ("proto", ".proto"),
+];
+
 /// Get tree-sitter query source for a file extension.
 pub fn get_query_source(ext: &str) -> Option<&'static str> {
-    // Map extension to language name, then get query
-    let lang = match ext {
+    language_name(ext).and_then(get_query_for_language)
+}
+
+/// Query language name for a file extension (the `<lang>` of `<lang>.scm`).
+pub fn language_name(ext: &str) -> Option<&'static str> {
+    Some(match ext {
         ".py" => "python",
         ".js" | ".jsx" | ".mjs" => "javascript",
         ".ts" | ".tsx" => "typescript",
@@ -26,9 +71,48 @@ pub fn get_query_source(ext: &str) -> Option<&'static str> {
         ".css" => "css",
         ".hcl" | ".tf" => "hcl",
         ".jl" => "julia",
+        ".ml" => "ocaml",
+        ".mli" => "ocaml_interface",
+        ".hs" => "haskell",
+        ".erl" | ".hrl" => "erlang",
+        ".proto" => "proto",
         _ => return None,
-    };
-    get_query_for_language(lang)
+    })
+}
+
+/// Load project query overrides from `dir` (`.og/queries/`): one
+/// `<lang>.scm` file per language, replacing its built-in query. Each file
+/// must name a known language and compile, so mistakes surface at build time.
+pub fn load_custom(dir: &Path) -> Result<HashMap<String, String>> {
+    let mut custom = HashMap::new();
+    if !dir.is_dir() {
+        return Ok(custom);
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("scm") {
+            continue;
+        }
+        let lang = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default();
+        let Some(&(_, ext)) = QUERY_LANGUAGES.iter().find(|(name, _)| *name == lang) else {
+            let known: Vec<&str> = QUERY_LANGUAGES.iter().map(|(name, _)| *name).collect();
+            bail!(
+                "Unknown query language '{lang}' in {} (expected one of: {})",
+                path.display(),
+                known.join(", ")
+            );
+        };
+        let source = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let language = get_language(ext).ok_or_else(|| anyhow!("No parser for {lang}"))?;
+        Query::new(&language, &source)
+            .map_err(|e| anyhow!("Invalid query in {}: {e}", path.display()))?;
+        custom.insert(lang.to_string(), source);
+    }
+    Ok(custom)
 }
 
 fn get_query_for_language(lang: &str) -> Option<&'static str> {
@@ -38,6 +122,7 @@ fn get_query_for_language(lang: &str) -> Option<&'static str> {
             (function_definition) @function
             (class_definition) @class
             (decorated_definition) @function
+            (module (expression_statement (assignment)) @constant)
             "#
         }
         "javascript" => {
@@ -45,6 +130,9 @@ fn get_query_for_language(lang: &str) -> Option<&'static str> {
             (function_declaration) @function
             (class_declaration) @class
             (arrow_function) @function
+            (program (lexical_declaration) @constant)
+            (program (variable_declaration) @constant)
+            (program (export_statement (lexical_declaration) @constant))
             "#
         }
         "typescript" => {
@@ -53,6 +141,9 @@ fn get_query_for_language(lang: &str) -> Option<&'static str> {
             (class_declaration) @class
             (interface_declaration) @class
             (arrow_function) @function
+            (program (lexical_declaration) @constant)
+            (program (variable_declaration) @constant)
+            (program (export_statement (lexical_declaration) @constant))
             "#
         }
         "rust" => {
@@ -62,6 +153,8 @@ fn get_query_for_language(lang: &str) -> Option<&'static str> {
             (struct_item) @class
             (trait_item) @class
             (enum_item) @class
+            (source_file (const_item) @constant)
+            (source_file (static_item) @constant)
             "#
         }
         "go" => {
@@ -69,6 +162,8 @@ fn get_query_for_language(lang: &str) -> Option<&'static str> {
             (function_declaration) @function
             (method_declaration) @function
             (type_declaration) @class
+            (source_file (const_declaration) @constant)
+            (source_file (var_declaration) @constant)
             "#
         }
         "c" => {
@@ -76,6 +171,8 @@ fn get_query_for_language(lang: &str) -> Option<&'static str> {
             (function_definition) @function
             (struct_specifier) @class
             (enum_specifier) @class
+            (preproc_def value: (_)) @macro
+            (preproc_function_def) @macro
             "#
         }
         "cpp" => {
@@ -83,6 +180,8 @@ fn get_query_for_language(lang: &str) -> Option<&'static str> {
             (function_definition) @function
             (class_specifier) @class
             (struct_specifier) @class
+            (preproc_def value: (_)) @macro
+            (preproc_function_def) @macro
             "#
         }
         "java" => {
@@ -154,9 +253,9 @@ fn get_query_for_language(lang: &str) -> Option<&'static str> {
             (struct_declaration) @class
             "#
         }
-        "yaml" => return None, // fallback_head — too many tiny blocks from tree-sitter
+        "yaml" => return None, // fallback_chunks — too many tiny blocks from tree-sitter
         "toml" => "(table) @item",
-        "json" => return None, // fallback_head — JSON isn't semantically searchable
+        "json" => return None, // fallback_chunks — JSON isn't semantically searchable
         "html" => {
             r#"
             (element) @element
@@ -173,6 +272,41 @@ fn get_query_for_language(lang: &str) -> Option<&'static str> {
             (module_definition) @class
             "#
         }
+        // Top-level and module-level bindings only; `let ... in` locals stay in their function
+        "ocaml" => {
+            r#"
+            (compilation_unit (value_definition (let_binding) @function))
+            (structure (value_definition (let_binding) @function))
+            (type_definition) @class
+            (module_definition) @class
+            "#
+        }
+        "ocaml_interface" => {
+            r#"
+            (value_specification) @function
+            (type_definition) @class
+            (module_definition) @class
+            "#
+        }
+        "haskell" => {
+            r#"
+            (declarations (function) @function)
+            (declarations (bind) @constant)
+            (declarations (data_type) @class)
+            (declarations (newtype) @class)
+            (declarations (type_synomym) @class)
+            "#
+        }
+        "erlang" => "(fun_decl) @function",
+        // Services drop out as containers, leaving one block per RPC
+        "proto" => {
+            r#"
+            (message) @struct
+            (enum) @enum
+            (service) @interface
+            (rpc) @method
+            "#
+        }
         "sql" => "(statement) @statement",
         _ => return None,
     })
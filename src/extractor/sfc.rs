@@ -0,0 +1,223 @@
+/// File extensions for single-file components (Vue, Svelte).
+pub const SFC_EXTENSIONS: &[&str] = &[".vue", ".svelte"];
+
+/// Top-level tags that delimit SFC regions.
+const REGION_TAGS: &[&str] = &["script", "template", "style"];
+
+/// A top-level region of a single-file component.
+pub struct Region<'a> {
+    /// Region kind: "script", "template", or "style".
+    pub kind: &'static str,
+    /// Value of the tag's `lang` attribute, if any.
+    pub lang: Option<String>,
+    /// Inner content (between the opening and closing tags).
+    pub content: &'a str,
+    /// Line of the first content byte (0-indexed).
+    pub start_line: usize,
+    /// Line of the last content byte (0-indexed).
+    pub end_line: usize,
+}
+
+impl Region<'_> {
+    /// Extension used to route script content through tree-sitter.
+    pub fn script_ext(&self) -> &'static str {
+        match self.lang.as_deref().map(str::to_ascii_lowercase).as_deref() {
+            Some("ts" | "typescript") => ".ts",
+            Some("tsx") => ".tsx",
+            _ => ".js",
+        }
+    }
+}
+
+/// Split an SFC into its `<script>`, `<template>`, and `<style>` regions by tag scanning.
+///
+/// Non-whitespace markup outside these tags (Svelte components have no `<template>`
+/// wrapper) is returned as a "template" region.
+pub fn split_regions(content: &str) -> Vec<Region<'_>> {
+    let mut regions = Vec::new();
+    let mut pos = 0;
+    let mut markup_start = 0;
+
+    while let Some(offset) = content[pos..].find('<') {
+        let tag_start = pos + offset;
+        let Some(kind) = region_tag_at(content, tag_start) else {
+            pos = tag_start + 1;
+            continue;
+        };
+        let Some(open_end) = content[tag_start..].find('>').map(|i| tag_start + i + 1) else {
+            break;
+        };
+        let Some((inner_end, close_end)) = find_close(content, open_end, kind) else {
+            break;
+        };
+
+        push_markup(&mut regions, content, markup_start, tag_start);
+
+        let attrs = &content[tag_start + 1 + kind.len()..open_end - 1];
+        regions.push(make_region(
+            content,
+            kind,
+            parse_lang(attrs),
+            open_end,
+            inner_end,
+        ));
+
+        pos = close_end;
+        markup_start = close_end;
+    }
+
+    push_markup(&mut regions, content, markup_start, content.len());
+    regions
+}
+
+/// Returns the region tag name if `content[at..]` opens one of `REGION_TAGS`.
+/// Compares bytes, since the text after `<` need not be ASCII.
+fn region_tag_at(content: &str, at: usize) -> Option<&'static str> {
+    let rest = &content.as_bytes()[at + 1..];
+    REGION_TAGS.iter().copied().find(|tag| {
+        rest.get(..tag.len())
+            .is_some_and(|b| b.eq_ignore_ascii_case(tag.as_bytes()))
+            && matches!(
+                rest.get(tag.len()),
+                Some(b'>' | b' ' | b'\t' | b'\n' | b'\r')
+            )
+    })
+}
+
+/// Find the closing tag for `kind`, counting nested tags of the same name
+/// (Vue allows `<template v-if>` inside the root template).
+/// Returns (start of closing tag, end of closing tag).
+fn find_close(content: &str, from: usize, kind: &str) -> Option<(usize, usize)> {
+    let open = format!("<{kind}");
+    let close = format!("</{kind}>");
+    let mut depth = 0;
+    let mut pos = from;
+
+    loop {
+        let next_close = content[pos..].find(&close).map(|i| pos + i)?;
+        let next_open = content[pos..next_close]
+            .find(&open)
+            .map(|i| pos + i)
+            .filter(|&i| region_tag_at(content, i) == Some(kind));
+
+        match next_open {
+            Some(i) => {
+                depth += 1;
+                pos = i + open.len();
+            }
+            None if depth == 0 => return Some((next_close, next_close + close.len())),
+            None => {
+                depth -= 1;
+                pos = next_close + close.len();
+            }
+        }
+    }
+}
+
+/// Extract the `lang` attribute value from an opening tag's attributes.
+fn parse_lang(attrs: &str) -> Option<String> {
+    let idx = attrs.find("lang=")?;
+    let value = &attrs[idx + "lang=".len()..];
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'');
+    let value = match quote {
+        Some(q) => value[1..].split(q).next().unwrap_or(""),
+        None => value
+            .split(|c: char| c.is_whitespace())
+            .next()
+            .unwrap_or(""),
+    };
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_lowercase())
+    }
+}
+
+fn push_markup<'a>(regions: &mut Vec<Region<'a>>, content: &'a str, start: usize, end: usize) {
+    if content[start..end].trim().is_empty() {
+        return;
+    }
+    regions.push(make_region(content, "template", None, start, end));
+}
+
+fn make_region<'a>(
+    content: &'a str,
+    kind: &'static str,
+    lang: Option<String>,
+    start: usize,
+    end: usize,
+) -> Region<'a> {
+    let start_line = content[..start].matches('\n').count();
+    let inner = &content[start..end];
+    Region {
+        kind,
+        lang,
+        content: inner,
+        start_line,
+        end_line: start_line + inner.trim_end().matches('\n').count(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VUE: &str = r#"<template>
+  <div>
+    <template v-if="ok"><span>{{ msg }}</span></template>
+  </div>
+</template>
+
+<script setup lang="ts">
+function greet(name: string) {
+  return `hi ${name}`;
+}
+</script>
+
+<style scoped>
+.x { color: red; }
+</style>
+"#;
+
+    #[test]
+    fn vue_regions() {
+        let regions = split_regions(VUE);
+        let kinds: Vec<&str> = regions.iter().map(|r| r.kind).collect();
+        assert_eq!(kinds, ["template", "script", "style"]);
+    }
+
+    #[test]
+    fn vue_nested_template() {
+        let regions = split_regions(VUE);
+        assert!(regions[0].content.contains("v-if"));
+        assert!(regions[0].content.contains("</div>"));
+    }
+
+    #[test]
+    fn vue_script_lang_and_lines() {
+        let regions = split_regions(VUE);
+        let script = &regions[1];
+        assert_eq!(script.script_ext(), ".ts");
+        assert!(script.content.contains("function greet"));
+        // Content begins right after `<script setup lang="ts">` on line 6
+        assert_eq!(script.start_line, 6);
+    }
+
+    #[test]
+    fn svelte_markup_outside_tags() {
+        let svelte = "<script>\nlet count = 0;\n</script>\n\n<button on:click={() => count++}>{count}</button>\n";
+        let regions = split_regions(svelte);
+        let kinds: Vec<&str> = regions.iter().map(|r| r.kind).collect();
+        assert_eq!(kinds, ["script", "template"]);
+        assert_eq!(regions[0].script_ext(), ".js");
+        assert!(regions[1].content.contains("<button"));
+    }
+
+    #[test]
+    fn multibyte_text_after_angle_bracket() {
+        let vue =
+            "<template>\n  <p>a <中文字 b</p>\n</template>\n<script>\nlet x = 1;\n</script>\n";
+        let kinds: Vec<&str> = split_regions(vue).iter().map(|r| r.kind).collect();
+        assert_eq!(kinds, ["template", "script"]);
+    }
+}
@@ -12,8 +12,16 @@ const CHUNK_SIZE: usize = 400; // ~400 tokens target
 const CHUNK_OVERLAP: usize = 50; // ~50 tokens overlap
 const MIN_CHUNK_SIZE: usize = 30; // minimum tokens for a chunk
 
-/// Sentence boundary: split after `.` `!` `?` followed by whitespace.
-static SENTENCE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[.!?]\s+").unwrap());
+/// Full-width terminators (CJK) end a sentence without a following space.
+const FULL_WIDTH_TERMINATORS: &[char] = &['。', '！', '？', '．'];
+
+/// Terminators that end a sentence when followed by whitespace.
+const TERMINATORS: &[char] = &['.', '!', '?', '…'];
+
+/// Words ending in `.` that rarely end a sentence (compared lowercase).
+const ABBREVIATIONS: &[&str] = &[
+    "e.g.", "i.e.", "etc.", "vs.", "cf.", "approx.", "fig.", "no.", "mr.", "mrs.", "ms.", "dr.",
+];
 
 /// Fenced code block opener/closer.
 static FENCE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(`{3,}|~{3,})(\w+)?").unwrap());
@@ -36,6 +44,12 @@ pub fn extract_text_blocks(file_path: &str, content: &str) -> Vec<Block> {
     }
 }
 
+/// Split plain text into overlapping chunks of about `CHUNK_SIZE` tokens,
+/// keeping short ones (commit messages).
+pub fn chunk_plain_text(content: &str) -> Vec<String> {
+    add_overlap(&split_text_recursive(content, CHUNK_SIZE), CHUNK_OVERLAP)
+}
+
 fn estimate_tokens(text: &str) -> usize {
     (text.len() / 4).max(1)
 }
@@ -45,6 +59,58 @@ fn split_text_recursive(text: &str, chunk_size: usize) -> Vec<String> {
     split_with_separators(text, chunk_size, &separators)
 }
 
+/// Split after sentence terminators: `.` `!` `?` and ellipses followed by
+/// whitespace, and CJK full stops anywhere. Abbreviations like `e.g.` and an
+/// ellipsis followed by a lowercase word don't end a sentence. Each part
+/// keeps its trailing whitespace, so the parts concatenate back to `text`.
+fn split_sentences(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, n)| n);
+        // A run like "?!" or "。」" ends at its last terminator
+        if next.is_some_and(|n| TERMINATORS.contains(&n) || FULL_WIDTH_TERMINATORS.contains(&n)) {
+            continue;
+        }
+        let end = i + c.len_utf8();
+        let boundary = if FULL_WIDTH_TERMINATORS.contains(&c) {
+            true
+        } else if TERMINATORS.contains(&c) && next.is_some_and(char::is_whitespace) {
+            let word = text[start..end]
+                .rsplit(char::is_whitespace)
+                .next()
+                .unwrap_or_default();
+            let rest = text[end..].trim_start();
+            let abbreviation = ABBREVIATIONS.contains(&word.to_lowercase().as_str());
+            let ellipsis = word.ends_with("...") || word.ends_with('…');
+            let continues = ellipsis && rest.starts_with(char::is_lowercase);
+            !abbreviation && !continues
+        } else {
+            false
+        };
+        if !boundary {
+            continue;
+        }
+        // Attach the whitespace after the terminator to this sentence
+        let mut split = end;
+        while let Some(&(j, n)) = chars.peek() {
+            if !n.is_whitespace() {
+                break;
+            }
+            split = j + n.len_utf8();
+            chars.next();
+        }
+        parts.push(&text[start..split]);
+        start = split;
+    }
+    if start < text.len() {
+        parts.push(&text[start..]);
+    }
+    parts.retain(|p| !p.trim().is_empty());
+    parts
+}
+
 fn split_with_separators(
     text: &str,
     chunk_size: usize,
@@ -61,15 +127,12 @@ fn split_with_separators(
     for (i, sep) in separators.iter().enumerate() {
         let (parts, joiner) = match sep {
             None => {
-                // Sentence splitting: split after sentence-ending punctuation
-                let parts: Vec<&str> = SENTENCE_RE
-                    .split(text)
-                    .filter(|s| !s.trim().is_empty())
-                    .collect();
+                // Sentence splitting: parts keep their terminator and trailing space
+                let parts = split_sentences(text);
                 if parts.len() <= 1 {
                     continue;
                 }
-                (parts, " ")
+                (parts, "")
             }
             Some(s) => {
                 if !text.contains(s) {
@@ -289,13 +352,14 @@ fn extract_markdown_blocks(file_path: &str, content: &str) -> Vec<Block> {
             let content_with_context = format!("{prefix}\n{}", section.content);
 
             blocks.push(Block {
-                id: Block::make_id(file_path, section.start_line, lang),
+                id: Block::make_id(file_path, lang, &content_with_context),
                 file: file_path.to_string(),
                 block_type: "code".to_string(),
                 name: lang.to_string(),
                 start_line: section.start_line,
                 end_line: section.end_line,
                 content: content_with_context,
+                ..Default::default()
             });
             continue;
         }
@@ -303,7 +367,7 @@ fn extract_markdown_blocks(file_path: &str, content: &str) -> Vec<Block> {
         let chunks = split_text_recursive(&section.content, CHUNK_SIZE);
         let chunks = add_overlap(&chunks, CHUNK_OVERLAP);
 
-        for (chunk_idx, chunk) in chunks.iter().enumerate() {
+        for chunk in &chunks {
             if estimate_tokens(chunk) < MIN_CHUNK_SIZE {
                 continue;
             }
@@ -320,13 +384,14 @@ fn extract_markdown_blocks(file_path: &str, content: &str) -> Vec<Block> {
             };
 
             blocks.push(Block {
-                id: Block::make_id(file_path, section.start_line + chunk_idx, &name),
+                id: Block::make_id(file_path, &name, &content_with_context),
                 file: file_path.to_string(),
                 block_type: block_type.to_string(),
                 name,
                 start_line: section.start_line,
                 end_line: section.end_line,
                 content: content_with_context,
+                ..Default::default()
             });
         }
     }
@@ -352,13 +417,14 @@ fn extract_plain_text_blocks(file_path: &str, content: &str) -> Vec<Block> {
             .unwrap_or("text");
 
         blocks.push(Block {
-            id: Block::make_id(file_path, line_num, name),
+            id: Block::make_id(file_path, name, chunk),
             file: file_path.to_string(),
             block_type: "text".to_string(),
             name: name.to_string(),
             start_line: line_num,
             end_line: line_num + chunk_lines,
             content: chunk.clone(),
+            ..Default::default()
         });
 
         line_num += chunk_lines;
@@ -366,3 +432,46 @@ fn extract_plain_text_blocks(file_path: &str, content: &str) -> Vec<Block> {
 
     blocks
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sentences_split_on_latin_and_cjk_terminators() {
+        assert_eq!(
+            split_sentences("First one. Second? Third!"),
+            ["First one. ", "Second? ", "Third!"]
+        );
+        assert_eq!(
+            split_sentences("这是第一句。这是第二句！最后一句？"),
+            ["这是第一句。", "这是第二句！", "最后一句？"]
+        );
+        assert_eq!(
+            split_sentences("設定ファイルを読み込みます。Then it parses args. 完了。"),
+            [
+                "設定ファイルを読み込みます。",
+                "Then it parses args. ",
+                "完了。"
+            ]
+        );
+    }
+
+    #[test]
+    fn sentences_keep_abbreviations_and_trailing_ellipses() {
+        assert_eq!(
+            split_sentences("Use a cache, e.g. an LRU, i.e. bounded. Done."),
+            ["Use a cache, e.g. an LRU, i.e. bounded. ", "Done."]
+        );
+        assert_eq!(
+            split_sentences("Wait... then retry. Really?! Yes."),
+            ["Wait... then retry. ", "Really?! ", "Yes."]
+        );
+        assert_eq!(
+            split_sentences("It failed… Retry later."),
+            ["It failed… ", "Retry later."]
+        );
+        let text = "Ein Satz. Noch einer.\n\nZwei.";
+        assert_eq!(split_sentences(text).concat(), text);
+    }
+}
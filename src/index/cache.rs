@@ -0,0 +1,168 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::types::{SearchMode, SearchResult};
+
+/// Env var bounding the number of cached queries (0 disables the cache).
+pub const CACHE_SIZE_ENV: &str = "OG_CACHE_SIZE";
+const DEFAULT_CACHE_SIZE: usize = 64;
+
+/// Bumped on every manifest save, so results cached before an index or update
+/// in this process are never returned.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+static CACHE: Mutex<Lru> = Mutex::new(Lru {
+    entries: Vec::new(),
+});
+
+/// Everything that determines a search's results.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Key {
+    index_dir: PathBuf,
+    query: String,
+    k: usize,
+    scope: Option<String>,
+    mode: SearchMode,
+    alpha: Option<f32>,
+    /// Relative path of the `--context-file`, if any.
+    context: Option<String>,
+    generation: u64,
+    /// Catches updates made by other processes (e.g. a concurrent `og build`).
+    manifest_mtime: Option<SystemTime>,
+    /// `.og/acronyms.txt` is read at query time, so edits to it change results.
+    acronyms_mtime: Option<SystemTime>,
+}
+
+impl Key {
+    pub fn new(
+        index_dir: &Path,
+        query: &str,
+        k: usize,
+        scope: Option<&str>,
+        mode: SearchMode,
+        alpha: Option<f32>,
+        context: Option<&str>,
+    ) -> Self {
+        Self {
+            index_dir: index_dir.to_path_buf(),
+            query: query.to_string(),
+            k,
+            scope: scope.map(str::to_string),
+            mode,
+            alpha,
+            context: context.map(str::to_string),
+            generation: GENERATION.load(Ordering::SeqCst),
+            manifest_mtime: mtime(&super::manifest::Manifest::file_path(index_dir)),
+            acronyms_mtime: mtime(&index_dir.join(super::ACRONYMS_FILE)),
+        }
+    }
+}
+
+fn mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Most recently used entries last.
+struct Lru {
+    entries: Vec<(Key, Vec<SearchResult>)>,
+}
+
+impl Lru {
+    fn get(&mut self, key: &Key) -> Option<Vec<SearchResult>> {
+        let pos = self.entries.iter().position(|(k, _)| k == key)?;
+        let entry = self.entries.remove(pos);
+        let results = entry.1.clone();
+        self.entries.push(entry);
+        Some(results)
+    }
+
+    fn insert(&mut self, key: Key, results: Vec<SearchResult>, capacity: usize) {
+        self.entries.retain(|(k, _)| k != &key);
+        self.entries.push((key, results));
+        let excess = self.entries.len().saturating_sub(capacity);
+        self.entries.drain(..excess);
+    }
+}
+
+fn capacity() -> usize {
+    std::env::var(CACHE_SIZE_ENV)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_CACHE_SIZE)
+}
+
+pub fn get(key: &Key) -> Option<Vec<SearchResult>> {
+    CACHE.lock().ok()?.get(key)
+}
+
+pub fn insert(key: Key, results: Vec<SearchResult>) {
+    let capacity = capacity();
+    if capacity == 0 {
+        return;
+    }
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.insert(key, results, capacity);
+    }
+}
+
+/// Drop all cached results; called whenever an index changes.
+pub fn invalidate() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(query: &str) -> Key {
+        Key::new(
+            Path::new("/nonexistent/.og"),
+            query,
+            10,
+            None,
+            SearchMode::Hybrid,
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut lru = Lru {
+            entries: Vec::new(),
+        };
+        lru.insert(key("a"), Vec::new(), 2);
+        lru.insert(key("b"), Vec::new(), 2);
+        assert!(lru.get(&key("a")).is_some());
+        lru.insert(key("c"), Vec::new(), 2);
+
+        assert!(lru.get(&key("b")).is_none());
+        assert!(lru.get(&key("a")).is_some());
+        assert!(lru.get(&key("c")).is_some());
+    }
+
+    #[test]
+    fn generation_changes_key() {
+        let before = key("a");
+        invalidate();
+        assert_ne!(before, key("a"));
+    }
+
+    #[test]
+    fn acronyms_file_changes_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = || Key::new(dir.path(), "acl", 10, None, SearchMode::Hybrid, None, None);
+        let before = key();
+        std::fs::write(
+            dir.path().join(crate::index::ACRONYMS_FILE),
+            "ACL = access control list\n",
+        )
+        .unwrap();
+        assert_ne!(before, key());
+    }
+}
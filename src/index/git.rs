@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::extractor::text::chunk_plain_text;
+use crate::types::{Block, GitInfo};
+
+/// Manifest key prefix of commit messages indexed by `og build --with-commits`,
+/// followed by the abbreviated SHA. These paths don't exist on disk.
+pub const COMMIT_PREFIX: &str = "@commits/";
+
+/// Block type of commit message blocks.
+pub const COMMIT_TYPE: &str = "commit";
+
+/// Most recent commits indexed by `--with-commits`.
+const MAX_COMMITS: usize = 10_000;
+
+/// SHA length in commit keys.
+const SHORT_SHA: usize = 12;
+
+/// Blame entry per line: (author, author time), or None for uncommitted lines.
+type LineBlame = Option<(String, u64)>;
+
+/// Attach the dominant author and latest commit time to each block (`og build --with-git`).
+/// Files outside a git work tree or unknown to git are left without git info.
+pub fn annotate(root: &Path, file: &Path, blocks: &mut [Block]) {
+    let Some(lines) = blame(root, file) else {
+        return;
+    };
+    for block in blocks {
+        block.git = block_info(&lines, block.start_line, block.end_line);
+    }
+}
+
+fn blame(root: &Path, file: &Path) -> Option<Vec<LineBlame>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["blame", "--line-porcelain", "--"])
+        .arg(file)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(parse_porcelain(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parse `git blame --line-porcelain` output into one entry per source line.
+fn parse_porcelain(output: &str) -> Vec<LineBlame> {
+    let mut lines = Vec::new();
+    let mut author = String::new();
+    let mut time = 0;
+    let mut uncommitted = false;
+
+    for line in output.lines() {
+        if line.starts_with('\t') {
+            lines.push((!uncommitted).then(|| (author.clone(), time)));
+        } else if let Some(a) = line.strip_prefix("author ") {
+            author = a.to_string();
+        } else if let Some(t) = line.strip_prefix("author-time ") {
+            time = t.trim().parse().unwrap_or(0);
+        } else if line.len() >= 40 && line.as_bytes()[..40].iter().all(u8::is_ascii_hexdigit) {
+            // Header line: "<sha> <orig> <final> [<count>]"; all-zero sha = not committed
+            uncommitted = line.as_bytes()[..40].iter().all(|b| *b == b'0');
+        }
+    }
+    lines
+}
+
+/// Most frequent author and newest commit time over lines `start..=end` (0-indexed).
+fn block_info(lines: &[LineBlame], start: usize, end: usize) -> Option<GitInfo> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut latest = 0;
+    for (author, time) in lines
+        .get(start..=end.min(lines.len().checked_sub(1)?))?
+        .iter()
+        .flatten()
+    {
+        *counts.entry(author.as_str()).or_default() += 1;
+        latest = latest.max(*time);
+    }
+    let (author, _) = counts
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))?;
+    Some(GitInfo {
+        author: author.to_string(),
+        commit_time: latest,
+    })
+}
+
+/// Whether a manifest key is an indexed commit message, not a file.
+pub fn is_commit_path(rel_path: &str) -> bool {
+    rel_path.starts_with(COMMIT_PREFIX)
+}
+
+/// Recent commit messages under `root` as virtual files for `--with-commits`:
+/// `root/@commits/<sha>` -> (`git log`-style text, commit time). Empty outside
+/// a git work tree.
+pub fn commit_files(root: &Path) -> Vec<(PathBuf, (String, u64))> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["log", "-n", &MAX_COMMITS.to_string()])
+        .arg("--format=%H%x1f%an%x1f%ct%x1f%B%x1e")
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_log(&String::from_utf8_lossy(&output.stdout))
+                .into_iter()
+                .map(|(key, text, time)| (root.join(key), (text, time)))
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Parse `git log` output with unit-separated fields and record-separated
+/// commits into (manifest key, text, commit time).
+fn parse_log(output: &str) -> Vec<(String, String, u64)> {
+    output
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_start_matches('\n').splitn(4, '\x1f');
+            let sha = fields.next()?;
+            let author = fields.next()?;
+            let time = fields.next()?.parse().ok()?;
+            let message = fields.next()?.trim();
+            let key = format!("{COMMIT_PREFIX}{}", &sha[..sha.len().min(SHORT_SHA)]);
+            let text = format!("commit {sha}\nAuthor: {author}\n\n{message}\n");
+            Some((key, text, time))
+        })
+        .collect()
+}
+
+/// Blocks for one commit's text from `commit_files`: chunked like plain
+/// text, named by the subject line, with the author and commit time.
+pub fn commit_blocks(rel_path: &str, content: &str, commit_time: u64) -> Vec<Block> {
+    let mut lines = content.lines();
+    let author = lines
+        .find_map(|l| l.strip_prefix("Author: "))
+        .unwrap_or_default()
+        .to_string();
+    let subject = lines
+        .find(|l| !l.trim().is_empty())
+        .unwrap_or_default()
+        .trim();
+
+    let mut line_num = 0;
+    chunk_plain_text(content)
+        .into_iter()
+        .map(|chunk| {
+            let chunk_lines = chunk.matches('\n').count() + 1;
+            let block = Block {
+                id: Block::make_id(rel_path, subject, &chunk),
+                file: rel_path.to_string(),
+                block_type: COMMIT_TYPE.to_string(),
+                name: subject.to_string(),
+                start_line: line_num,
+                end_line: line_num + chunk_lines - 1,
+                content: chunk,
+                git: Some(GitInfo {
+                    author: author.clone(),
+                    commit_time,
+                }),
+                ..Default::default()
+            };
+            line_num += chunk_lines;
+            block
+        })
+        .collect()
+}
+
+/// Parse a `YYYY-MM-DD` date (UTC midnight) or a unix timestamp into seconds.
+pub fn parse_date(s: &str) -> Option<u64> {
+    if let Ok(ts) = s.parse::<u64>() {
+        return Some(ts);
+    }
+    let mut parts = s.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Days since 1970-01-01 (Howard Hinnant's days_from_civil)
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    u64::try_from(days * 86_400).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PORCELAIN: &str = "\
+1111111111111111111111111111111111111111 1 1 2
+author Alice
+author-mail <alice@example.com>
+author-time 1700000000
+summary init
+filename a.rs
+\tfn a() {
+1111111111111111111111111111111111111111 2 2
+author Alice
+author-time 1700000000
+filename a.rs
+\t}
+2222222222222222222222222222222222222222 3 3 1
+author Bob
+author-time 1710000000
+filename a.rs
+\tfn b() {}
+0000000000000000000000000000000000000000 4 4 1
+author Not Committed Yet
+author-time 1720000000
+filename a.rs
+\t// wip
+";
+
+    #[test]
+    fn parses_porcelain_lines() {
+        let lines = parse_porcelain(PORCELAIN);
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[2], Some(("Bob".to_string(), 1_710_000_000)));
+        assert_eq!(lines[3], None);
+    }
+
+    #[test]
+    fn block_info_dominant_author_latest_time() {
+        let lines = parse_porcelain(PORCELAIN);
+        let info = block_info(&lines, 0, 3).unwrap();
+        assert_eq!(info.author, "Alice");
+        assert_eq!(info.commit_time, 1_710_000_000);
+        assert!(block_info(&lines, 3, 3).is_none());
+    }
+
+    #[test]
+    fn commit_messages_become_blocks() {
+        let log = "abc123def4567890\x1fAlice\x1f1700000000\x1fAdd retry backoff\n\nRetries with jitter.\n\x1e\n\
+                   0123456789abcdef\x1fBob\x1f1690000000\x1fInitial commit\n\x1e\n";
+        let commits = parse_log(log);
+        assert_eq!(commits.len(), 2);
+        let (key, text, time) = &commits[0];
+        assert_eq!(key, "@commits/abc123def456");
+        assert_eq!(*time, 1_700_000_000);
+        assert!(is_commit_path(key));
+
+        let blocks = commit_blocks(key, text, *time);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].name, "Add retry backoff");
+        assert_eq!(blocks[0].block_type, COMMIT_TYPE);
+        assert!(blocks[0].content.contains("Retries with jitter."));
+        assert_eq!(
+            blocks[0].git,
+            Some(GitInfo {
+                author: "Alice".to_string(),
+                commit_time: 1_700_000_000,
+            })
+        );
+    }
+
+    #[test]
+    fn parse_dates() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("2024-03-01"), Some(1_709_251_200));
+        assert_eq!(parse_date("1700000000"), Some(1_700_000_000));
+        assert_eq!(parse_date("2024-13-01"), None);
+        assert_eq!(parse_date("yesterday"), None);
+    }
+}
@@ -1,19 +1,47 @@
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::embedder;
+use crate::embedder::{self, Precision};
+use crate::types::IndexOptions;
 
-pub const MANIFEST_VERSION: u32 = 10;
+pub const MANIFEST_VERSION: u32 = 16;
 const MANIFEST_FILE: &str = "manifest.json";
 
+/// Compact binary manifest (`og build --manifest-format bin`), used instead of
+/// `manifest.json` when present.
+const BINARY_MANIFEST_FILE: &str = "manifest.bin";
+const BINARY_MAGIC: &[u8; 4] = b"OGMB";
+
+/// Files hashed into the fingerprint's sample.
+const FINGERPRINT_SAMPLE: usize = 16;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Manifest {
     pub version: u32,
     pub model: String,
+    #[serde(default)]
+    pub options: IndexOptions,
     pub files: HashMap<String, FileEntry>,
+    /// Summary of the indexed tree, refreshed on save; None for older manifests.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<Fingerprint>,
+    /// Saved as `manifest.bin` rather than JSON; set when loaded from one.
+    #[serde(skip)]
+    pub binary: bool,
+}
+
+/// Identifies the tree an index was built from, so a copy used against a
+/// different checkout can be detected.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    /// Basename of the index root when last saved.
+    pub root: String,
+    pub files: usize,
+    /// Hash over the content hashes of `Manifest::sample_paths`.
+    pub sample_hash: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +50,9 @@ pub struct FileEntry {
     pub blocks: Vec<String>,
     #[serde(default)]
     pub mtime: u64,
+    /// Store shard holding the blocks (`og build --shard`); None when unsharded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shard: Option<String>,
 }
 
 impl Default for Manifest {
@@ -29,13 +60,110 @@ impl Default for Manifest {
         Self {
             version: MANIFEST_VERSION,
             model: embedder::MODEL.version.to_string(),
+            options: IndexOptions::default(),
             files: HashMap::new(),
+            fingerprint: None,
+            binary: false,
         }
     }
 }
 
+/// Summary of a manifest read without compatibility checks (for `og list`).
+#[derive(Debug, Clone)]
+pub struct ManifestInfo {
+    pub version: u32,
+    pub model: String,
+    pub blocks: usize,
+}
+
+impl ManifestInfo {
+    /// Whether the index must be rebuilt for this og (format or model changed).
+    pub fn needs_rebuild(&self) -> bool {
+        self.version != MANIFEST_VERSION || self.precision().is_none()
+    }
+
+    /// Model precision the index was built with; None for a different model.
+    pub fn precision(&self) -> Option<Precision> {
+        embedder::MODEL.precision_of(&self.model)
+    }
+}
+
 impl Manifest {
+    /// Whether `index_dir` holds a manifest in either format.
+    pub fn exists(index_dir: &Path) -> bool {
+        index_dir.join(BINARY_MANIFEST_FILE).exists() || index_dir.join(MANIFEST_FILE).exists()
+    }
+
+    /// Whether `index_dir` holds a binary manifest.
+    pub fn is_binary(index_dir: &Path) -> bool {
+        index_dir.join(BINARY_MANIFEST_FILE).exists()
+    }
+
+    /// Path of the manifest file in use (binary if present, else JSON).
+    pub fn file_path(index_dir: &Path) -> PathBuf {
+        if Self::is_binary(index_dir) {
+            index_dir.join(BINARY_MANIFEST_FILE)
+        } else {
+            index_dir.join(MANIFEST_FILE)
+        }
+    }
+
+    /// Read version, model, and block count from any manifest version.
+    pub fn info(index_dir: &Path) -> Result<ManifestInfo> {
+        let binary = index_dir.join(BINARY_MANIFEST_FILE);
+        if binary.exists() {
+            let data = std::fs::read(&binary)?;
+            let (version, mut reader) = binary::header(&data)?;
+            if version != MANIFEST_VERSION {
+                return Ok(ManifestInfo {
+                    version,
+                    model: reader.string().unwrap_or_default(),
+                    blocks: 0,
+                });
+            }
+            let manifest = binary::decode(version, reader)?;
+            return Ok(ManifestInfo {
+                version,
+                blocks: manifest.files.values().map(|e| e.blocks.len()).sum(),
+                model: manifest.model,
+            });
+        }
+
+        let content = std::fs::read_to_string(index_dir.join(MANIFEST_FILE))?;
+        let data: serde_json::Value = serde_json::from_str(&content)?;
+        let blocks = data
+            .get("files")
+            .and_then(|f| f.as_object())
+            .map(|files| {
+                files
+                    .values()
+                    .filter_map(|e| e.get("blocks").and_then(|b| b.as_array()))
+                    .map(|b| b.len())
+                    .sum()
+            })
+            .unwrap_or(0);
+        Ok(ManifestInfo {
+            version: data.get("version").and_then(|v| v.as_u64()).unwrap_or(1) as u32,
+            model: data
+                .get("model")
+                .and_then(|m| m.as_str())
+                .unwrap_or("")
+                .to_string(),
+            blocks,
+        })
+    }
+
     pub fn load(index_dir: &Path) -> Result<Self> {
+        let binary = index_dir.join(BINARY_MANIFEST_FILE);
+        if binary.exists() {
+            let data = std::fs::read(&binary)?;
+            let (version, reader) = binary::header(&data)?;
+            check_version(version, || {
+                binary::decode(version, reader.clone()).is_ok_and(|m| !m.files.is_empty())
+            })?;
+            return binary::decode(version, reader);
+        }
+
         let manifest_path = index_dir.join(MANIFEST_FILE);
 
         if !manifest_path.exists() {
@@ -50,36 +178,305 @@ impl Manifest {
         let data: serde_json::Value = serde_json::from_str(&content)?;
 
         let version = data.get("version").and_then(|v| v.as_u64()).unwrap_or(1) as u32;
+        check_version(version, || {
+            data.get("files")
+                .map(|f| f.as_object().is_some_and(|o| !o.is_empty()))
+                .unwrap_or(false)
+        })?;
 
-        if version > MANIFEST_VERSION {
-            bail!(
-                "Index was created by a newer version of og. \
-                 Please upgrade og or run 'og build --force' to rebuild."
-            );
+        let manifest: Manifest = serde_json::from_value(data)?;
+        Ok(manifest)
+    }
+
+    /// Model precision recorded in the manifest (INT8 for unknown models).
+    pub fn precision(&self) -> Precision {
+        embedder::MODEL
+            .precision_of(&self.model)
+            .unwrap_or_default()
+    }
+
+    /// Up to `FINGERPRINT_SAMPLE` indexed paths, evenly spaced in sorted order.
+    pub fn sample_paths(&self) -> Vec<&str> {
+        let mut paths: Vec<&str> = self
+            .files
+            .keys()
+            .map(String::as_str)
+            .filter(|p| !super::git::is_commit_path(p))
+            .collect();
+        paths.sort_unstable();
+        let step = paths.len().div_ceil(FINGERPRINT_SAMPLE).max(1);
+        paths.into_iter().step_by(step).collect()
+    }
+
+    /// Hash over `(path, content hash)` of each sampled path; `hash_of` returns
+    /// None for missing files.
+    pub fn sample_hash<'a>(
+        paths: &[&'a str],
+        hash_of: impl Fn(&'a str) -> Option<String>,
+    ) -> String {
+        let mut hasher = blake3::Hasher::new();
+        for path in paths {
+            hasher.update(path.as_bytes());
+            hasher.update(hash_of(path).unwrap_or_default().as_bytes());
         }
+        hasher.finalize().to_hex()[..16].to_string()
+    }
 
-        // Old manifests are incompatible — different model, dims, metric
-        if version < MANIFEST_VERSION {
-            let has_files = data
-                .get("files")
-                .map(|f| f.as_object().is_some_and(|o| !o.is_empty()))
-                .unwrap_or(false);
-            if has_files {
-                bail!("Index was created by an older version. Run 'og build --force' to rebuild.");
+    fn compute_fingerprint(&self, index_dir: &Path) -> Fingerprint {
+        let root = index_dir
+            .parent()
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let sample_hash = Self::sample_hash(&self.sample_paths(), |p| {
+            self.files.get(p).map(|e| e.hash.clone())
+        });
+        Fingerprint {
+            root,
+            files: self
+                .files
+                .keys()
+                .filter(|p| !super::git::is_commit_path(p))
+                .count(),
+            sample_hash,
+        }
+    }
+
+    /// Write the manifest atomically in its format (`binary`), removing a
+    /// manifest left in the other format.
+    pub fn save(&mut self, index_dir: &Path) -> Result<()> {
+        self.fingerprint = Some(self.compute_fingerprint(index_dir));
+        std::fs::create_dir_all(index_dir)?;
+        let (file, other) = if self.binary {
+            (BINARY_MANIFEST_FILE, MANIFEST_FILE)
+        } else {
+            (MANIFEST_FILE, BINARY_MANIFEST_FILE)
+        };
+        let tmp_path = index_dir.join(format!(".{file}.tmp"));
+        if self.binary {
+            std::fs::write(&tmp_path, binary::encode(self)?)?;
+        } else {
+            std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        }
+        std::fs::rename(&tmp_path, index_dir.join(file))?;
+        let other = index_dir.join(other);
+        if other.exists() {
+            std::fs::remove_file(other)?;
+        }
+        super::cache::invalidate();
+        Ok(())
+    }
+}
+
+/// Reject manifests from other og versions. Older ones are only an error once
+/// they index files (`has_files` is evaluated lazily).
+fn check_version(version: u32, has_files: impl FnOnce() -> bool) -> Result<()> {
+    if version > MANIFEST_VERSION {
+        bail!(
+            "Index was created by a newer version of og. \
+             Please upgrade og or run 'og build --force' to rebuild."
+        );
+    }
+    // Old manifests are incompatible — different model, dims, metric
+    if version < MANIFEST_VERSION && has_files() {
+        bail!("Index was created by an older version. Run 'og build --force' to rebuild.");
+    }
+    Ok(())
+}
+
+/// `manifest.bin` layout (little-endian, strings as u32 length + UTF-8):
+/// magic, version u32, model, options JSON, fingerprint JSON (empty if none),
+/// file count u64, then per file: path, hash, mtime u64, shard (empty if none),
+/// block count u32, block IDs.
+mod binary {
+    use anyhow::{bail, Context, Result};
+
+    use super::{FileEntry, Manifest, BINARY_MAGIC};
+
+    #[derive(Clone)]
+    pub struct Reader<'a> {
+        data: &'a [u8],
+    }
+
+    /// Smallest encoded file entry: empty path, hash and shard (a u32 length
+    /// each), mtime and block count.
+    const MIN_ENTRY_BYTES: usize = 3 * 4 + 8 + 4;
+
+    impl<'a> Reader<'a> {
+        fn remaining(&self) -> usize {
+            self.data.len()
+        }
+
+        fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+            if self.data.len() < n {
+                bail!("Truncated manifest.bin");
             }
+            let (head, rest) = self.data.split_at(n);
+            self.data = rest;
+            Ok(head)
         }
 
-        let manifest: Manifest = serde_json::from_value(data)?;
-        Ok(manifest)
+        fn u32(&mut self) -> Result<u32> {
+            Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+        }
+
+        fn u64(&mut self) -> Result<u64> {
+            Ok(u64::from_le_bytes(self.take(8)?.try_into()?))
+        }
+
+        pub fn string(&mut self) -> Result<String> {
+            let len = self.u32()? as usize;
+            let bytes = self.take(len)?;
+            Ok(std::str::from_utf8(bytes)
+                .context("Invalid UTF-8 in manifest.bin")?
+                .to_string())
+        }
     }
 
-    pub fn save(&self, index_dir: &Path) -> Result<()> {
-        std::fs::create_dir_all(index_dir)?;
-        let manifest_path = index_dir.join(MANIFEST_FILE);
-        let tmp_path = index_dir.join(".manifest.json.tmp");
-        let content = serde_json::to_string_pretty(self)?;
-        std::fs::write(&tmp_path, &content)?;
-        std::fs::rename(&tmp_path, &manifest_path)?;
+    /// Check the magic and read the version; the reader continues after it.
+    pub fn header(data: &[u8]) -> Result<(u32, Reader<'_>)> {
+        let mut reader = Reader { data };
+        if reader.take(BINARY_MAGIC.len()).ok() != Some(BINARY_MAGIC.as_slice()) {
+            bail!("manifest.bin is not an og manifest");
+        }
+        let version = reader.u32()?;
+        Ok((version, reader))
+    }
+
+    pub fn decode(version: u32, mut r: Reader<'_>) -> Result<Manifest> {
+        let model = r.string()?;
+        let options = serde_json::from_str(&r.string()?)?;
+        let fingerprint = match r.string()? {
+            f if f.is_empty() => None,
+            f => Some(serde_json::from_str(&f)?),
+        };
+        // The count is untrusted: cap the preallocation at what the bytes left can hold
+        let count = r.u64()? as usize;
+        let mut files =
+            std::collections::HashMap::with_capacity(count.min(r.remaining() / MIN_ENTRY_BYTES));
+        for _ in 0..count {
+            let path = r.string()?;
+            let hash = r.string()?;
+            let mtime = r.u64()?;
+            let shard = Some(r.string()?).filter(|s| !s.is_empty());
+            let blocks = (0..r.u32()?)
+                .map(|_| r.string())
+                .collect::<Result<Vec<_>>>()?;
+            files.insert(
+                path,
+                FileEntry {
+                    hash,
+                    blocks,
+                    mtime,
+                    shard,
+                },
+            );
+        }
+        Ok(Manifest {
+            version,
+            model,
+            options,
+            files,
+            fingerprint,
+            binary: true,
+        })
+    }
+
+    fn put_str(out: &mut Vec<u8>, s: &str) -> Result<()> {
+        out.extend_from_slice(&u32::try_from(s.len())?.to_le_bytes());
+        out.extend_from_slice(s.as_bytes());
         Ok(())
     }
+
+    pub fn encode(manifest: &Manifest) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        out.extend_from_slice(BINARY_MAGIC);
+        out.extend_from_slice(&manifest.version.to_le_bytes());
+        put_str(&mut out, &manifest.model)?;
+        put_str(&mut out, &serde_json::to_string(&manifest.options)?)?;
+        let fingerprint = match &manifest.fingerprint {
+            Some(f) => serde_json::to_string(f)?,
+            None => String::new(),
+        };
+        put_str(&mut out, &fingerprint)?;
+        out.extend_from_slice(&(manifest.files.len() as u64).to_le_bytes());
+        for (path, entry) in &manifest.files {
+            put_str(&mut out, path)?;
+            put_str(&mut out, &entry.hash)?;
+            out.extend_from_slice(&entry.mtime.to_le_bytes());
+            put_str(&mut out, entry.shard.as_deref().unwrap_or(""))?;
+            out.extend_from_slice(&u32::try_from(entry.blocks.len())?.to_le_bytes());
+            for block in &entry.blocks {
+                put_str(&mut out, block)?;
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Manifest {
+        let mut manifest = Manifest::default();
+        manifest.files.insert(
+            "src/main.rs".to_string(),
+            FileEntry {
+                hash: "abc123".to_string(),
+                blocks: vec!["src/main.rs:0:main".to_string()],
+                mtime: 42,
+                shard: Some("src".to_string()),
+            },
+        );
+        manifest
+    }
+
+    #[test]
+    fn binary_round_trip_and_format_switch() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let index_dir = tmp.path().join(".og");
+
+        let mut manifest = sample();
+        manifest.save(&index_dir).unwrap();
+        assert!(index_dir.join(MANIFEST_FILE).exists());
+
+        manifest.binary = true;
+        manifest.save(&index_dir).unwrap();
+        assert!(!index_dir.join(MANIFEST_FILE).exists());
+        assert_eq!(
+            Manifest::file_path(&index_dir),
+            index_dir.join(BINARY_MANIFEST_FILE)
+        );
+
+        let loaded = Manifest::load(&index_dir).unwrap();
+        assert!(loaded.binary);
+        assert_eq!(loaded.files["src/main.rs"].blocks, ["src/main.rs:0:main"]);
+        assert_eq!(loaded.files["src/main.rs"].mtime, 42);
+        assert_eq!(loaded.files["src/main.rs"].shard.as_deref(), Some("src"));
+        assert_eq!(loaded.options, manifest.options);
+        assert_eq!(loaded.fingerprint, manifest.fingerprint);
+        assert_eq!(Manifest::info(&index_dir).unwrap().blocks, 1);
+    }
+
+    #[test]
+    fn binary_rejects_old_versions() {
+        let mut manifest = sample();
+        manifest.version = MANIFEST_VERSION - 1;
+        let data = binary::encode(&manifest).unwrap();
+        let (version, _) = binary::header(&data).unwrap();
+        assert!(check_version(version, || true).is_err());
+        assert!(binary::header(b"{\"version\": 1}").is_err());
+    }
+
+    #[test]
+    fn binary_corrupt_count_is_an_error() {
+        // With no files, the entry count is the last 8 bytes
+        let mut data = binary::encode(&Manifest::default()).unwrap();
+        let len = data.len();
+        data[len - 8..].copy_from_slice(&u64::MAX.to_le_bytes());
+        let (version, reader) = binary::header(&data).unwrap();
+        let err = binary::decode(version, reader).unwrap_err();
+        assert!(err.to_string().contains("Truncated"), "{err}");
+    }
 }
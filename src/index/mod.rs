@@ -1,23 +1,46 @@
+pub mod cache;
+pub mod git;
 pub mod manifest;
+pub mod query_log;
+pub mod signature;
+pub mod split;
+pub mod store;
 pub mod walker;
 
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 
 use anyhow::{bail, Context, Result};
+use ndarray::Array2;
 use rayon::prelude::*;
 
-use crate::embedder::{self, Embedder};
+use crate::embedder::{self, Embedder, Precision};
 use crate::extractor::Extractor;
-use crate::tokenize::split_identifiers;
-use crate::types::{Block, IndexStats, SearchResult};
+use crate::tokenize::split_identifiers_with;
+use crate::types::{
+    Block, DocFilter, FileRef, GitInfo, IndexOptions, IndexStats, SearchMode, SearchResult,
+};
 use omendb::SearchOptions;
 
 use manifest::{FileEntry, Manifest};
+use store::Store;
 
 pub const INDEX_DIR: &str = ".og";
 pub const VECTORS_DIR: &str = "vectors";
+/// Project tree-sitter query overrides (`.og/queries/<lang>.scm`), kept when
+/// the index is cleared or rebuilt.
+pub const QUERIES_DIR: &str = "queries";
+/// Project acronym map (`.og/acronyms.txt`) expanded in BM25 queries, kept
+/// when the index is cleared or rebuilt.
+pub const ACRONYMS_FILE: &str = "acronyms.txt";
+/// User-provided files in the index directory that clearing it keeps.
+const KEPT_FILES: &[&str] = &[QUERIES_DIR, ACRONYMS_FILE];
+/// Temporary store written by `og vacuum` before it replaces `VECTORS_DIR`.
+const VACUUM_DIR: &str = "vectors.vacuum";
 
 /// Block types that are documentation, not code.
 const DOC_BLOCK_TYPES: &[&str] = &["text", "section"];
@@ -25,37 +48,441 @@ const DOC_BLOCK_TYPES: &[&str] = &["text", "section"];
 /// When search scope filters results, over-fetch by this factor to compensate.
 const SCOPE_OVERFETCH: usize = 5;
 
+/// `--signatures` re-ranks this many times `k` candidates.
+const SIGNATURE_OVERFETCH: usize = 3;
+
+/// `--and-file` compares at most this many of the file's blocks.
+const AND_FILE_BLOCKS: usize = 8;
+
+/// Nearest blocks retrieved per `--and-file` block.
+const AND_FILE_CANDIDATES: usize = 200;
+
+/// `--and-file` floor: a block must score at least this fraction of the
+/// file block's similarity to itself, so the floor adapts to block length.
+const AND_FILE_MIN_RATIO: f32 = 0.7;
+
+/// Set by the SIGINT/SIGTERM handler; `index()` stops at the next batch boundary.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Install a SIGINT/SIGTERM handler for `og build`. The first signal lets
+/// `index()` finish its current batch, flush the store, and save the manifest
+/// for completed files; a second signal exits immediately.
+pub fn install_interrupt_handler() {
+    let _ = ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            std::process::exit(130);
+        }
+        eprintln!("\nInterrupted, saving progress (Ctrl-C again to abort)...");
+    });
+}
+
+/// Env var: overall time limit in milliseconds for a search or MCP tool call.
+pub const TIMEOUT_ENV: &str = "OG_TIMEOUT_MS";
+
+/// Env var: semantic (MaxSim) score below which candidates are dropped before
+/// merging. Unset by default; unlike `--threshold` it applies before boosts.
+pub const MIN_SEMANTIC_ENV: &str = "OG_MIN_SEMANTIC";
+
+/// Scale of `--context-file` token embeddings relative to the query's, so the
+/// query still dominates the MaxSim score.
+const CONTEXT_FILE_WEIGHT: f32 = 0.5;
+
+/// Indexes smaller than this never warn about a file-count mismatch.
+const FINGERPRINT_MIN_FILES: usize = 10;
+
+/// Operation timeout from `OG_TIMEOUT_MS`, if set to a positive number.
+pub fn timeout_from_env() -> Option<Duration> {
+    std::env::var(TIMEOUT_ENV)
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .filter(|ms| *ms > 0)
+        .map(Duration::from_millis)
+}
+
+/// Reject empty and whitespace-only queries, which embed to degenerate
+/// vectors and give BM25 no terms.
+pub fn check_query(query: &str) -> Result<()> {
+    if query.trim().is_empty() {
+        bail!("Empty query. Provide search terms, e.g. og \"error handling\"");
+    }
+    Ok(())
+}
+
+/// Strip markdown left on a query pasted from docs or chat: a surrounding
+/// code fence, paired inline-code backticks, and leading `# ` header and
+/// `> ` quote markers on each line; then collapse whitespace. Code such as
+/// `#include` or `a > b` is left alone.
+pub fn normalize_query(query: &str) -> String {
+    let mut lines: Vec<&str> = query.trim().lines().collect();
+    if lines
+        .first()
+        .is_some_and(|l| l.trim_start().starts_with("```"))
+    {
+        lines.remove(0);
+        if lines.last().is_some_and(|l| l.trim() == "```") {
+            lines.pop();
+        }
+    }
+
+    let mut words = Vec::new();
+    for line in lines {
+        let mut line = line.trim_start();
+        loop {
+            let header = line.trim_start_matches('#');
+            if header.len() < line.len() && (header.is_empty() || header.starts_with(' ')) {
+                line = header.trim_start();
+            } else if let Some(rest) = line
+                .strip_prefix('>')
+                .filter(|r| r.is_empty() || r.starts_with(' '))
+            {
+                line = rest.trim_start();
+            } else {
+                break;
+            }
+        }
+        words.extend(line.split_whitespace());
+    }
+
+    let query = words.join(" ");
+    if query.matches('`').count() % 2 == 0 {
+        query.replace('`', "")
+    } else {
+        query
+    }
+}
+
+/// Semantic score floor from `OG_MIN_SEMANTIC`, if set to a number.
+fn min_semantic_from_env() -> Option<f32> {
+    parse_min_semantic(&std::env::var(MIN_SEMANTIC_ENV).ok()?)
+}
+
+fn parse_min_semantic(value: &str) -> Option<f32> {
+    value.trim().parse::<f32>().ok().filter(|f| f.is_finite())
+}
+
+/// A merged search result with its raw score from each retrieval path.
+#[derive(Debug, Clone)]
+pub struct CandidateScores {
+    pub result: SearchResult,
+    /// Pure semantic (MaxSim) score, if the block was a semantic candidate.
+    pub semantic: Option<f32>,
+    /// BM25-candidate (MaxSim-reranked) score, if the block was a BM25 candidate.
+    pub bm25: Option<f32>,
+}
+
+/// Every candidate a query produced, for `--trace-json`.
+#[derive(Debug, Clone)]
+pub struct SearchTrace {
+    /// Semantic path candidates as (block ID, raw MaxSim score), best first.
+    pub semantic: Vec<(String, f32)>,
+    /// BM25 path candidates as (block ID, MaxSim-reranked score), best first.
+    pub bm25: Vec<(String, f32)>,
+    /// In-scope candidates after merging both paths, before boosts.
+    pub merged: Vec<CandidateScores>,
+}
+
+/// What each retrieval stage produced for a query (`--explain-empty`).
+#[derive(Debug, Clone)]
+pub struct SearchDiagnostics {
+    /// Blocks in the index.
+    pub blocks: usize,
+    /// Candidates from the semantic path.
+    pub semantic: usize,
+    /// Candidates from the BM25 path.
+    pub bm25: usize,
+    /// Distinct candidates after merging both paths.
+    pub merged: usize,
+    /// Merged candidates outside the search scope.
+    pub out_of_scope: usize,
+    /// Scope relative to the index root, if searching a subdirectory.
+    pub scope: Option<String>,
+    /// In-scope candidates by score, before result filters and threshold.
+    pub top: Vec<SearchResult>,
+    /// Query terms sent to BM25 after identifier splitting and synonyms.
+    pub bm25_terms: Vec<String>,
+}
+
+/// A vector store kept open across searches.
+pub struct SearchSession {
+    store: Store,
+}
+
 /// Manages semantic search index using omendb.
 pub struct SemanticIndex {
     root: PathBuf,
     index_dir: PathBuf,
-    vectors_path: String,
+    vectors_path: PathBuf,
     search_scope: Option<String>,
-    embedder: Box<dyn Embedder>,
+    search_mode: SearchMode,
+    /// Semantic weight for normalized score fusion; None keeps the max score per ID.
+    alpha: Option<f32>,
+    /// Which block kinds `find_similar` returns (default: code only).
+    doc_filter: DocFilter,
+    /// Created on first embedding operation so metadata-only commands
+    /// (status, list, clean, ...) never load the ONNX session.
+    embedder: OnceLock<Box<dyn Embedder>>,
+    /// Index-time options to record on the next `index()`; None reuses the manifest's.
+    options: Option<IndexOptions>,
+    /// Model precision for `og build`; None uses the precision the index was built with.
+    precision: Option<Precision>,
+    /// Time limit for indexing and search; indexing stops between batches.
+    deadline: Option<Instant>,
+    /// `--context-file` (relative path) and its weighted block embeddings,
+    /// appended to every query's token embeddings.
+    context: Option<(String, Array2<f32>)>,
+    /// Relative paths recorded as modified within `--changed-since`; other
+    /// files are dropped like out-of-scope results.
+    changed_files: Option<HashSet<String>>,
+    /// Re-rank candidates by their signatures alone (`--signatures`).
+    signatures: bool,
+    /// Block IDs similar to the `--and-file` file; other results are dropped.
+    and_file_ids: Option<HashSet<String>>,
+    /// Drop keywords and English stopwords from the BM25 query (`--strip-stopwords`).
+    strip_stopwords: bool,
 }
 
 impl SemanticIndex {
     pub fn new(root: &Path, search_scope: Option<&Path>) -> Result<Self> {
         let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
         let index_dir = root.join(INDEX_DIR);
-        let vectors_path = index_dir.join(VECTORS_DIR).to_string_lossy().into_owned();
+        let vectors_path = index_dir.join(VECTORS_DIR);
         let scope = Self::compute_scope(&root, search_scope);
-        let embedder = embedder::create_embedder()?;
 
         Ok(Self {
             root,
             index_dir,
             vectors_path,
             search_scope: scope,
-            embedder,
+            search_mode: SearchMode::default(),
+            alpha: None,
+            doc_filter: DocFilter::default(),
+            embedder: OnceLock::new(),
+            options: None,
+            precision: None,
+            deadline: None,
+            context: None,
+            changed_files: None,
+            signatures: false,
+            and_file_ids: None,
+            strip_stopwords: false,
+        })
+    }
+
+    /// Index root directory.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The embedder, loading the model on first use.
+    fn embedder(&self) -> Result<&dyn Embedder> {
+        if let Some(embedder) = self.embedder.get() {
+            return Ok(embedder.as_ref());
+        }
+        let embedder = embedder::create_embedder(self.precision())?;
+        Ok(self.embedder.get_or_init(|| embedder).as_ref())
+    }
+
+    /// Set index-time options (for `og build`). Without this, indexing reuses
+    /// the options recorded in the manifest.
+    pub fn set_options(&mut self, options: IndexOptions) {
+        self.options = Some(options);
+    }
+
+    /// Index-time options recorded in the manifest.
+    pub fn options(&self) -> Result<IndexOptions> {
+        Ok(Manifest::load(&self.index_dir)?.options)
+    }
+
+    /// Select the model precision to build with (`--model-int8`/`--model-fp32`).
+    pub fn set_precision(&mut self, precision: Precision) {
+        self.precision = Some(precision);
+    }
+
+    /// Explicit precision, else the one recorded in the manifest (INT8 for new indexes).
+    fn precision(&self) -> Precision {
+        self.precision.unwrap_or_else(|| {
+            Manifest::load(&self.index_dir)
+                .map(|m| m.precision())
+                .unwrap_or_default()
         })
     }
 
+    /// Abort indexing (after flushing completed files) and search once `deadline` passes.
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+
+    fn past_deadline(&self) -> bool {
+        self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+
+    fn check_deadline(&self) -> Result<()> {
+        if self.past_deadline() {
+            bail!("Operation timed out ({TIMEOUT_ENV})");
+        }
+        Ok(())
+    }
+
     /// Set search scope after construction (for reusing a single instance).
     pub fn set_search_scope(&mut self, search_scope: Option<&Path>) {
         self.search_scope = Self::compute_scope(&self.root, search_scope);
     }
 
+    /// Set which retrieval signals `search` uses (default: hybrid).
+    pub fn set_search_mode(&mut self, mode: SearchMode) {
+        self.search_mode = mode;
+    }
+
+    /// Fuse BM25 and semantic scores as `alpha*semantic + (1-alpha)*bm25` after
+    /// min-max normalizing each path. None (default) keeps the higher raw score per ID.
+    pub fn set_alpha(&mut self, alpha: Option<f32>) {
+        self.alpha = alpha;
+    }
+
+    /// Bias searches toward `file` (`--context-file`): the embeddings of its
+    /// top block, scaled by `CONTEXT_FILE_WEIGHT`, are added to each query's
+    /// tokens so MaxSim favors code relevant to both.
+    pub fn set_context_file(&mut self, file: &Path) -> Result<()> {
+        let file = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+        let rel_path = self.to_relative(&file);
+        let manifest = Manifest::load(&self.index_dir)?;
+        let block_id = manifest
+            .files
+            .get(&rel_path)
+            .and_then(|entry| entry.blocks.first())
+            .with_context(|| format!("Context file not in index: {rel_path}"))?;
+        let (tokens, _meta) = self
+            .open_store()?
+            .get_tokens(block_id)
+            .context("Could not retrieve context block token embeddings")?;
+        let dim = tokens.first().map_or(0, Vec::len);
+        let flat: Vec<f32> = tokens
+            .iter()
+            .flatten()
+            .map(|v| v * CONTEXT_FILE_WEIGHT)
+            .collect();
+        let tokens = Array2::from_shape_vec((tokens.len(), dim), flat)?;
+        self.context = Some((rel_path, tokens));
+        Ok(())
+    }
+
+    /// Restrict searches to files whose mtime, as recorded at the last build
+    /// or update, is at or after `cutoff` (unix seconds; `--changed-since`).
+    pub fn set_changed_since(&mut self, cutoff: Option<u64>) -> Result<()> {
+        self.changed_files = match cutoff {
+            Some(cutoff) => Some(
+                Manifest::load(&self.index_dir)?
+                    .files
+                    .into_iter()
+                    .filter(|(_, entry)| entry.mtime >= cutoff)
+                    .map(|(path, _)| path)
+                    .collect(),
+            ),
+            None => None,
+        };
+        Ok(())
+    }
+
+    /// Require results to also be similar to `file` (`--and-file`): each of
+    /// its first blocks retrieves its nearest blocks, and those scoring above
+    /// `AND_FILE_MIN_RATIO` of the block's self-similarity are kept. Searches
+    /// then drop candidates outside that set, intersecting both signals.
+    pub fn set_and_file(&mut self, file: Option<&Path>) -> Result<()> {
+        let Some(file) = file else {
+            self.and_file_ids = None;
+            return Ok(());
+        };
+        let file = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+        let rel_path = self.to_relative(&file);
+        let manifest = Manifest::load(&self.index_dir)?;
+        let entry = manifest
+            .files
+            .get(&rel_path)
+            .with_context(|| format!("--and-file not in index: {rel_path}"))?;
+        let store = self.open_store()?;
+
+        let mut ids = HashSet::new();
+        for block_id in entry.blocks.iter().take(AND_FILE_BLOCKS) {
+            let Some((tokens, _meta)) = store.get_tokens(block_id) else {
+                continue;
+            };
+            let token_refs: Vec<&[f32]> = tokens.iter().map(|v| v.as_slice()).collect();
+            let results = store.query_with_options(
+                &token_refs,
+                AND_FILE_CANDIDATES,
+                &SearchOptions::default(),
+            )?;
+            let Some(best) = results.iter().map(|r| r.distance).reduce(f32::max) else {
+                continue;
+            };
+            let floor = best * AND_FILE_MIN_RATIO;
+            ids.extend(
+                results
+                    .into_iter()
+                    .filter(|r| r.distance >= floor && !entry.blocks.contains(&r.id))
+                    .map(|r| r.id),
+            );
+        }
+        self.and_file_ids = Some(ids);
+        Ok(())
+    }
+
+    /// Re-rank results by similarity between the query and each block's
+    /// signature, ignoring the body (`--signatures`).
+    pub fn set_signatures(&mut self, signatures: bool) {
+        self.signatures = signatures;
+    }
+
+    /// Leave language keywords and English stopwords out of the BM25 query;
+    /// the semantic query is unchanged.
+    pub fn set_strip_stopwords(&mut self, strip: bool) {
+        self.strip_stopwords = strip;
+    }
+
+    /// `--expand-acronyms`: require the acronym map. Queries expand acronyms
+    /// whenever `.og/acronyms.txt` exists, so without the flag a missing map
+    /// is not an error.
+    pub fn set_expand_acronyms(&mut self, expand: bool) -> Result<()> {
+        let path = self.index_dir.join(ACRONYMS_FILE);
+        if expand && !path.exists() {
+            bail!(
+                "--expand-acronyms needs an acronym map in {} ('ACRONYM = words' per line)",
+                path.display()
+            );
+        }
+        Ok(())
+    }
+
+    /// The acronym map from `.og/acronyms.txt`; empty when there is none.
+    fn acronyms(&self) -> Result<Vec<crate::synonyms::Acronym>> {
+        let path = self.index_dir.join(ACRONYMS_FILE);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        crate::synonyms::parse_acronyms(&text).with_context(|| format!("In {}", path.display()))
+    }
+
+    /// BM25 query text, split the same way the index's BM25 text was, without
+    /// stopwords under `--strip-stopwords`, and with acronyms and synonyms expanded.
+    fn bm25_query(&self, query: &str, options: &IndexOptions) -> Result<String> {
+        let query = if self.strip_stopwords {
+            crate::tokenize::strip_stopwords(query)
+        } else {
+            query.to_string()
+        };
+        let text = crate::synonyms::expand_acronyms(&bm25_text(&query, options), &self.acronyms()?);
+        Ok(crate::synonyms::expand_query(&text))
+    }
+
+    /// Include or restrict to documentation blocks in similar-code search.
+    pub fn set_doc_filter(&mut self, doc_filter: DocFilter) {
+        self.doc_filter = doc_filter;
+    }
+
     fn compute_scope(root: &Path, search_scope: Option<&Path>) -> Option<String> {
         search_scope.and_then(|s| {
             let s = s.canonicalize().unwrap_or_else(|_| s.to_path_buf());
@@ -79,15 +506,22 @@ impl SemanticIndex {
     ) -> Result<IndexStats> {
         std::fs::create_dir_all(&self.index_dir)?;
         let mut manifest = Manifest::load(&self.index_dir)?;
-        manifest.model = embedder::MODEL.version.to_string();
+        manifest.model =
+            embedder::MODEL.version_for(self.precision.unwrap_or(manifest.precision()));
+        if let Some(options) = &self.options {
+            manifest.options = options.clone();
+        }
+        let options = manifest.options.clone();
         let mut stats = IndexStats::default();
 
-        // Open omendb multi-vector store
-        let mut store = self.open_or_create_store()?;
+        // Open omendb multi-vector store (one per top-level directory with --shard)
+        let mut store = Store::open_or_create(&self.vectors_path, options.shard)?;
         store.enable_text_search()?;
 
-        // Identify files needing processing (borrow content, don't clone)
+        // Identify files needing processing (borrow content, don't clone).
+        // Old entries of changed files are diffed against the new blocks below.
         let mut to_process: Vec<(&Path, &str, String, String, u64)> = Vec::new();
+        let mut previous: HashMap<String, FileEntry> = HashMap::new();
         for (path, (content, mtime)) in files {
             let rel_path = self.to_relative(path);
             let file_hash = hash_content(content);
@@ -97,11 +531,20 @@ impl SemanticIndex {
                     stats.skipped += 1;
                     continue;
                 }
-                // Delete old blocks
-                for block_id in &entry.blocks {
-                    let _ = store.delete(block_id);
+                previous.insert(rel_path.clone(), entry.clone());
+            }
+
+            if !options.keep_minified
+                && !git::is_commit_path(&rel_path)
+                && walker::is_minified_or_generated(&rel_path, content)
+            {
+                log::debug!("skip path={rel_path} reason=minified or generated");
+                if let Some(entry) = manifest.files.remove(&rel_path) {
+                    store.delete_blocks(&entry);
+                    stats.deleted += entry.blocks.len();
                 }
-                stats.deleted += entry.blocks.len();
+                stats.skipped += 1;
+                continue;
             }
 
             to_process.push((
@@ -116,6 +559,7 @@ impl SemanticIndex {
         if to_process.is_empty() {
             if stats.deleted > 0 {
                 store.flush()?;
+                manifest.save(&self.index_dir)?;
             }
             return Ok(stats);
         }
@@ -123,16 +567,87 @@ impl SemanticIndex {
         store.flush()?;
 
         // Extract blocks in parallel, reusing Extractor per thread
-        let all_blocks: Vec<(Vec<Block>, String, String, u64)> = to_process
+        let custom_queries = Arc::new(crate::extractor::queries::load_custom(
+            &self.index_dir.join(QUERIES_DIR),
+        )?);
+        let mut all_blocks: Vec<(Vec<Block>, String, String, u64, usize)> = to_process
             .par_iter()
             .map_init(
-                Extractor::new,
-                |extractor, (_path, content, rel_path, file_hash, mtime)| {
-                    let blocks = extractor.extract(rel_path, content).unwrap_or_default();
-                    (blocks, rel_path.clone(), file_hash.clone(), *mtime)
+                || Extractor::with_options(&options).with_custom_queries(custom_queries.clone()),
+                |extractor, (path, content, rel_path, file_hash, mtime)| {
+                    if git::is_commit_path(rel_path) {
+                        let blocks =
+                            split::split_long_blocks(git::commit_blocks(rel_path, content, *mtime));
+                        return (blocks, rel_path.clone(), file_hash.clone(), *mtime, 0);
+                    }
+                    let mut blocks = extractor.extract(rel_path, content).unwrap_or_default();
+                    let pruned = if options.prune_min_tokens > 0 {
+                        crate::extractor::prune_trivial_blocks(
+                            &mut blocks,
+                            options.prune_min_tokens,
+                        )
+                    } else {
+                        0
+                    };
+                    if options.max_block_lines > 0 {
+                        blocks = split::split_by_lines(blocks, options.max_block_lines);
+                    }
+                    let mut blocks = split::split_long_blocks(blocks);
+                    if options.with_git {
+                        git::annotate(&self.root, path, &mut blocks);
+                    }
+                    (blocks, rel_path.clone(), file_hash.clone(), *mtime, pruned)
                 },
             )
             .collect();
+        stats.pruned = all_blocks.iter().map(|(.., pruned)| pruned).sum();
+        // Splitting changes content, so IDs are settled only now
+        for (blocks, ..) in &mut all_blocks {
+            Block::assign_ids(blocks);
+        }
+
+        // A changed file's blocks whose stable ID and embedding text match the
+        // old entry keep their vectors; only their metadata (line numbers, git
+        // annotations) is rewritten. Old IDs that are not kept are deleted.
+        let mut reused: Vec<Vec<bool>> = Vec::with_capacity(all_blocks.len());
+        for (blocks, rel_path, ..) in &all_blocks {
+            let Some(old) = previous.get(rel_path) else {
+                reused.push(vec![false; blocks.len()]);
+                continue;
+            };
+            let keep = unchanged_blocks(&old.blocks, blocks, |id| {
+                store
+                    .get_metadata_by_id(id)
+                    .map(|m| embedding_text_from_metadata(&m))
+            });
+            let kept: HashSet<&str> = blocks
+                .iter()
+                .zip(&keep)
+                .filter(|(_, k)| **k)
+                .map(|(b, _)| b.id.as_str())
+                .collect();
+            let gone = FileEntry {
+                blocks: old
+                    .blocks
+                    .iter()
+                    .filter(|id| !kept.contains(id.as_str()))
+                    .cloned()
+                    .collect(),
+                ..old.clone()
+            };
+            log::debug!(
+                "store delete path={rel_path} blocks={} kept={}",
+                gone.blocks.len(),
+                kept.len()
+            );
+            store.delete_blocks(&gone);
+            stats.deleted += gone.blocks.len();
+            for (block, _) in blocks.iter().zip(&keep).filter(|(_, k)| **k) {
+                store.update_metadata(&block.id, block_metadata(block))?;
+                stats.reused += 1;
+            }
+            reused.push(keep);
+        }
 
         // Flatten blocks, compute embedding text once, track file stats.
         // Store (file_idx, block_idx) to reference blocks without cloning.
@@ -143,13 +658,16 @@ impl SemanticIndex {
         }
 
         let mut prepared: Vec<PreparedBlock> = Vec::new();
-        for (file_idx, (blocks, _rel_path, _file_hash, _mtime)) in all_blocks.iter().enumerate() {
+        for (file_idx, (blocks, ..)) in all_blocks.iter().enumerate() {
             if blocks.is_empty() {
                 stats.errors += 1;
             } else {
                 stats.files += 1;
             }
             for (block_idx, block) in blocks.iter().enumerate() {
+                if reused[file_idx][block_idx] {
+                    continue;
+                }
                 let text = block.embedding_text();
                 prepared.push(PreparedBlock {
                     file_idx,
@@ -159,19 +677,36 @@ impl SemanticIndex {
             }
         }
 
-        if prepared.is_empty() {
-            manifest.save(&self.index_dir)?;
-            return Ok(stats);
-        }
-
         // Sort by text length for better batching (avoids recomputing embedding_text)
         prepared.sort_by_key(|p| p.text.len());
 
         let total = prepared.len();
-        let batch_size = embedder::MODEL.batch_size;
+        // One model batch per embedding session, embedded concurrently. The
+        // model is not loaded when every block was reused.
+        let batch_size = if total == 0 {
+            1
+        } else {
+            embedder::MODEL.batch_size * self.embedder()?.concurrency()
+        };
+
+        // Blocks still to store per file; a file enters the manifest only once all are stored
+        let mut remaining: Vec<usize> = reused
+            .iter()
+            .map(|keep| keep.iter().filter(|k| !**k).count())
+            .collect();
+        let mut interrupted = false;
+        let mut timed_out = false;
 
         // Embed in batches
         for start in (0..total).step_by(batch_size) {
+            if INTERRUPTED.load(Ordering::SeqCst) {
+                interrupted = true;
+                break;
+            }
+            if self.past_deadline() {
+                timed_out = true;
+                break;
+            }
             let end = (start + batch_size).min(total);
             if let Some(progress) = on_progress {
                 progress(
@@ -185,7 +720,12 @@ impl SemanticIndex {
                 .iter()
                 .map(|p| p.text.as_str())
                 .collect();
-            let token_embeddings = self.embedder.embed_documents(&batch_refs)?;
+            let t0 = Instant::now();
+            let token_embeddings = self.embedder()?.embed_documents(&batch_refs)?;
+            log::debug!(
+                "embed batch={start}-{end} total={total} ms={}",
+                t0.elapsed().as_millis()
+            );
 
             for (idx, token_emb) in token_embeddings.embeddings.iter().enumerate() {
                 let p = &prepared[start + idx];
@@ -198,39 +738,57 @@ impl SemanticIndex {
                     .map(|r| r.to_vec())
                     .collect();
 
-                let metadata = serde_json::json!({
-                    "file": block.file,
-                    "type": block.block_type,
-                    "name": block.name,
-                    "start_line": block.start_line,
-                    "end_line": block.end_line,
-                    "content": block.content,
-                });
-
-                let bm25_text = split_identifiers(&p.text);
-                store.store_with_text(&block.id, tokens, &bm25_text, metadata)?;
+                let bm25_text = bm25_document(&p.text, &block.file, &options);
+                store.store_with_text(&block.id, tokens, &bm25_text, block_metadata(block))?;
+                remaining[p.file_idx] -= 1;
 
                 stats.blocks += 1;
             }
         }
 
+        // Manifest is saved only after a successful flush, so an interrupted or
+        // failed run leaves stale entries that the next build re-processes.
         store.flush()?;
+        log::debug!("store flush blocks={}", stats.blocks);
 
         // Update manifest (mtime was captured before content read)
-        for (blocks, rel_path, file_hash, mtime) in &all_blocks {
-            if !blocks.is_empty() {
+        for ((blocks, rel_path, file_hash, mtime, _), left) in all_blocks.iter().zip(&remaining) {
+            if !blocks.is_empty() && *left == 0 {
                 manifest.files.insert(
                     rel_path.clone(),
                     FileEntry {
                         hash: file_hash.clone(),
                         blocks: blocks.iter().map(|b| b.id.clone()).collect(),
                         mtime: *mtime,
+                        shard: store.shard_for(rel_path),
                     },
                 );
             }
         }
 
         manifest.save(&self.index_dir)?;
+        log::info!(
+            "index blocks={} reused={} files={} unchanged={} deleted={} pruned={}",
+            stats.blocks,
+            stats.reused,
+            stats.files,
+            stats.skipped,
+            stats.deleted,
+            stats.pruned
+        );
+
+        if interrupted {
+            bail!(
+                "Interrupted after {} blocks; run 'og build' to resume",
+                stats.blocks
+            );
+        }
+        if timed_out {
+            bail!(
+                "Operation timed out ({TIMEOUT_ENV}) after indexing {} blocks; run 'og build' to resume",
+                stats.blocks
+            );
+        }
 
         if let Some(progress) = on_progress {
             progress(total, total, "Done");
@@ -241,28 +799,354 @@ impl SemanticIndex {
 
     /// Hybrid search: semantic + BM25 with merged candidates.
     pub fn search(&self, query: &str, k: usize) -> Result<Vec<SearchResult>> {
-        let store = self.open_store()?;
+        let store = self.open_scoped_store()?;
+        self.search_store(&store, query, None, k, None)
+    }
+
+    /// Run several queries against one opened store (for `--batch`).
+    pub fn search_batch(&self, queries: &[String], k: usize) -> Result<Vec<Vec<SearchResult>>> {
+        let store = self.open_scoped_store()?;
+        let queries: Vec<String> = queries.iter().map(|q| normalize_query(q)).collect();
+        // Embed all queries in shared model runs instead of one at a time
+        let texts: Vec<&str> = queries.iter().map(String::as_str).collect();
+        texts.iter().try_for_each(|q| check_query(q))?;
+        let embedded = if texts.len() < 2 {
+            vec![None; texts.len()]
+        } else {
+            let embedded = self.embedder()?.embed_queries(&texts)?;
+            self.check_deadline()?;
+            embedded.into_iter().map(Some).collect()
+        };
+        queries
+            .iter()
+            .zip(embedded)
+            .map(|(q, tokens)| self.search_store(&store, q, tokens, k, None))
+            .collect()
+    }
+
+    /// Hybrid search that reports the pure semantic results through `on_partial`
+    /// before running BM25, so callers can show them while the merge completes.
+    pub fn search_streaming(
+        &self,
+        query: &str,
+        k: usize,
+        on_partial: &mut dyn FnMut(Vec<SearchResult>),
+    ) -> Result<Vec<SearchResult>> {
+        let store = self.open_scoped_store()?;
+        self.search_store(&store, query, None, k, Some(on_partial))
+    }
 
-        let query_tokens = self.embedder.embed_query(query)?;
+    /// Open the store once for a series of searches (`og --repl`).
+    pub fn open_session(&self) -> Result<SearchSession> {
+        Ok(SearchSession {
+            store: self.open_scoped_store()?,
+        })
+    }
+
+    /// Hybrid search against a store opened with `open_session`.
+    pub fn search_session(
+        &self,
+        session: &SearchSession,
+        query: &str,
+        k: usize,
+    ) -> Result<Vec<SearchResult>> {
+        self.search_store(&session.store, query, None, k, None)
+    }
+
+    /// Search with the cache and query log. `query_tokens` are the query's
+    /// embeddings when already computed (batched), else embedded here.
+    fn search_store(
+        &self,
+        store: &Store,
+        query: &str,
+        query_tokens: Option<Array2<f32>>,
+        k: usize,
+        on_partial: Option<&mut dyn FnMut(Vec<SearchResult>)>,
+    ) -> Result<Vec<SearchResult>> {
+        let t0 = Instant::now();
+        let query = &normalize_query(query);
+
+        // Streaming callers need the partial results, --changed-since depends
+        // on file times, --signatures re-ranks, --and-file filters by another
+        // file's neighbours, and --strip-stopwords changes the BM25 query, so
+        // all bypass the cache
+        let key = (on_partial.is_none()
+            && self.changed_files.is_none()
+            && !self.signatures
+            && self.and_file_ids.is_none()
+            && !self.strip_stopwords)
+            .then(|| {
+                cache::Key::new(
+                    &self.index_dir,
+                    query,
+                    k,
+                    self.search_scope.as_deref(),
+                    self.search_mode,
+                    self.alpha,
+                    self.context.as_ref().map(|(file, _)| file.as_str()),
+                )
+            });
+        let output = match key.as_ref().and_then(cache::get) {
+            Some(cached) => cached,
+            None => {
+                let output = self.run_search(store, query, query_tokens, k, on_partial)?;
+                if let Some(key) = key {
+                    cache::insert(key, output.clone());
+                }
+                output
+            }
+        };
+
+        if query_log::enabled() {
+            let logged: Vec<(String, f32)> =
+                output.iter().map(|r| (r.id.clone(), r.score)).collect();
+            query_log::append(&self.root.to_string_lossy(), query, &logged, t0.elapsed());
+        }
+
+        Ok(output)
+    }
+
+    fn run_search(
+        &self,
+        store: &Store,
+        query: &str,
+        query_tokens: Option<Array2<f32>>,
+        k: usize,
+        on_partial: Option<&mut dyn FnMut(Vec<SearchResult>)>,
+    ) -> Result<Vec<SearchResult>> {
+        if !self.signatures {
+            let (semantic_results, bm25_results) =
+                self.retrieve(store, query, query_tokens, k, on_partial)?;
+            return Ok(self.merge_candidates(semantic_results, bm25_results, k));
+        }
+
+        check_query(query)?;
+        let query_tokens = match query_tokens {
+            Some(tokens) => tokens,
+            None => self.embedder()?.embed_query(query)?,
+        };
+        let fetch_k = k.saturating_mul(SIGNATURE_OVERFETCH);
+        let (semantic_results, bm25_results) = self.retrieve(
+            store,
+            query,
+            Some(query_tokens.clone()),
+            fetch_k,
+            on_partial,
+        )?;
+        let candidates = self.merge_candidates(semantic_results, bm25_results, fetch_k);
+        self.rerank_by_signature(&query_tokens, candidates, k)
+    }
+
+    /// Rescore candidates by MaxSim between the query and each block's
+    /// signature (see `signature::signature`), embedded on the fly, and keep
+    /// the top `k`. Scores become the mean best token similarity, in [-1, 1].
+    fn rerank_by_signature(
+        &self,
+        query_tokens: &Array2<f32>,
+        mut candidates: Vec<SearchResult>,
+        k: usize,
+    ) -> Result<Vec<SearchResult>> {
+        let signatures: Vec<String> = candidates
+            .iter()
+            .map(|r| {
+                let signature = signature::signature(r.content.as_deref().unwrap_or(""));
+                if signature.is_empty() {
+                    format!("{} {}", r.block_type, r.name)
+                } else {
+                    signature
+                }
+            })
+            .collect();
+
+        let embedder = self.embedder()?;
+        let mut scores = Vec::with_capacity(signatures.len());
+        for batch in signatures.chunks(embedder::MODEL.batch_size) {
+            self.check_deadline()?;
+            let texts: Vec<&str> = batch.iter().map(String::as_str).collect();
+            let embedded = embedder.embed_documents(&texts)?;
+            scores.extend(
+                embedded
+                    .embeddings
+                    .iter()
+                    .map(|doc| signature::mean_max_sim(query_tokens, doc)),
+            );
+        }
+
+        for (r, score) in candidates.iter_mut().zip(scores) {
+            r.score = score;
+        }
+        candidates.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        candidates.truncate(k);
+        Ok(candidates)
+    }
+
+    /// Search like `search`, but keep each candidate's score from the semantic
+    /// and BM25 paths alongside the merged result (`og why`).
+    pub fn search_with_scores(&self, query: &str, k: usize) -> Result<Vec<CandidateScores>> {
+        Ok(self.search_trace(query, k)?.merged)
+    }
+
+    /// Like `search_with_scores`, also keeping each path's full candidate
+    /// list by block ID (`--trace-json`).
+    pub fn search_trace(&self, query: &str, k: usize) -> Result<SearchTrace> {
+        let store = self.open_scoped_store()?;
+        let query = &normalize_query(query);
+        let (semantic_results, bm25_results) = self.retrieve(&store, query, None, k, None)?;
+        let ids = |results: &[omendb::SearchResult]| -> Vec<(String, f32)> {
+            let mut ids: Vec<_> = results.iter().map(|r| (r.id.clone(), r.distance)).collect();
+            ids.sort_by(|a, b| b.1.total_cmp(&a.1));
+            ids
+        };
+        let semantic_ids = ids(&semantic_results);
+        let bm25_ids = ids(&bm25_results);
+
+        let key = |r: &SearchResult| (r.file.clone(), r.line, r.name.clone());
+        let scores = |results: &[omendb::SearchResult]| -> HashMap<_, f32> {
+            results
+                .iter()
+                .map(|r| (key(&self.result_from_omendb(r)), r.distance))
+                .collect()
+        };
+        let semantic = scores(&semantic_results);
+        let bm25 = scores(&bm25_results);
+
+        let merged = self
+            .merge_candidates(semantic_results, bm25_results, k)
+            .into_iter()
+            .map(|result| CandidateScores {
+                semantic: semantic.get(&key(&result)).copied(),
+                bm25: bm25.get(&key(&result)).copied(),
+                result,
+            })
+            .collect();
+        Ok(SearchTrace {
+            semantic: semantic_ids,
+            bm25: bm25_ids,
+            merged,
+        })
+    }
+
+    /// Re-run retrieval for a query that returned nothing and report what each
+    /// stage saw (`--explain-empty`). `top` holds the best in-scope candidates
+    /// before any result filters or threshold.
+    pub fn diagnose(&self, query: &str, k: usize) -> Result<SearchDiagnostics> {
+        let store = self.open_scoped_store()?;
+        let query = &normalize_query(query);
+        let (semantic_results, bm25_results) = self.retrieve(&store, query, None, k, None)?;
+        let (semantic, bm25) = (semantic_results.len(), bm25_results.len());
+
+        let merged = self.merge_by_id(semantic_results, bm25_results);
+        let in_scope = self.rank_candidates(merged.iter(), usize::MAX);
+        let options = self.options().unwrap_or_default();
+        let mut bm25_terms: Vec<String> = self
+            .bm25_query(query, &options)?
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect();
+        bm25_terms.sort_unstable();
+        bm25_terms.dedup();
+
+        Ok(SearchDiagnostics {
+            blocks: Manifest::load(&self.index_dir)?
+                .files
+                .values()
+                .map(|e| e.blocks.len())
+                .sum(),
+            semantic,
+            bm25,
+            merged: merged.len(),
+            out_of_scope: merged.len() - in_scope.len(),
+            scope: self.search_scope.clone(),
+            top: in_scope,
+            bm25_terms,
+        })
+    }
+
+    /// Run the semantic and BM25 retrieval paths enabled by the search mode.
+    fn retrieve(
+        &self,
+        store: &Store,
+        query: &str,
+        query_tokens: Option<Array2<f32>>,
+        k: usize,
+        on_partial: Option<&mut dyn FnMut(Vec<SearchResult>)>,
+    ) -> Result<(Vec<omendb::SearchResult>, Vec<omendb::SearchResult>)> {
+        check_query(query)?;
+        let mut query_tokens = match query_tokens {
+            Some(tokens) => tokens,
+            None => self.embedder()?.embed_query(query)?,
+        };
+        if let Some((_, context)) = &self.context {
+            query_tokens =
+                ndarray::concatenate(ndarray::Axis(0), &[query_tokens.view(), context.view()])?;
+        }
+        self.check_deadline()?;
         let tokens: Vec<Vec<f32>> = (0..query_tokens.nrows())
             .map(|r| query_tokens.row(r).to_vec())
             .collect();
         let token_refs: Vec<&[f32]> = tokens.iter().map(|v| v.as_slice()).collect();
 
         // Over-fetch more when scope filtering will discard results
-        let overfetch = if self.search_scope.is_some() {
+        let overfetch = if self.search_scope.is_some()
+            || self.changed_files.is_some()
+            || self.and_file_ids.is_some()
+        {
             SCOPE_OVERFETCH
         } else {
             1
         };
         let search_k = k.saturating_mul(overfetch);
 
-        // Run both BM25+MaxSim and pure semantic search, merge by ID
-        let bm25_query = crate::synonyms::expand_query(&split_identifiers(query));
-        let bm25_results =
-            store.search_multi_with_text(&bm25_query, &token_refs, search_k, None)?;
-        let semantic_results =
-            store.query_with_options(&token_refs, search_k, &SearchOptions::default())?;
+        // Run pure semantic and/or BM25+MaxSim search (per mode), merge by ID
+        let mut semantic_results = if self.search_mode == SearchMode::Bm25Only {
+            Vec::new()
+        } else {
+            store.query_with_options(&token_refs, search_k, &SearchOptions::default())?
+        };
+        if let Some(floor) = min_semantic_from_env() {
+            semantic_results.retain(|r| r.distance >= floor);
+        }
+        if let Some(on_partial) = on_partial {
+            on_partial(self.rank_candidates(semantic_results.iter(), k));
+        }
+        self.check_deadline()?;
+        let bm25_results = if self.search_mode == SearchMode::SemanticOnly {
+            Vec::new()
+        } else {
+            let options = self.options().unwrap_or_default();
+            let bm25_query = self.bm25_query(query, &options)?;
+            store.search_multi_with_text(&bm25_query, &token_refs, search_k, None)?
+        };
+        Ok((semantic_results, bm25_results))
+    }
+
+    /// Merge both paths by ID (max score, or alpha fusion) and keep the top `k`.
+    fn merge_candidates(
+        &self,
+        semantic_results: Vec<omendb::SearchResult>,
+        bm25_results: Vec<omendb::SearchResult>,
+        k: usize,
+    ) -> Vec<SearchResult> {
+        let merged = self.merge_by_id(semantic_results, bm25_results);
+        self.rank_candidates(merged.iter(), k)
+    }
+
+    /// Merge both paths by ID, before scope filtering.
+    fn merge_by_id(
+        &self,
+        semantic_results: Vec<omendb::SearchResult>,
+        bm25_results: Vec<omendb::SearchResult>,
+    ) -> Vec<omendb::SearchResult> {
+        let fused = self.alpha.map(|alpha| {
+            let scores = |results: &[omendb::SearchResult]| -> Vec<(String, f32)> {
+                results.iter().map(|r| (r.id.clone(), r.distance)).collect()
+            };
+            fuse_scores(&scores(&semantic_results), &scores(&bm25_results), alpha)
+        });
 
         // Merge: keep higher score per ID
         let mut best: HashMap<String, omendb::SearchResult> =
@@ -285,21 +1169,46 @@ impl SemanticIndex {
         merge(bm25_results);
         merge(semantic_results);
 
+        if let Some(fused) = fused {
+            for (id, r) in best.iter_mut() {
+                r.distance = fused.get(id).copied().unwrap_or(0.0);
+            }
+        }
+
+        best.into_values().collect()
+    }
+
+    /// Apply the search scope, convert, and keep the top `k` by score.
+    fn rank_candidates<'r>(
+        &self,
+        candidates: impl Iterator<Item = &'r omendb::SearchResult>,
+        k: usize,
+    ) -> Vec<SearchResult> {
         let mut output = Vec::new();
-        for r in best.into_values() {
+        for r in candidates {
+            let file = r
+                .metadata
+                .get("file")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
             if let Some(scope) = &self.search_scope {
-                let file = r
-                    .metadata
-                    .get("file")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("");
                 let scope = scope.as_str();
                 if file != scope && !file.starts_with(&format!("{scope}/")) {
                     continue;
                 }
             }
+            if let Some(changed) = &self.changed_files {
+                if !changed.contains(file) {
+                    continue;
+                }
+            }
+            if let Some(ids) = &self.and_file_ids {
+                if !ids.contains(&r.id) {
+                    continue;
+                }
+            }
 
-            output.push(self.result_from_omendb(&r));
+            output.push(self.result_from_omendb(r));
         }
 
         output.sort_by(|a, b| {
@@ -308,7 +1217,7 @@ impl SemanticIndex {
                 .unwrap_or(std::cmp::Ordering::Equal)
         });
         output.truncate(k);
-        Ok(output)
+        output
     }
 
     /// Find blocks similar to a given file/block.
@@ -321,7 +1230,49 @@ impl SemanticIndex {
     ) -> Result<Vec<SearchResult>> {
         let manifest = Manifest::load(&self.index_dir)?;
         let store = self.open_store()?;
+        let (blocks, block_id) = self.resolve_block(&manifest, &store, file_path, line, name)?;
+
+        // Get the block's token embeddings and search with MaxSim reranking
+        let (query_tokens, _meta) = store
+            .get_tokens(&block_id)
+            .with_context(|| "Could not retrieve block token embeddings")?;
+
+        let block_set: std::collections::HashSet<&str> =
+            blocks.iter().map(|s| s.as_str()).collect();
+        self.similar_to_tokens(&store, &query_tokens, k, &block_set)
+    }
+
+    /// Content of the block a file reference points at, for `@file#name`
+    /// references spliced into queries.
+    pub fn reference_content(&self, file_ref: &FileRef) -> Result<String> {
+        let (file_path, line, name) = match file_ref {
+            FileRef::ByName { path, name } => (path, None, Some(name.as_str())),
+            FileRef::ByLine { path, line } => (path, Some(*line), None),
+            FileRef::ByFile { path } => (path, None, None),
+        };
+        let file_path = Path::new(file_path)
+            .canonicalize()
+            .unwrap_or_else(|_| PathBuf::from(file_path));
+        let manifest = Manifest::load(&self.index_dir)?;
+        let store = self.open_store()?;
+        let (_, block_id) =
+            self.resolve_block(&manifest, &store, &file_path.to_string_lossy(), line, name)?;
+        store
+            .get_metadata_by_id(&block_id)
+            .and_then(|meta| meta.get("content")?.as_str().map(str::to_string))
+            .with_context(|| format!("Could not retrieve content of {block_id}"))
+    }
 
+    /// The blocks of `file_path` and the ID of the one a reference targets:
+    /// the block named `name`, the innermost block at `line`, or the first.
+    fn resolve_block<'m>(
+        &self,
+        manifest: &'m Manifest,
+        store: &Store,
+        file_path: &str,
+        line: Option<usize>,
+        name: Option<&str>,
+    ) -> Result<(&'m [String], String)> {
         let rel_path = self.to_relative(&PathBuf::from(file_path));
         let entry = manifest
             .files
@@ -332,31 +1283,62 @@ impl SemanticIndex {
             bail!("No blocks found in {rel_path}");
         }
 
-        // Find target block
         let block_id = if let Some(name) = name {
-            find_block_by_name(&store, &entry.blocks, name)?
+            find_block_by_name(store, &entry.blocks, name)?
         } else if let Some(line) = line {
-            find_block_by_line(&store, &entry.blocks, line)
+            find_block_by_line(store, &entry.blocks, line)
                 .unwrap_or_else(|| entry.blocks[0].clone())
         } else {
             entry.blocks[0].clone()
         };
+        Ok((&entry.blocks, block_id))
+    }
 
-        // Get the block's token embeddings and search with MaxSim reranking
-        let (query_tokens, _meta) = store
-            .get_tokens(&block_id)
-            .with_context(|| "Could not retrieve block token embeddings")?;
+    /// Query length in tokens before the tokenizer truncates it to `query_max_length`.
+    pub fn query_token_count(&self, query: &str) -> Result<usize> {
+        self.embedder()?.count_query_tokens(query)
+    }
 
-        let token_refs: Vec<&[f32]> = query_tokens.iter().map(|v| v.as_slice()).collect();
-        let search_k = k.saturating_mul(3).saturating_add(entry.blocks.len());
-        let results = store.query_with_options(&token_refs, search_k, &SearchOptions::default())?;
+    /// Split `query` at word boundaries into parts of at most `max_tokens` tokens.
+    pub fn split_query(&self, query: &str, max_tokens: usize) -> Result<Vec<String>> {
+        let embedder = self.embedder()?;
+        split_words(query, max_tokens, |text| embedder.count_query_tokens(text))
+    }
 
-        let block_set: std::collections::HashSet<&str> =
-            entry.blocks.iter().map(|s| s.as_str()).collect();
+    /// Find code similar to an arbitrary snippet (`og similar --from-text`).
+    /// The snippet is embedded document-style, like indexed blocks, so it is
+    /// compared the same way `find_similar` compares a stored block.
+    pub fn find_similar_text(&self, text: &str, k: usize) -> Result<Vec<SearchResult>> {
+        let store = self.open_store()?;
+        let embeddings = self.embedder()?.embed_documents(&[text])?;
+        let tokens: Vec<Vec<f32>> = embeddings
+            .embeddings
+            .first()
+            .context("Embedder returned no embeddings")?
+            .rows()
+            .into_iter()
+            .take(embedder::MAX_STORED_TOKENS)
+            .map(|r| r.to_vec())
+            .collect();
+        self.similar_to_tokens(&store, &tokens, k, &std::collections::HashSet::new())
+    }
+
+    /// MaxSim search from token embeddings, skipping `exclude` IDs and blocks
+    /// rejected by the doc filter.
+    fn similar_to_tokens(
+        &self,
+        store: &Store,
+        tokens: &[Vec<f32>],
+        k: usize,
+        exclude: &std::collections::HashSet<&str>,
+    ) -> Result<Vec<SearchResult>> {
+        let token_refs: Vec<&[f32]> = tokens.iter().map(|v| v.as_slice()).collect();
+        let search_k = k.saturating_mul(3).saturating_add(exclude.len());
+        let results = store.query_with_options(&token_refs, search_k, &SearchOptions::default())?;
 
         let mut output = Vec::new();
         for r in results {
-            if block_set.contains(r.id.as_str()) {
+            if exclude.contains(r.id.as_str()) {
                 continue;
             }
 
@@ -366,7 +1348,7 @@ impl SemanticIndex {
                 .and_then(|v| v.as_str())
                 .unwrap_or("");
 
-            if DOC_BLOCK_TYPES.contains(&block_type) {
+            if !self.doc_filter.keeps(DOC_BLOCK_TYPES.contains(&block_type)) {
                 continue;
             }
 
@@ -394,7 +1376,7 @@ impl SemanticIndex {
 
     /// Check if index exists.
     pub fn is_indexed(&self) -> bool {
-        self.index_dir.join("manifest.json").exists()
+        Manifest::exists(&self.index_dir)
     }
 
     /// Count indexed blocks.
@@ -434,7 +1416,8 @@ impl SemanticIndex {
     }
 
     /// Compare file metadata against manifest mtimes.
-    /// Returns (maybe_changed paths, deleted rel_paths).
+    /// Returns (maybe_changed paths, deleted rel_paths). Indexed commit
+    /// messages are not files, so only `og build` refreshes them.
     fn mtime_diff(
         &self,
         metadata: &HashMap<PathBuf, walker::FileMetadata>,
@@ -456,7 +1439,7 @@ impl SemanticIndex {
         let deleted: Vec<String> = manifest
             .files
             .keys()
-            .filter(|k| !current_rel_files.contains(*k))
+            .filter(|k| !current_rel_files.contains(*k) && !git::is_commit_path(k))
             .cloned()
             .collect();
 
@@ -472,6 +1455,60 @@ impl SemanticIndex {
         Ok(self.mtime_diff(metadata, &manifest))
     }
 
+    /// Warning text when the tree on disk looks unrelated to the one the index
+    /// was built from (e.g. an index copied into another checkout): most sampled
+    /// files missing or changed, or a file count off by more than 2x.
+    /// `disk_files` is the number of indexable files found by a scan, if any.
+    pub fn fingerprint_warning(&self, disk_files: Option<usize>) -> Result<Option<String>> {
+        let manifest = Manifest::load(&self.index_dir)?;
+        let Some(fingerprint) = &manifest.fingerprint else {
+            return Ok(None);
+        };
+
+        let sample = manifest.sample_paths();
+        let disk_hash = |rel_path: &str| {
+            let raw = std::fs::read(self.root.join(rel_path)).ok()?;
+            let (content, _) = walker::decode(raw, manifest.options.transcode)?;
+            Some(hash_content(&content))
+        };
+        if Manifest::sample_hash(&sample, disk_hash) == fingerprint.sample_hash
+            && disk_files.is_none_or(|n| n == fingerprint.files)
+        {
+            return Ok(None);
+        }
+
+        let differing = sample
+            .iter()
+            .filter(|p| disk_hash(p) != manifest.files.get(**p).map(|e| e.hash.clone()))
+            .count();
+        let count_off = disk_files.is_some_and(|n| {
+            fingerprint.files >= FINGERPRINT_MIN_FILES
+                && (n > fingerprint.files * 2 || n * 2 < fingerprint.files)
+        });
+        if differing * 2 <= sample.len() && !count_off {
+            return Ok(None);
+        }
+
+        let mut message = format!(
+            "Index at {} looks stale: {differing} of {} sampled files are missing or changed",
+            self.index_dir.display(),
+            sample.len()
+        );
+        if let Some(n) = disk_files {
+            message.push_str(&format!(
+                ", {n} files on disk vs {} indexed",
+                fingerprint.files
+            ));
+        }
+        if self.root.file_name().map(|n| n.to_string_lossy())
+            != Some(fingerprint.root.as_str().into())
+        {
+            message.push_str(&format!(" (built in '{}')", fingerprint.root));
+        }
+        message.push_str(". Run 'og build --force' to rebuild.");
+        Ok(Some(message))
+    }
+
     /// Check for stale files and update if needed. Single manifest load.
     /// Uses metadata for fast pre-check, only reads content for changed files.
     pub fn check_and_update(
@@ -495,13 +1532,8 @@ impl SemanticIndex {
                 Ok(data) => data,
                 Err(_) => continue,
             };
-            let check_len = raw.len().min(8192);
-            if raw[..check_len].contains(&0) {
+            let Some((content, _)) = walker::decode(raw, manifest.options.transcode) else {
                 continue;
-            }
-            let content = match String::from_utf8(raw) {
-                Ok(s) => s,
-                Err(_) => continue,
             };
             let rel_path = self.to_relative(path);
             let file_hash = hash_content(&content);
@@ -519,21 +1551,23 @@ impl SemanticIndex {
 
         let actual_stale = changed_files.len() + deleted.len();
 
-        // Delete vectors for deleted files (reuse already-loaded manifest)
+        // Move renamed files, then delete vectors for deleted files (reuse already-loaded manifest)
+        let mut deleted = deleted;
         let mut deleted_count = 0;
+        let renamed;
         {
             let mut store = self.open_store()?;
+            renamed =
+                self.apply_renames(&mut store, &mut manifest, &mut changed_files, &mut deleted)?;
 
             for rel_path in &deleted {
                 if let Some(entry) = manifest.files.remove(rel_path) {
-                    for block_id in &entry.blocks {
-                        let _ = store.delete(block_id);
-                    }
+                    store.delete_blocks(&entry);
                     deleted_count += entry.blocks.len();
                 }
             }
 
-            if deleted_count > 0 {
+            if deleted_count > 0 || renamed > 0 {
                 store.flush()?;
                 manifest.save(&self.index_dir)?;
             }
@@ -541,6 +1575,7 @@ impl SemanticIndex {
 
         let mut stats = self.index(&changed_files, None)?;
         stats.deleted += deleted_count;
+        stats.renamed = renamed;
         Ok((actual_stale, Some(stats)))
     }
 
@@ -563,7 +1598,7 @@ impl SemanticIndex {
     /// Incremental update.
     pub fn update(&self, files: &HashMap<PathBuf, (String, u64)>) -> Result<IndexStats> {
         let mut manifest = Manifest::load(&self.index_dir)?;
-        let (changed, deleted) = self.get_stale_files_with_manifest(files, &manifest);
+        let (changed, mut deleted) = self.get_stale_files_with_manifest(files, &manifest);
 
         if changed.is_empty() && deleted.is_empty() {
             return Ok(IndexStats {
@@ -572,44 +1607,199 @@ impl SemanticIndex {
             });
         }
 
-        // Delete vectors for deleted files in a scoped block
-        // so the store lock is released before self.index() re-acquires it
+        let mut changed_files: HashMap<PathBuf, (String, u64)> = changed
+            .into_iter()
+            .filter_map(|p| files.get(&p).map(|c| (p, c.clone())))
+            .collect();
+
+        // Move renamed files and delete vectors for deleted files in a scoped
+        // block so the store lock is released before self.index() re-acquires it
         let mut deleted_count = 0;
+        let renamed;
         {
             let mut store = self.open_store()?;
+            renamed =
+                self.apply_renames(&mut store, &mut manifest, &mut changed_files, &mut deleted)?;
 
             for rel_path in &deleted {
                 if let Some(entry) = manifest.files.remove(rel_path) {
-                    for block_id in &entry.blocks {
-                        let _ = store.delete(block_id);
-                    }
+                    store.delete_blocks(&entry);
                     deleted_count += entry.blocks.len();
                 }
             }
 
-            if deleted_count > 0 {
+            if deleted_count > 0 || renamed > 0 {
                 store.flush()?;
                 manifest.save(&self.index_dir)?;
             }
         }
 
         // Re-index changed files (opens store internally)
-        let changed_files: HashMap<PathBuf, (String, u64)> = changed
-            .into_iter()
-            .filter_map(|p| files.get(&p).map(|c| (p, c.clone())))
-            .collect();
-
         let mut stats = self.index(&changed_files, None)?;
         stats.deleted += deleted_count;
+        stats.renamed = renamed;
+        Ok(stats)
+    }
+
+    /// Detect renames: a new file whose content hash matches a deleted file's
+    /// takes over its stored vectors under the new path instead of being
+    /// re-embedded. Moved paths are removed from `changed` and `deleted`.
+    /// Returns the number of files moved.
+    fn apply_renames(
+        &self,
+        store: &mut Store,
+        manifest: &mut Manifest,
+        changed: &mut HashMap<PathBuf, (String, u64)>,
+        deleted: &mut Vec<String>,
+    ) -> Result<usize> {
+        let mut by_hash: HashMap<&str, &str> = deleted
+            .iter()
+            .filter_map(|p| Some((manifest.files.get(p)?.hash.as_str(), p.as_str())))
+            .collect();
+        if by_hash.is_empty() {
+            return Ok(0);
+        }
+
+        // Pick every pair first; the manifest is borrowed by `by_hash` until then
+        let mut moves: Vec<(PathBuf, String, String)> = Vec::new();
+        for (path, (content, _)) in changed.iter() {
+            let rel_path = self.to_relative(path);
+            if manifest.files.contains_key(&rel_path)
+                || (!manifest.options.keep_minified
+                    && walker::is_minified_or_generated(&rel_path, content))
+            {
+                continue;
+            }
+            if let Some(old_path) = by_hash.remove(hash_content(content).as_str()) {
+                moves.push((path.clone(), old_path.to_string(), rel_path));
+            }
+        }
+
+        let mut renamed = 0;
+        for (path, old_path, rel_path) in moves {
+            let entry = &manifest.files[&old_path];
+            let hash = entry.hash.clone();
+            // Every block must still be in the store, or the file is re-embedded
+            let Some(blocks) = entry
+                .blocks
+                .iter()
+                .map(|id| {
+                    let new_id = moved_block_id(id, &old_path, &rel_path)?;
+                    let (tokens, metadata) = store.get_tokens(id)?;
+                    Some((new_id, tokens, metadata))
+                })
+                .collect::<Option<Vec<_>>>()
+            else {
+                continue;
+            };
+
+            let mut ids = Vec::with_capacity(blocks.len());
+            for (new_id, tokens, mut metadata) in blocks {
+                metadata["file"] = serde_json::json!(rel_path);
+                let bm25_text = bm25_document(
+                    &embedding_text_from_metadata(&metadata),
+                    &rel_path,
+                    &manifest.options,
+                );
+                store.store_with_text(&new_id, tokens, &bm25_text, metadata)?;
+                ids.push(new_id);
+            }
+            log::debug!("rename from={old_path} to={rel_path} blocks={}", ids.len());
+
+            if let Some(old) = manifest.files.remove(&old_path) {
+                store.delete_blocks(&old);
+            }
+            let (_, mtime) = changed.remove(&path).unwrap_or_default();
+            let shard = store.shard_for(&rel_path);
+            manifest.files.insert(
+                rel_path,
+                FileEntry {
+                    hash,
+                    blocks: ids,
+                    mtime,
+                    shard,
+                },
+            );
+            deleted.retain(|p| *p != old_path);
+            renamed += 1;
+        }
+        Ok(renamed)
+    }
+
+    /// Rewrite the store with only the blocks listed in the manifest, dropping
+    /// orphaned vectors left behind by incremental updates. BM25 text is
+    /// regenerated with the current tokenizer.
+    ///
+    /// Returns stats with `blocks` kept, `deleted` orphans reclaimed, and
+    /// `errors` manifest blocks missing from the store.
+    pub fn vacuum(&self) -> Result<IndexStats> {
+        self.rewrite_store(&Manifest::load(&self.index_dir)?)
+    }
+
+    /// Regenerate every block's BM25 text under `options`, reusing stored
+    /// embeddings, and record the options. For changes that only affect BM25
+    /// text (see `IndexOptions::differs_only_in_bm25`), this avoids re-embedding.
+    pub fn rebuild_text(&self, options: IndexOptions) -> Result<IndexStats> {
+        let mut manifest = Manifest::load(&self.index_dir)?;
+        manifest.options = options;
+        let stats = self.rewrite_store(&manifest)?;
+        manifest.save(&self.index_dir)?;
+        log::info!(
+            "bm25 rebuild blocks={} orphans={}",
+            stats.blocks,
+            stats.deleted
+        );
+        Ok(stats)
+    }
+
+    /// Copy the manifest's blocks into a fresh store with BM25 text built
+    /// from `manifest.options`, then swap it into place.
+    fn rewrite_store(&self, manifest: &Manifest) -> Result<IndexStats> {
+        let vacuum_path = self.index_dir.join(VACUUM_DIR);
+        let mut stats = IndexStats::default();
+
+        // Copy live blocks in a scoped block so both stores are closed before the swap
+        let before = {
+            let old = self.open_store()?;
+
+            remove_store_files(&vacuum_path)?;
+            let mut store = Store::create(&vacuum_path, manifest.options.shard)
+                .context("Failed to create vacuum store")?;
+
+            for block_id in manifest.files.values().flat_map(|e| &e.blocks) {
+                let Some((tokens, metadata)) = old.get_tokens(block_id) else {
+                    stats.errors += 1;
+                    continue;
+                };
+                let file = metadata.get("file").and_then(|v| v.as_str()).unwrap_or("");
+                let bm25_text = bm25_document(
+                    &embedding_text_from_metadata(&metadata),
+                    file,
+                    &manifest.options,
+                );
+                store.store_with_text(block_id, tokens, &bm25_text, metadata)?;
+                stats.blocks += 1;
+            }
+            store.flush()?;
+            old.len()
+        };
+
+        // Swap the compacted store into place
+        let vectors_path = self.vectors_path.as_path();
+        remove_store_files(vectors_path)?;
+        for (from, to) in store_files(&vacuum_path).zip(store_files(vectors_path)) {
+            if from.exists() {
+                std::fs::rename(&from, &to)?;
+            }
+        }
+
+        stats.deleted = before.saturating_sub(stats.blocks);
         Ok(stats)
     }
 
     /// Delete the entire index.
     pub fn clear(&self) -> Result<()> {
-        if self.index_dir.exists() {
-            std::fs::remove_dir_all(&self.index_dir)?;
-        }
-        Ok(())
+        clear_index_dir(&self.index_dir)
     }
 
     /// Remove all blocks matching a path prefix.
@@ -633,9 +1823,7 @@ impl SemanticIndex {
 
         for rel_path in &to_remove {
             if let Some(entry) = manifest.files.remove(rel_path) {
-                for block_id in &entry.blocks {
-                    let _ = store.delete(block_id);
-                }
+                store.delete_blocks(&entry);
                 stats.blocks += entry.blocks.len();
                 stats.files += 1;
             }
@@ -654,6 +1842,7 @@ impl SemanticIndex {
             .and_then(|v| v.as_str())
             .unwrap_or("");
         SearchResult {
+            id: r.id.clone(),
             file: self.to_absolute(file),
             block_type: r
                 .metadata
@@ -683,6 +1872,34 @@ impl SemanticIndex {
                 .and_then(|v| v.as_str())
                 .map(|s| s.to_string()),
             score: r.distance,
+            git: r
+                .metadata
+                .get("author")
+                .and_then(|v| v.as_str())
+                .map(|author| GitInfo {
+                    author: author.to_string(),
+                    commit_time: r
+                        .metadata
+                        .get("commit_time")
+                        .and_then(|v| v.as_u64())
+                        .unwrap_or(0),
+                }),
+            capture: r
+                .metadata
+                .get("capture")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            language: r
+                .metadata
+                .get("language")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            content_hash: r
+                .metadata
+                .get("content_hash")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            ..Default::default()
         }
     }
 
@@ -701,27 +1918,32 @@ impl SemanticIndex {
         }
     }
 
-    /// Open existing multi-vector store (for search/read operations).
-    fn open_store(&self) -> Result<omendb::VectorStore> {
-        omendb::VectorStore::open(&self.vectors_path).context("Failed to open vector store")
+    /// Open the store with every shard (for reads that may touch any file).
+    fn open_store(&self) -> Result<Store> {
+        Store::open(&self.vectors_path, self.sharded(), None)
     }
 
-    /// Open existing store or create a new multi-vector store (for indexing).
-    fn open_or_create_store(&self) -> Result<omendb::VectorStore> {
-        let vectors_path = Path::new(&self.vectors_path);
-        // omendb appends ".omen" to the path for the storage file
-        let mut omen_path = vectors_path.as_os_str().to_os_string();
-        omen_path.push(".omen");
+    /// Open the store for a search: with a sharded index and a search scope,
+    /// only the shard the scope falls in.
+    fn open_scoped_store(&self) -> Result<Store> {
+        let sharded = self.sharded();
+        let shard = if sharded { self.scope_shard() } else { None };
+        Store::open(&self.vectors_path, sharded, shard)
+    }
 
-        if vectors_path.exists() || Path::new(&omen_path).exists() {
-            omendb::VectorStore::open(&self.vectors_path).context("Failed to open vector store")
-        } else {
-            omendb::VectorStore::multi_vector_with(
-                embedder::MODEL.token_dim,
-                omendb::MultiVectorConfig::compact(),
-            )?
-            .persist(&self.vectors_path)
-            .context("Failed to create vector store")
+    /// Whether the index was built with `og build --shard`.
+    fn sharded(&self) -> bool {
+        Manifest::load(&self.index_dir).is_ok_and(|m| m.options.shard)
+    }
+
+    /// The shard holding every file in the search scope: its top-level
+    /// directory, or the root shard for a top-level file.
+    fn scope_shard(&self) -> Option<&str> {
+        let scope = self.search_scope.as_deref()?;
+        match scope.split_once('/') {
+            Some((top, _)) => Some(top),
+            None if self.root.join(scope).is_dir() => Some(scope),
+            None => Some(store::ROOT_SHARD),
         }
     }
 }
@@ -735,7 +1957,7 @@ pub fn find_index_root(search_path: &Path) -> (PathBuf, Option<PathBuf>) {
     let mut current = search_path.clone();
     loop {
         let index_dir = current.join(INDEX_DIR);
-        if index_dir.join("manifest.json").exists() {
+        if Manifest::exists(&index_dir) {
             return (current, Some(index_dir));
         }
         if !current.pop() {
@@ -743,7 +1965,35 @@ pub fn find_index_root(search_path: &Path) -> (PathBuf, Option<PathBuf>) {
         }
     }
 
-    (search_path, None)
+    // No index yet: a workspace file above the path declares where it goes
+    match find_workspace_root(&search_path) {
+        Some(base) => (base, None),
+        None => (search_path, None),
+    }
+}
+
+/// Nearest directory at or above `path` containing an `og.workspace` file.
+pub fn find_workspace_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|dir| dir.join(walker::WORKSPACE_FILE).is_file())
+        .map(Path::to_path_buf)
+}
+
+/// Every directory from `search_path` up to the filesystem root that holds
+/// an index, nearest first. `find_index_root` uses the first of them.
+pub fn find_index_roots(search_path: &Path) -> Vec<PathBuf> {
+    let mut current = search_path
+        .canonicalize()
+        .unwrap_or_else(|_| search_path.to_path_buf());
+    let mut roots = Vec::new();
+    loop {
+        if Manifest::exists(&current.join(INDEX_DIR)) {
+            roots.push(current.clone());
+        }
+        if !current.pop() {
+            return roots;
+        }
+    }
 }
 
 /// Find parent directory with existing index (not at path itself).
@@ -757,7 +2007,7 @@ pub fn find_parent_index(path: &Path) -> Option<PathBuf> {
 
     loop {
         let index_dir = current.join(INDEX_DIR);
-        if index_dir.join("manifest.json").exists() {
+        if Manifest::exists(&index_dir) {
             return Some(current);
         }
         if !current.pop() {
@@ -768,6 +2018,217 @@ pub fn find_parent_index(path: &Path) -> Option<PathBuf> {
     None
 }
 
+/// Greedily pack whitespace-separated words into parts within `max_tokens`.
+/// A single word over the budget becomes its own (truncated) part.
+fn split_words(
+    text: &str,
+    max_tokens: usize,
+    count: impl Fn(&str) -> Result<usize>,
+) -> Result<Vec<String>> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        if current.is_empty() {
+            current = word.to_string();
+            continue;
+        }
+        let candidate = format!("{current} {word}");
+        if count(&candidate)? > max_tokens {
+            parts.push(std::mem::replace(&mut current, word.to_string()));
+        } else {
+            current = candidate;
+        }
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    Ok(parts)
+}
+
+/// Weighted fusion of two result lists: each list's scores are min-max normalized
+/// to [0, 1], then combined as `alpha*semantic + (1-alpha)*bm25`. An ID found by
+/// only one path gets that path's weighted score.
+fn fuse_scores(
+    semantic: &[(String, f32)],
+    bm25: &[(String, f32)],
+    alpha: f32,
+) -> HashMap<String, f32> {
+    fn normalized(results: &[(String, f32)]) -> impl Iterator<Item = (&String, f32)> {
+        let (min, max) = results
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), (_, s)| {
+                (lo.min(*s), hi.max(*s))
+            });
+        let range = max - min;
+        results.iter().map(move |(id, s)| {
+            let norm = if range > f32::EPSILON {
+                (s - min) / range
+            } else {
+                1.0
+            };
+            (id, norm)
+        })
+    }
+
+    let mut fused: HashMap<String, f32> = HashMap::new();
+    for (id, norm) in normalized(semantic) {
+        *fused.entry(id.clone()).or_default() += alpha * norm;
+    }
+    for (id, norm) in normalized(bm25) {
+        *fused.entry(id.clone()).or_default() += (1.0 - alpha) * norm;
+    }
+    fused
+}
+
+/// Delete an index directory, keeping any query overrides and acronym map in it.
+pub fn clear_index_dir(index_dir: &Path) -> Result<()> {
+    if !KEPT_FILES.iter().any(|name| index_dir.join(name).exists()) {
+        if index_dir.exists() {
+            std::fs::remove_dir_all(index_dir)?;
+        }
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(index_dir)? {
+        let entry = entry?;
+        if KEPT_FILES.iter().any(|name| entry.file_name() == *name) {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            std::fs::remove_dir_all(entry.path())?;
+        } else {
+            std::fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// Paths omendb uses for a store at `path` (directory and ".omen" file).
+fn store_files(path: &Path) -> impl Iterator<Item = PathBuf> {
+    let mut omen_path = path.as_os_str().to_os_string();
+    omen_path.push(".omen");
+    [path.to_path_buf(), PathBuf::from(omen_path)].into_iter()
+}
+
+fn remove_store_files(path: &Path) -> Result<()> {
+    for p in store_files(path) {
+        if p.is_dir() {
+            std::fs::remove_dir_all(&p)?;
+        } else if p.exists() {
+            std::fs::remove_file(&p)?;
+        }
+    }
+    Ok(())
+}
+
+/// Text indexed for BM25 (and the matching query text): identifiers split into
+/// their parts unless the index was built with `--no-bm25-expand`.
+fn bm25_text(text: &str, options: &IndexOptions) -> String {
+    if options.no_bm25_expand {
+        text.to_string()
+    } else {
+        split_identifiers_with(text, options.split_min_word, options.split_min_part)
+    }
+}
+
+/// BM25 text stored for a block: `bm25_text` of its content, plus its path
+/// words once when the index was built with `--path-tokens`.
+fn bm25_document(text: &str, file: &str, options: &IndexOptions) -> String {
+    let text = bm25_text(text, options);
+    if !options.path_tokens {
+        return text;
+    }
+    format!("{text}\n{}", bm25_text(&path_words(file), options))
+}
+
+/// Directory and file stem words of a relative path, each once
+/// (`src/auth/session_store.rs` -> `src auth session_store`).
+fn path_words(file: &str) -> String {
+    let path = Path::new(file);
+    let dirs = path.parent().into_iter().flat_map(Path::components);
+    let parts = dirs
+        .map(|c| c.as_os_str())
+        .chain(path.file_stem())
+        .map(|p| p.to_string_lossy());
+    let mut words: Vec<String> = Vec::new();
+    for part in parts {
+        for word in part.split(|c: char| !c.is_alphanumeric() && c != '_') {
+            if !word.is_empty() && !words.iter().any(|w| w == word) {
+                words.push(word.to_string());
+            }
+        }
+    }
+    words.join(" ")
+}
+
+/// `block_id` of a block in `from` rewritten for the same block in `to`.
+/// IDs start with `file:` (see `Block::make_id`).
+fn moved_block_id(block_id: &str, from: &str, to: &str) -> Option<String> {
+    let rest = block_id.strip_prefix(from)?;
+    rest.starts_with(':').then(|| format!("{to}{rest}"))
+}
+
+/// Stored metadata for a block; search results are rebuilt from it.
+fn block_metadata(block: &Block) -> serde_json::Value {
+    let mut metadata = serde_json::json!({
+        "file": block.file,
+        "type": block.block_type,
+        "name": block.name,
+        "start_line": block.start_line,
+        "end_line": block.end_line,
+        "content": block.content,
+    });
+    if let Some(region) = &block.region {
+        metadata["region"] = serde_json::json!(region);
+    }
+    if let Some(context) = &block.context {
+        metadata["context"] = serde_json::json!(context);
+    }
+    if let Some(part) = block.part {
+        metadata["part"] = serde_json::json!(part);
+    }
+    if let Some(capture) = &block.capture {
+        metadata["capture"] = serde_json::json!(capture);
+    }
+    if let Some(language) = &block.language {
+        metadata["language"] = serde_json::json!(language);
+    }
+    metadata["content_hash"] = serde_json::json!(hash_content(&block.content));
+    if let Some(git) = &block.git {
+        metadata["author"] = serde_json::json!(git.author);
+        metadata["commit_time"] = serde_json::json!(git.commit_time);
+    }
+    metadata
+}
+
+/// Which of a changed file's new `blocks` can keep their stored vectors: the
+/// old entry lists the same stable ID and `stored_text` (the embedding text
+/// the store holds for an ID) still matches, so `--embed-context` changes
+/// are re-embedded.
+fn unchanged_blocks(
+    old_ids: &[String],
+    blocks: &[Block],
+    stored_text: impl Fn(&str) -> Option<String>,
+) -> Vec<bool> {
+    let old: HashSet<&str> = old_ids.iter().map(String::as_str).collect();
+    blocks
+        .iter()
+        .map(|block| {
+            old.contains(block.id.as_str())
+                && stored_text(&block.id).is_some_and(|text| text == block.embedding_text())
+        })
+        .collect()
+}
+
+/// Rebuild a block's embedding text from stored metadata (for BM25 re-indexing).
+fn embedding_text_from_metadata(metadata: &serde_json::Value) -> String {
+    let field = |key: &str| metadata.get(key).and_then(|v| v.as_str()).unwrap_or("");
+    let text = format!("{} {}\n{}", field("type"), field("name"), field("content"));
+    match metadata.get("context").and_then(|v| v.as_str()) {
+        Some(context) => format!("{context}\n{text}"),
+        None => text,
+    }
+}
+
 /// Find all .og/ directories under path.
 pub fn find_subdir_indexes(path: &Path, include_root: bool) -> Vec<PathBuf> {
     let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
@@ -780,9 +2241,7 @@ pub fn find_subdir_indexes(path: &Path, include_root: bool) -> Vec<PathBuf> {
         let Ok(entry) = entry else { continue };
         if entry.file_name() == INDEX_DIR && entry.file_type().is_dir() {
             let idx_path = entry.path().to_path_buf();
-            if idx_path.join("manifest.json").exists()
-                && (include_root || idx_path.parent() != Some(&path))
-            {
+            if Manifest::exists(&idx_path) && (include_root || idx_path.parent() != Some(&path)) {
                 indexes.push(idx_path);
             }
         }
@@ -791,11 +2250,7 @@ pub fn find_subdir_indexes(path: &Path, include_root: bool) -> Vec<PathBuf> {
     indexes
 }
 
-fn find_block_by_name(
-    store: &omendb::VectorStore,
-    block_ids: &[String],
-    name: &str,
-) -> Result<String> {
+fn find_block_by_name(store: &Store, block_ids: &[String], name: &str) -> Result<String> {
     let mut matches = Vec::new();
 
     for block_id in block_ids {
@@ -831,24 +2286,281 @@ fn find_block_by_name(
     }
 }
 
-fn find_block_by_line(
-    store: &omendb::VectorStore,
-    block_ids: &[String],
-    line: usize,
-) -> Option<String> {
-    for block_id in block_ids {
-        if let Some(meta) = store.get_metadata_by_id(block_id) {
-            let start = meta.get("start_line").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
-            let end = meta.get("end_line").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
-            if start <= line && line <= end {
-                return Some(block_id.clone());
-            }
-        }
-    }
-    None
+fn find_block_by_line(store: &Store, block_ids: &[String], line: usize) -> Option<String> {
+    let ranges = block_ids.iter().filter_map(|block_id| {
+        let meta = store.get_metadata_by_id(block_id)?;
+        let start = meta.get("start_line").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+        let end = meta.get("end_line").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+        Some((block_id, start, end))
+    });
+    innermost_block(ranges, line).cloned()
+}
+
+/// The smallest `(id, start, end)` range containing `line`, so a line inside a
+/// method (or a decorated function's body) resolves to it rather than to an
+/// enclosing class or decorator wrapper. Ties keep the first range.
+fn innermost_block<T>(ranges: impl Iterator<Item = (T, usize, usize)>, line: usize) -> Option<T> {
+    ranges
+        .filter(|(_, start, end)| (*start..=*end).contains(&line))
+        .fold(
+            None,
+            |best: Option<(T, usize)>, (id, start, end)| match best {
+                Some((_, span)) if span <= end - start => best,
+                _ => Some((id, end - start)),
+            },
+        )
+        .map(|(id, _)| id)
 }
 
 fn hash_content(content: &str) -> String {
     let hash = blake3::hash(content.as_bytes());
     hash.to_hex()[..16].to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scores(items: &[(&str, f32)]) -> Vec<(String, f32)> {
+        items.iter().map(|(id, s)| (id.to_string(), *s)).collect()
+    }
+
+    #[test]
+    fn fuse_scores_weights_both_paths() {
+        let semantic = scores(&[("a", -10.0), ("b", -20.0)]);
+        let bm25 = scores(&[("b", 8.0), ("c", 2.0)]);
+        let fused = fuse_scores(&semantic, &bm25, 0.5);
+
+        // a: top semantic only; b: bottom semantic + top bm25; c: bottom bm25 only
+        assert_eq!(fused["a"], 0.5);
+        assert_eq!(fused["b"], 0.5);
+        assert_eq!(fused["c"], 0.0);
+    }
+
+    #[test]
+    fn path_words_once_per_component() {
+        assert_eq!(path_words("src/auth/auth-session.rs"), "src auth session");
+        assert_eq!(
+            path_words("lib/userService.test.ts"),
+            "lib userService test"
+        );
+
+        let options = IndexOptions {
+            path_tokens: true,
+            ..IndexOptions::default()
+        };
+        let text = bm25_document("fn login() {}", "src/auth/sessionStore.rs", &options);
+        assert!(
+            text.ends_with("src auth sessionStore session store"),
+            "{text}"
+        );
+        assert_eq!(
+            bm25_document("fn login() {}", "src/a.rs", &IndexOptions::default()),
+            bm25_text("fn login() {}", &IndexOptions::default())
+        );
+    }
+
+    #[test]
+    fn stable_ids_survive_line_shifts() {
+        let block = |line: usize, name: &str, content: &str| Block {
+            id: Block::make_id("a.rs", name, content),
+            file: "a.rs".to_string(),
+            block_type: "function".to_string(),
+            name: name.to_string(),
+            start_line: line,
+            end_line: line + 2,
+            content: content.to_string(),
+            ..Default::default()
+        };
+        let ids = |blocks: &[Block]| blocks.iter().map(|b| b.id.clone()).collect::<Vec<_>>();
+
+        let mut before = vec![block(0, "f", "fn f() {}"), block(4, "g", "fn g() {}")];
+        let mut after = vec![block(3, "f", "fn f() {}"), block(7, "g", "fn g() { 1 }")];
+        Block::assign_ids(&mut before);
+        Block::assign_ids(&mut after);
+        assert_eq!(before[0].id, after[0].id);
+        assert_ne!(before[1].id, after[1].id);
+        assert!(before[0].id.starts_with("a.rs:f:"));
+
+        let mut twins = vec![block(0, "f", "x"), block(5, "f", "x")];
+        Block::assign_ids(&mut twins);
+        assert_eq!(ids(&twins)[1], format!("{}~1", twins[0].id));
+    }
+
+    #[test]
+    fn edit_above_block_keeps_its_stored_id() {
+        let mut extractor = Extractor::new();
+        let before = "fn f() {\n    1\n}\n\nfn g() {\n    2\n}\n";
+        let after = "fn f() {\n    10\n}\n\n\n\nfn g() {\n    2\n}\n";
+        let mut old = extractor.extract("a.rs", before).unwrap();
+        let mut new = extractor.extract("a.rs", after).unwrap();
+        Block::assign_ids(&mut old);
+        Block::assign_ids(&mut new);
+
+        // The "store" as the previous build left it
+        let stored: HashMap<String, String> = old
+            .iter()
+            .map(|b| (b.id.clone(), b.embedding_text()))
+            .collect();
+        let old_ids: Vec<String> = old.iter().map(|b| b.id.clone()).collect();
+        let keep = unchanged_blocks(&old_ids, &new, |id| stored.get(id).cloned());
+
+        let g = new.iter().position(|b| b.name == "g").unwrap();
+        let f = new.iter().position(|b| b.name == "f").unwrap();
+        assert!(keep[g], "g moved down but is unchanged");
+        assert!(!keep[f], "f's body changed");
+        assert!(old_ids.contains(&new[g].id));
+        assert_eq!(new[g].start_line, 6);
+
+        // Same ID but a different embedding text (e.g. new context) is re-embedded
+        let keep = unchanged_blocks(&old_ids, &new, |_| Some(String::new()));
+        assert!(!keep[g]);
+    }
+
+    #[test]
+    fn moved_block_id_swaps_path() {
+        let id = Block::make_id("src/old.rs", "parse", "fn parse() {}");
+        assert_eq!(
+            moved_block_id(&id, "src/old.rs", "lib/new.rs"),
+            Some(id.replacen("src/old.rs", "lib/new.rs", 1))
+        );
+        assert_eq!(
+            moved_block_id("src/old.rs.bak:1:x", "src/old.rs", "a.rs"),
+            None
+        );
+    }
+
+    #[test]
+    fn bm25_only_option_changes() {
+        let options = IndexOptions::default();
+        let path_tokens = IndexOptions {
+            path_tokens: true,
+            split_min_part: 1,
+            ..IndexOptions::default()
+        };
+        assert!(options.differs_only_in_bm25(&path_tokens));
+        assert!(!options.differs_only_in_bm25(&options));
+        assert!(!options.differs_only_in_bm25(&IndexOptions {
+            path_tokens: true,
+            embed_docs: true,
+            ..IndexOptions::default()
+        }));
+    }
+
+    #[test]
+    fn build_flags_keep_unpassed_options() {
+        let built = IndexOptions {
+            fallback_lines: 100,
+            with_git: true,
+            shard: true,
+            ..IndexOptions::default()
+        };
+        assert_eq!(
+            crate::types::IndexOptionFlags::default().apply(built.clone()),
+            built
+        );
+
+        let flags = crate::types::IndexOptionFlags {
+            path_tokens: Some(true),
+            ..Default::default()
+        };
+        let merged = flags.apply(built.clone());
+        assert!(merged.with_git && merged.path_tokens);
+        assert!(built.differs_only_in_bm25(&merged));
+    }
+
+    #[test]
+    fn normalize_query_strips_markdown() {
+        assert_eq!(
+            normalize_query("```rust\nfn parse(input: &str)\n```"),
+            "fn parse(input: &str)"
+        );
+        assert_eq!(normalize_query("## Error   handling"), "Error handling");
+        assert_eq!(
+            normalize_query("> quoted\n> `retry_backoff` logic"),
+            "quoted retry_backoff logic"
+        );
+        assert_eq!(normalize_query("#include <stdio.h>"), "#include <stdio.h>");
+        assert_eq!(normalize_query("a > b && c"), "a > b && c");
+        assert_eq!(normalize_query("let s = `tpl"), "let s = `tpl");
+    }
+
+    #[test]
+    fn rejects_blank_queries() {
+        assert!(check_query("").is_err());
+        assert!(check_query(" \t\n").is_err());
+        assert!(check_query(" auth ").is_ok());
+    }
+
+    #[test]
+    fn min_semantic_parses_finite_floats() {
+        assert_eq!(parse_min_semantic(" 12.5 "), Some(12.5));
+        assert_eq!(parse_min_semantic("-3"), Some(-3.0));
+        assert_eq!(parse_min_semantic("NaN"), None);
+        assert_eq!(parse_min_semantic(""), None);
+    }
+
+    #[test]
+    fn split_words_respects_budget() {
+        let words = |t: &str| Ok(t.split_whitespace().count());
+        let parts = split_words("a b c d e", 2, words).unwrap();
+        assert_eq!(parts, ["a b", "c d", "e"]);
+        assert_eq!(split_words("  ", 2, words).unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn fingerprint_detects_unrelated_tree() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path().canonicalize().unwrap();
+        let mut manifest = Manifest::default();
+        for name in ["a.rs", "b.rs", "c.rs", "d.rs"] {
+            let content = format!("fn {}() {{}}\n", &name[..1]);
+            std::fs::write(root.join(name), &content).unwrap();
+            manifest.files.insert(
+                name.to_string(),
+                FileEntry {
+                    hash: hash_content(&content),
+                    blocks: Vec::new(),
+                    mtime: 0,
+                    shard: None,
+                },
+            );
+        }
+        manifest.save(&root.join(INDEX_DIR)).unwrap();
+        let index = SemanticIndex::new(&root, None).unwrap();
+        assert_eq!(index.fingerprint_warning(Some(4)).unwrap(), None);
+
+        // One edited file is normal drift, not a different tree
+        std::fs::write(root.join("a.rs"), "fn changed() {}\n").unwrap();
+        assert_eq!(index.fingerprint_warning(Some(4)).unwrap(), None);
+
+        std::fs::remove_file(root.join("b.rs")).unwrap();
+        std::fs::write(root.join("c.rs"), "fn other() {}\n").unwrap();
+        let warning = index.fingerprint_warning(None).unwrap().unwrap();
+        assert!(warning.contains("3 of 4 sampled files"), "{warning}");
+    }
+
+    #[test]
+    fn innermost_block_wins_for_nested_ranges() {
+        // decorated_definition 10-20 wrapping function 11-20, inside class 0-40
+        let ranges = [("class", 0, 40), ("decorated", 10, 20), ("func", 11, 20)];
+        assert_eq!(innermost_block(ranges.into_iter(), 15), Some("func"));
+        assert_eq!(innermost_block(ranges.into_iter(), 10), Some("decorated"));
+        assert_eq!(innermost_block(ranges.into_iter(), 30), Some("class"));
+        assert_eq!(innermost_block(ranges.into_iter(), 41), None);
+
+        // Equal spans keep the first match
+        let ties = [("a", 5, 8), ("b", 5, 8)];
+        assert_eq!(innermost_block(ties.into_iter(), 6), Some("a"));
+    }
+
+    #[test]
+    fn fuse_scores_alpha_extremes() {
+        let semantic = scores(&[("a", 1.0), ("b", 0.0)]);
+        let bm25 = scores(&[("a", 0.0), ("b", 1.0)]);
+        let semantic_only = fuse_scores(&semantic, &bm25, 1.0);
+        assert!(semantic_only["a"] > semantic_only["b"]);
+        let bm25_only = fuse_scores(&semantic, &bm25, 0.0);
+        assert!(bm25_only["b"] > bm25_only["a"]);
+    }
+}
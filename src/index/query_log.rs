@@ -0,0 +1,48 @@
+use std::io::Write;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Env var naming the JSON-lines file that searches are appended to.
+pub const QUERY_LOG_ENV: &str = "OG_QUERY_LOG";
+
+/// Whether query logging is enabled.
+pub fn enabled() -> bool {
+    log_path().is_some()
+}
+
+fn log_path() -> Option<std::ffi::OsString> {
+    std::env::var_os(QUERY_LOG_ENV).filter(|p| !p.is_empty())
+}
+
+/// Append one JSON line for a search when `OG_QUERY_LOG` is set.
+/// `results` holds (block ID, score). Logging never fails the search:
+/// I/O errors are ignored.
+pub fn append(root: &str, query: &str, results: &[(String, f32)], elapsed: Duration) {
+    let Some(path) = log_path() else {
+        return;
+    };
+
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let entry = serde_json::json!({
+        "ts": ts,
+        "root": root,
+        "query": query,
+        "elapsed_ms": elapsed.as_secs_f64() * 1000.0,
+        "results": results
+            .iter()
+            .map(|(id, score)| serde_json::json!({ "id": id, "score": score }))
+            .collect::<Vec<_>>(),
+    });
+
+    // One write per line so concurrent appenders don't interleave
+    let line = format!("{entry}\n");
+    if let Ok(mut file) = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+    {
+        let _ = file.write_all(line.as_bytes());
+    }
+}
@@ -0,0 +1,101 @@
+use ndarray::Array2;
+
+/// Cap on lines joined for a signature split across lines (long parameter lists).
+const MAX_SIGNATURE_LINES: usize = 8;
+
+/// The declaration of a block without its body (`--signatures`): lines from
+/// the first non-attribute, non-comment line until the parentheses close,
+/// cut at a body-opening `{`. Multi-line signatures are joined with spaces.
+pub fn signature(content: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    let mut depth = 0i32;
+    for line in content.lines().map(str::trim) {
+        if parts.is_empty() && is_preamble(line) {
+            continue;
+        }
+
+        let mut end = line.len();
+        let mut body = false;
+        for (i, c) in line.char_indices() {
+            match c {
+                '(' | '[' => depth += 1,
+                ')' | ']' => depth -= 1,
+                '{' if depth <= 0 => {
+                    end = i;
+                    body = true;
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        let part = line[..end].trim_end();
+        if !part.is_empty() {
+            parts.push(part);
+        }
+        if body
+            || parts.len() >= MAX_SIGNATURE_LINES
+            || (depth <= 0 && !parts.is_empty() && !part.ends_with(','))
+        {
+            break;
+        }
+    }
+    parts.join(" ")
+}
+
+/// Lines that precede a declaration: blanks, attributes, decorators, comments.
+fn is_preamble(line: &str) -> bool {
+    line.is_empty()
+        || line.starts_with("#[")
+        || line.starts_with('@')
+        || line.starts_with("//")
+        || line.starts_with("/*")
+        || line.starts_with('*')
+}
+
+/// Mean over query tokens of the best dot product with any signature token.
+/// Token embeddings are L2-normalized, so this lies in [-1, 1].
+pub fn mean_max_sim(query: &Array2<f32>, doc: &Array2<f32>) -> f32 {
+    if query.nrows() == 0 || doc.nrows() == 0 {
+        return 0.0;
+    }
+    let sims = query.dot(&doc.t());
+    let total: f32 = sims
+        .rows()
+        .into_iter()
+        .map(|row| row.iter().copied().fold(f32::NEG_INFINITY, f32::max))
+        .sum();
+    total / query.nrows() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_stops_at_body() {
+        assert_eq!(
+            signature(
+                "/// Docs\n#[inline]\npub fn handle(req: Request) -> Response {\n    todo!()\n}"
+            ),
+            "pub fn handle(req: Request) -> Response"
+        );
+        assert_eq!(
+            signature("@app.route('/')\ndef index(\n    request,\n    user=None,\n) -> Response:\n    return 1\n"),
+            "def index( request, user=None, ) -> Response:"
+        );
+        assert_eq!(
+            signature("function f(opts = {}) {\n  return opts;\n}"),
+            "function f(opts = {})"
+        );
+        assert_eq!(signature("class Cache:\n    pass\n"), "class Cache:");
+    }
+
+    #[test]
+    fn mean_max_sim_averages_best_matches() {
+        let query = Array2::from_shape_vec((2, 2), vec![1.0, 0.0, 0.0, 1.0]).unwrap();
+        let doc = Array2::from_shape_vec((2, 2), vec![1.0, 0.0, 0.6, 0.8]).unwrap();
+        assert!((mean_max_sim(&query, &doc) - 0.9).abs() < 1e-6);
+        assert_eq!(mean_max_sim(&query, &Array2::zeros((0, 2))), 0.0);
+    }
+}
@@ -0,0 +1,199 @@
+use crate::embedder::{MAX_STORED_TOKENS, MODEL};
+use crate::types::Block;
+
+/// Target size of each part (~tokens), leaving room for the type/name/context header.
+const PART_TOKENS: usize = 384;
+/// Lines carried over from the previous part (~tokens), as in the text chunker.
+const OVERLAP_TOKENS: usize = 50;
+
+/// Tokens the embedder keeps per block; anything past this is invisible to search.
+fn max_tokens() -> usize {
+    MODEL.doc_max_length.min(MAX_STORED_TOKENS)
+}
+
+fn estimate_tokens(text: &str) -> usize {
+    (text.len() / 4).max(1)
+}
+
+/// Split blocks whose embedding text exceeds the model's length into overlapping
+/// line-based parts with the same name and type, tagged with a part index.
+pub fn split_long_blocks(blocks: Vec<Block>) -> Vec<Block> {
+    let mut out = Vec::with_capacity(blocks.len());
+    for block in blocks {
+        if estimate_tokens(&block.embedding_text()) <= max_tokens() {
+            out.push(block);
+        } else {
+            out.extend(split_block(&block));
+        }
+    }
+    out
+}
+
+/// Split captured code blocks longer than `max_lines` that contain no other
+/// block (e.g. a class whose methods the grammar doesn't capture) into
+/// sequential parts of at most `max_lines` lines, tagged with a part index.
+pub fn split_by_lines(blocks: Vec<Block>, max_lines: usize) -> Vec<Block> {
+    let has_child = |block: &Block| {
+        blocks.iter().any(|b| {
+            b.start_line >= block.start_line
+                && b.end_line <= block.end_line
+                && (b.start_line, b.end_line) != (block.start_line, block.end_line)
+        })
+    };
+    let oversized: Vec<bool> = blocks
+        .iter()
+        .map(|b| {
+            b.capture.is_some()
+                && b.part.is_none()
+                && b.end_line.saturating_sub(b.start_line) + 1 > max_lines
+                && !has_child(b)
+        })
+        .collect();
+
+    let mut out = Vec::with_capacity(blocks.len());
+    for (block, oversized) in blocks.into_iter().zip(oversized) {
+        if !oversized {
+            out.push(block);
+            continue;
+        }
+        let lines: Vec<&str> = block.content.lines().collect();
+        out.extend(lines.chunks(max_lines).enumerate().map(|(part, chunk)| {
+            let start_line = block.start_line + part * max_lines;
+            let content = chunk.join("\n");
+            Block {
+                id: Block::make_id(&block.file, &block.name, &content),
+                start_line,
+                end_line: start_line + chunk.len() - 1,
+                content,
+                part: Some(part),
+                ..block.clone()
+            }
+        }));
+    }
+    out
+}
+
+fn split_block(block: &Block) -> Vec<Block> {
+    let lines: Vec<&str> = block.content.lines().collect();
+    let line_tokens = |i: usize| lines[i].len() / 4 + 1;
+
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        let mut end = start;
+        let mut tokens = 0;
+        while end < lines.len() && (end == start || tokens + line_tokens(end) <= PART_TOKENS) {
+            tokens += line_tokens(end);
+            end += 1;
+        }
+        ranges.push((start, end));
+        if end == lines.len() {
+            break;
+        }
+
+        // Step back for overlap, always advancing past the previous start
+        let mut next = end;
+        let mut overlap = 0;
+        while next > start + 1 && overlap + line_tokens(next - 1) <= OVERLAP_TOKENS {
+            next -= 1;
+            overlap += line_tokens(next);
+        }
+        start = next;
+    }
+
+    if ranges.len() <= 1 {
+        return vec![block.clone()];
+    }
+
+    ranges
+        .into_iter()
+        .enumerate()
+        .map(|(part, (start, end))| {
+            let start_line = block.start_line + start;
+            let content = lines[start..end].join("\n");
+            Block {
+                id: Block::make_id(&block.file, &block.name, &content),
+                start_line,
+                end_line: block.start_line + end - 1,
+                content,
+                part: Some(part),
+                ..block.clone()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(lines: usize) -> Block {
+        let content = (0..lines)
+            .map(|i| format!("    let value_{i} = compute_something_long({i});"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Block {
+            id: Block::make_id("a.rs", "big", &content),
+            file: "a.rs".to_string(),
+            block_type: "function".to_string(),
+            name: "big".to_string(),
+            start_line: 10,
+            end_line: 10 + lines - 1,
+            content,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn max_lines_splits_leaf_blocks_only() {
+        let big = Block {
+            capture: Some("class_declaration".to_string()),
+            ..block(25)
+        };
+        let parts = split_by_lines(vec![big.clone()], 10);
+        let ranges: Vec<_> = parts
+            .iter()
+            .map(|b| (b.start_line, b.end_line, b.part))
+            .collect();
+        assert_eq!(
+            ranges,
+            [(10, 19, Some(0)), (20, 29, Some(1)), (30, 34, Some(2))]
+        );
+        assert!(parts.iter().all(|b| b.name == "big"));
+        assert_eq!(parts[2].content.lines().count(), 5);
+
+        // A block with a nested block is left whole
+        let inner = Block {
+            start_line: 12,
+            end_line: 14,
+            ..block(3)
+        };
+        assert_eq!(split_by_lines(vec![big, inner], 10).len(), 2);
+    }
+
+    #[test]
+    fn short_blocks_unchanged() {
+        let blocks = split_long_blocks(vec![block(5)]);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].part, None);
+    }
+
+    #[test]
+    fn long_blocks_split_with_overlap() {
+        let original = block(500);
+        let parts = split_long_blocks(vec![original.clone()]);
+        assert!(parts.len() > 1);
+
+        assert!(parts[0].id.starts_with("a.rs:big:"));
+        assert_ne!(parts[0].id, parts[1].id);
+        assert_eq!(parts[0].start_line, original.start_line);
+        assert_eq!(parts.last().unwrap().end_line, original.end_line);
+        for (i, pair) in parts.windows(2).enumerate() {
+            assert_eq!(pair[0].part, Some(i));
+            assert_eq!(pair[0].name, "big");
+            assert!(pair[1].start_line <= pair[0].end_line, "parts overlap");
+            assert!(pair[1].start_line > pair[0].start_line);
+            assert!(estimate_tokens(&pair[0].embedding_text()) <= max_tokens());
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use omendb::{QueryInput, SearchOptions, SearchResult, VectorStore};
+
+use crate::embedder;
+use crate::index::manifest::FileEntry;
+
+/// Shard of files directly under the index root (`og build --shard`).
+pub const ROOT_SHARD: &str = "@root";
+
+/// Key of the single store of an unsharded index.
+const UNSHARDED: &str = "";
+
+/// Shard holding `rel_path`: its top-level directory, or `ROOT_SHARD`.
+pub fn shard_of(rel_path: &str) -> &str {
+    match rel_path.split_once('/') {
+        Some((top, _)) => top,
+        None => ROOT_SHARD,
+    }
+}
+
+/// The vector store of an index: one omendb store at `.og/vectors`, or with
+/// `og build --shard` one per top-level directory under `.og/vectors/<shard>`.
+/// Reads span every open shard; writes go to the shard of the block's file,
+/// created on first use.
+pub struct Store {
+    path: PathBuf,
+    sharded: bool,
+    shards: BTreeMap<String, VectorStore>,
+}
+
+impl Store {
+    /// Open an existing store. A sharded store opens every shard, or only
+    /// `only` when given (missing shards are skipped).
+    pub fn open(path: &Path, sharded: bool, only: Option<&str>) -> Result<Self> {
+        let mut store = Self {
+            path: path.to_path_buf(),
+            sharded,
+            shards: BTreeMap::new(),
+        };
+        if !sharded {
+            store.shards.insert(UNSHARDED.to_string(), open_path(path)?);
+            return Ok(store);
+        }
+        let names = match only {
+            Some(shard) => vec![shard.to_string()],
+            None => shard_names(path)?,
+        };
+        for name in names {
+            let shard_path = path.join(&name);
+            if store_exists(&shard_path) {
+                store.shards.insert(name, open_path(&shard_path)?);
+            }
+        }
+        Ok(store)
+    }
+
+    /// Open the store, creating it (unsharded) if missing. Shards are
+    /// created when a block is first stored in them.
+    pub fn open_or_create(path: &Path, sharded: bool) -> Result<Self> {
+        if sharded || store_exists(path) {
+            Self::open(path, sharded, None)
+        } else {
+            Self::create(path, sharded)
+        }
+    }
+
+    /// Create an empty store at `path`.
+    pub fn create(path: &Path, sharded: bool) -> Result<Self> {
+        let mut store = Self {
+            path: path.to_path_buf(),
+            sharded,
+            shards: BTreeMap::new(),
+        };
+        if !sharded {
+            store
+                .shards
+                .insert(UNSHARDED.to_string(), create_path(path)?);
+        }
+        Ok(store)
+    }
+
+    /// Shard name recorded in the manifest for `rel_path`; None when unsharded.
+    pub fn shard_for(&self, rel_path: &str) -> Option<String> {
+        self.sharded.then(|| shard_of(rel_path).to_string())
+    }
+
+    fn shard_mut(&mut self, name: &str) -> Result<&mut VectorStore> {
+        if !self.shards.contains_key(name) {
+            let shard_path = self.path.join(name);
+            let store = if store_exists(&shard_path) {
+                open_path(&shard_path)?
+            } else {
+                std::fs::create_dir_all(&self.path)?;
+                create_path(&shard_path)?
+            };
+            self.shards.insert(name.to_string(), store);
+        }
+        Ok(self.shards.get_mut(name).expect("shard just inserted"))
+    }
+
+    pub fn enable_text_search(&mut self) -> Result<()> {
+        self.shards
+            .values_mut()
+            .try_for_each(|store| store.enable_text_search())
+    }
+
+    /// Store a block in the shard of its metadata's `file`.
+    pub fn store_with_text(
+        &mut self,
+        id: &str,
+        tokens: Vec<Vec<f32>>,
+        text: &str,
+        metadata: serde_json::Value,
+    ) -> Result<()> {
+        let name = self.shard_of_metadata(&metadata);
+        self.shard_mut(&name)?
+            .store_with_text(id, tokens, text, metadata)
+    }
+
+    /// Replace a stored block's metadata in place, keeping its vectors and
+    /// BM25 text.
+    pub fn update_metadata(&mut self, id: &str, metadata: serde_json::Value) -> Result<()> {
+        let name = self.shard_of_metadata(&metadata);
+        self.shard_mut(&name)?.update(id, None, Some(metadata))
+    }
+
+    fn shard_of_metadata(&self, metadata: &serde_json::Value) -> String {
+        if self.sharded {
+            shard_of(metadata.get("file").and_then(|v| v.as_str()).unwrap_or("")).to_string()
+        } else {
+            UNSHARDED.to_string()
+        }
+    }
+
+    /// Delete a file's blocks from the shard the manifest recorded for it.
+    pub fn delete_blocks(&mut self, entry: &FileEntry) {
+        let name = entry.shard.as_deref().unwrap_or(UNSHARDED);
+        if let Some(store) = self.shards.get_mut(name) {
+            for block_id in &entry.blocks {
+                let _ = store.delete(block_id);
+            }
+        }
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.shards.values_mut().try_for_each(|store| store.flush())
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.values().map(|store| store.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get_tokens(&self, id: &str) -> Option<(Vec<Vec<f32>>, serde_json::Value)> {
+        self.shards.values().find_map(|store| store.get_tokens(id))
+    }
+
+    pub fn get_metadata_by_id(&self, id: &str) -> Option<serde_json::Value> {
+        self.shards
+            .values()
+            .find_map(|store| store.get_metadata_by_id(id).cloned())
+    }
+
+    /// MaxSim search over every open shard, best `k` overall.
+    pub fn query_with_options<Q: QueryInput>(
+        &self,
+        query: &Q,
+        k: usize,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        self.merged(k, |store| store.query_with_options(query, k, options))
+    }
+
+    /// BM25 + MaxSim search over every open shard, best `k` overall. BM25
+    /// statistics are per shard; candidates are ranked by their MaxSim scores.
+    pub fn search_multi_with_text(
+        &self,
+        query: &str,
+        tokens: &[&[f32]],
+        k: usize,
+        num_candidates: Option<usize>,
+    ) -> Result<Vec<SearchResult>> {
+        self.merged(k, |store| {
+            store.search_multi_with_text(query, tokens, k, num_candidates)
+        })
+    }
+
+    fn merged(
+        &self,
+        k: usize,
+        search: impl Fn(&VectorStore) -> Result<Vec<SearchResult>>,
+    ) -> Result<Vec<SearchResult>> {
+        let mut results = Vec::new();
+        for store in self.shards.values() {
+            results.extend(search(store)?);
+        }
+        if self.shards.len() > 1 {
+            results.sort_by(|a, b| b.distance.total_cmp(&a.distance));
+            results.truncate(k);
+        }
+        Ok(results)
+    }
+}
+
+/// Whether omendb has a store at `path` (directory or ".omen" file).
+fn store_exists(path: &Path) -> bool {
+    super::store_files(path).any(|p| p.exists())
+}
+
+fn open_path(path: &Path) -> Result<VectorStore> {
+    log::debug!("store open path={}", path.display());
+    VectorStore::open(path).context("Failed to open vector store")
+}
+
+fn create_path(path: &Path) -> Result<VectorStore> {
+    log::debug!("store create path={}", path.display());
+    let mut store = VectorStore::multi_vector_with(
+        embedder::MODEL.token_dim,
+        omendb::MultiVectorConfig::compact(),
+    )?
+    .persist(path)
+    .context("Failed to create vector store")?;
+    store.enable_text_search()?;
+    Ok(store)
+}
+
+/// Shards present under a sharded store's directory.
+fn shard_names(path: &Path) -> Result<Vec<String>> {
+    if !path.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut names: Vec<String> = std::fs::read_dir(path)?
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().to_string_lossy().into_owned();
+            Some(
+                name.strip_suffix(".omen")
+                    .map_or(name.clone(), str::to_string),
+            )
+        })
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shards_by_top_level_directory() {
+        assert_eq!(shard_of("src/index/mod.rs"), "src");
+        assert_eq!(shard_of("@commits/abc123"), "@commits");
+        assert_eq!(shard_of("README.md"), ROOT_SHARD);
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let vectors = tmp.path().join("vectors");
+        assert!(shard_names(&vectors).unwrap().is_empty());
+        std::fs::create_dir_all(vectors.join("src")).unwrap();
+        std::fs::write(vectors.join("src.omen"), "").unwrap();
+        std::fs::write(vectors.join("lib.omen"), "").unwrap();
+        assert_eq!(shard_names(&vectors).unwrap(), ["lib", "src"]);
+    }
+}
@@ -1,10 +1,15 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use ignore::WalkBuilder;
 
+use super::INDEX_DIR;
+use crate::extractor::text::TEXT_EXTENSIONS;
+use crate::types::IndexOptions;
+
 /// Maximum file size to index (1MB).
 const MAX_FILE_SIZE: u64 = 1_000_000;
 
@@ -72,43 +77,213 @@ const BINARY_EXTENSIONS: &[&str] = &[
     ".lock",
 ];
 
+/// Average line length (bytes) above which a code file is treated as minified.
+const MINIFIED_AVG_LINE: usize = 300;
+
+/// Files smaller than this are never treated as minified.
+const MINIFIED_MIN_SIZE: usize = 2048;
+
+/// Leading lines searched for generated-file markers.
+const GENERATED_HEADER_LINES: usize = 5;
+
+/// Markers code generators put at the top of their output.
+const GENERATED_MARKERS: &[&str] = &["@generated", "DO NOT EDIT"];
+
+/// Lists the directories (relative to its own) that make up a workspace index.
+pub const WORKSPACE_FILE: &str = "og.workspace";
+
+/// Version-control and index directories, never descended into regardless of
+/// ignore files or hidden-file settings (a fresh repo has no .gitignore).
+const PRUNED_DIRS: &[&str] = &[".git", ".hg", ".svn", INDEX_DIR];
+
 /// Metadata for a scanned file: (file_size, mtime_secs).
 pub type FileMetadata = (u64, u64);
 
-/// Check if a file path should be skipped during scanning.
-fn should_skip(path: &Path) -> bool {
+/// Scanned text files: path -> (content, mtime_secs).
+pub type ScannedFiles = HashMap<PathBuf, (String, u64)>;
+
+/// What a scan found: the text files, how many were transcoded to UTF-8, and
+/// how many files the walk yielded but left out, by reason.
+pub struct ScanReport {
+    pub files: ScannedFiles,
+    pub transcoded: usize,
+    pub skipped: BTreeMap<&'static str, usize>,
+}
+
+/// Why a file path should be skipped during scanning, if it should.
+fn name_skip_reason(path: &Path) -> Option<&'static str> {
     if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-        if name.starts_with('.') || name.ends_with("-lock.json") {
-            return true;
+        if name.starts_with('.') {
+            return Some("hidden");
+        }
+        if name.to_lowercase().ends_with("-lock.json") {
+            return Some("lockfile");
         }
     }
     if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
         let ext_lower = format!(".{}", ext.to_lowercase());
         if BINARY_EXTENSIONS.contains(&ext_lower.as_str()) {
-            return true;
+            return Some("binary extension");
         }
     }
-    false
+    None
 }
 
-/// Build a directory walker with standard filtering options.
-fn build_walker(root: &Path) -> ignore::Walk {
-    WalkBuilder::new(root)
+/// Member directories declared in `root/og.workspace`: one path per line,
+/// relative to `root`, with `#` comments. None when `root` has no workspace file.
+/// Members must exist and lie under `root`, since stored paths are relative to it.
+pub fn workspace_members(root: &Path) -> Result<Option<Vec<PathBuf>>> {
+    let file = root.join(WORKSPACE_FILE);
+    if !file.is_file() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&file)
+        .with_context(|| format!("Failed to read {}", file.display()))?;
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+
+    let mut members = Vec::new();
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Ok(member) = root.join(line).canonicalize() else {
+            bail!(
+                "{}: workspace member '{line}' does not exist",
+                file.display()
+            );
+        };
+        if !member.starts_with(&root) || !member.is_dir() {
+            bail!(
+                "{}: workspace member '{line}' must be a directory under {}",
+                file.display(),
+                root.display()
+            );
+        }
+        members.push(member);
+    }
+    if members.is_empty() {
+        bail!("{} lists no directories", file.display());
+    }
+    Ok(Some(members))
+}
+
+/// Walker over `root`, or only its declared members for a workspace root.
+fn walk_builder(root: &Path) -> Result<WalkBuilder> {
+    let members = workspace_members(root)?.unwrap_or_else(|| vec![root.to_path_buf()]);
+    let mut builder = WalkBuilder::new(&members[0]);
+    for member in &members[1..] {
+        builder.add(member);
+    }
+    Ok(builder)
+}
+
+/// Whether a walk entry is one of `PRUNED_DIRS` below the walk root.
+fn is_pruned_dir(entry: &ignore::DirEntry) -> bool {
+    entry.depth() > 0
+        && entry.file_type().is_some_and(|t| t.is_dir())
+        && entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| PRUNED_DIRS.contains(&name))
+}
+
+/// Build a directory walker with standard filtering options. A workspace root
+/// walks only its declared members. Oversized files are skipped by the scans.
+fn build_walker(root: &Path) -> Result<ignore::Walk> {
+    Ok(walk_builder(root)?
         .hidden(true)
         .git_ignore(true)
         .git_global(true)
         .git_exclude(true)
         .follow_links(false)
-        .max_filesize(Some(MAX_FILE_SIZE))
-        .build()
+        .filter_entry(|entry| !is_pruned_dir(entry))
+        .build())
+}
+
+/// Log each file or directory that ignore rules (.gitignore, .ignore, git
+/// excludes) kept out of a walk, given every path the walk yielded. Ignored
+/// directories are logged once, not descended into.
+fn log_ignored(root: &Path, seen: HashSet<PathBuf>) -> Result<()> {
+    let mut builder = walk_builder(root)?;
+    builder
+        .standard_filters(false)
+        .hidden(true)
+        .follow_links(false)
+        .filter_entry(move |entry| {
+            if is_pruned_dir(entry) {
+                return false;
+            }
+            if entry.depth() == 0 || seen.contains(entry.path()) {
+                return true;
+            }
+            log::debug!("skip path={} reason=ignored", entry.path().display());
+            false
+        });
+    for _ in builder.build() {}
+    Ok(())
+}
+
+/// Files marked `linguist-generated` in the nearest `.gitattributes` at or
+/// above `root` (stopping at the repository root), as GitHub treats them.
+/// Later lines override earlier ones, so `-linguist-generated` re-includes.
+fn generated_matcher(root: &Path) -> Option<Gitignore> {
+    let dir = root
+        .ancestors()
+        .find(|d| d.join(".gitattributes").is_file() || d.join(".git").exists())?;
+    let content = std::fs::read_to_string(dir.join(".gitattributes")).ok()?;
+
+    let mut builder = GitignoreBuilder::new(dir);
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let Some(pattern) = fields.next().filter(|p| !p.starts_with('#')) else {
+            continue;
+        };
+        for attr in fields {
+            let glob = match attr {
+                "linguist-generated" | "linguist-generated=true" => pattern.to_string(),
+                "-linguist-generated" | "!linguist-generated" | "linguist-generated=false" => {
+                    format!("!{pattern}")
+                }
+                _ => continue,
+            };
+            let _ = builder.add_line(None, &glob);
+        }
+    }
+    builder.build().ok().filter(|m| !m.is_empty())
+}
+
+/// Why a scanned file of `size` bytes should be left out of the index, if it should.
+fn skip_reason(path: &Path, size: u64, generated: Option<&Gitignore>) -> Option<&'static str> {
+    if let Some(reason) = name_skip_reason(path) {
+        return Some(reason);
+    }
+    if size > MAX_FILE_SIZE {
+        return Some("too large");
+    }
+    generated
+        .is_some_and(|m| m.matched(path, false).is_ignore())
+        .then_some("linguist-generated")
+}
+
+fn generated_filter(root: &Path, options: &IndexOptions) -> Option<Gitignore> {
+    if options.index_generated {
+        None
+    } else {
+        generated_matcher(root)
+    }
 }
 
 /// Scan directory tree for file metadata only (no content reads).
 /// Returns path -> (file_size, mtime_secs) for each eligible file.
-pub fn scan_metadata(root: &Path) -> Result<HashMap<PathBuf, FileMetadata>> {
+pub fn scan_metadata(
+    root: &Path,
+    options: &IndexOptions,
+) -> Result<HashMap<PathBuf, FileMetadata>> {
     let mut results = HashMap::new();
+    let generated = generated_filter(root, options);
 
-    for entry in build_walker(root) {
+    for entry in build_walker(root)? {
         let entry = match entry {
             Ok(e) => e,
             Err(_) => continue,
@@ -119,12 +294,11 @@ pub fn scan_metadata(root: &Path) -> Result<HashMap<PathBuf, FileMetadata>> {
         }
 
         let path = entry.path();
-        if should_skip(path) {
-            continue;
-        }
-
         if let Ok(meta) = std::fs::metadata(path) {
             let size = meta.len();
+            if skip_reason(path, size, generated.as_ref()).is_some() {
+                continue;
+            }
             let mtime = meta
                 .modified()
                 .unwrap_or(SystemTime::UNIX_EPOCH)
@@ -150,21 +324,45 @@ pub fn file_mtime(path: &Path) -> u64 {
 
 /// Scan directory tree for text files, returning path -> (content, mtime).
 /// mtime is captured before reading content so it's never newer than what was read.
-pub fn scan(root: &Path) -> Result<HashMap<PathBuf, (String, u64)>> {
+pub fn scan(root: &Path, options: &IndexOptions) -> Result<ScannedFiles> {
+    Ok(scan_counted(root, options)?.0)
+}
+
+/// `scan`, also returning how many files were transcoded to UTF-8.
+pub fn scan_counted(root: &Path, options: &IndexOptions) -> Result<(ScannedFiles, usize)> {
+    let report = scan_report(root, options)?;
+    Ok((report.files, report.transcoded))
+}
+
+/// `scan`, also counting transcoded files and skipped files by reason.
+/// Files hidden by ignore rules are not counted; `-vv` logs them.
+pub fn scan_report(root: &Path, options: &IndexOptions) -> Result<ScanReport> {
     let mut results = HashMap::new();
+    let mut transcoded = 0;
+    let mut skipped: BTreeMap<&'static str, usize> = BTreeMap::new();
+    let generated = generated_filter(root, options);
+    // Paths the walk yielded, to find what ignore rules hid (debug only)
+    let log_skips = log::log_enabled!(log::Level::Debug);
+    let mut seen = HashSet::new();
 
-    for entry in build_walker(root) {
+    for entry in build_walker(root)? {
         let entry = match entry {
             Ok(e) => e,
             Err(_) => continue,
         };
+        if log_skips {
+            seen.insert(entry.path().to_path_buf());
+        }
 
         if entry.file_type().is_none_or(|ft| !ft.is_file()) {
             continue;
         }
 
         let path = entry.path();
-        if should_skip(path) {
+        let size = entry.metadata().map_or(0, |m| m.len());
+        if let Some(reason) = skip_reason(path, size, generated.as_ref()) {
+            log::debug!("skip path={} reason={reason}", path.display());
+            *skipped.entry(reason).or_default() += 1;
             continue;
         }
 
@@ -173,22 +371,255 @@ pub fn scan(root: &Path) -> Result<HashMap<PathBuf, (String, u64)>> {
 
         let raw = match std::fs::read(path) {
             Ok(data) => data,
-            Err(_) => continue,
+            Err(e) => {
+                log::debug!("skip path={} reason=unreadable error={e}", path.display());
+                *skipped.entry("unreadable").or_default() += 1;
+                continue;
+            }
         };
 
-        // Binary detection: null byte in first 8192 bytes
-        let check_len = raw.len().min(8192);
-        if raw[..check_len].contains(&0) {
+        let Some((content, was_transcoded)) = decode(raw, options.transcode) else {
+            let reason = if options.transcode {
+                "binary"
+            } else {
+                "binary or non-UTF-8"
+            };
+            log::debug!("skip path={} reason={reason}", path.display());
+            *skipped.entry(reason).or_default() += 1;
             continue;
-        }
-
-        let content = match String::from_utf8(raw) {
-            Ok(s) => s,
-            Err(_) => continue,
         };
+        transcoded += usize::from(was_transcoded);
 
         results.insert(path.to_path_buf(), (content, mtime));
     }
 
-    Ok(results)
+    if log_skips {
+        log_ignored(root, seen)?;
+    }
+    if options.with_commits {
+        let commits = super::git::commit_files(root);
+        log::info!("scan commits={}", commits.len());
+        results.extend(commits);
+    }
+    log::info!(
+        "scan root={} files={} skipped={} transcoded={transcoded}",
+        root.display(),
+        results.len(),
+        skipped.values().sum::<usize>()
+    );
+
+    Ok(ScanReport {
+        files: results,
+        transcoded,
+        skipped,
+    })
+}
+
+/// Decode file bytes as text, dropping a UTF-8 BOM. With `transcode`, UTF-16
+/// files with a BOM and other non-UTF-8 text (read as Windows-1252, a Latin-1
+/// superset) are converted to UTF-8. Returns the text and whether it was
+/// transcoded, or None for binary and (without `transcode`) non-UTF-8 files.
+pub fn decode(raw: Vec<u8>, transcode: bool) -> Option<(String, bool)> {
+    if transcode {
+        if let Some((encoding, bom_len)) = encoding_rs::Encoding::for_bom(&raw) {
+            if encoding != encoding_rs::UTF_8 {
+                let (text, had_errors) = encoding.decode_without_bom_handling(&raw[bom_len..]);
+                return (!had_errors).then(|| (text.into_owned(), true));
+            }
+        }
+    }
+
+    // Binary detection: null byte in first 8192 bytes
+    let check_len = raw.len().min(8192);
+    if raw[..check_len].contains(&0) {
+        return None;
+    }
+
+    match String::from_utf8(raw) {
+        Ok(text) => match text.strip_prefix('\u{feff}') {
+            Some(rest) => Some((rest.to_string(), false)),
+            None => Some((text, false)),
+        },
+        Err(e) if transcode => {
+            let (text, _) = encoding_rs::WINDOWS_1252.decode_without_bom_handling(e.as_bytes());
+            Some((text.into_owned(), true))
+        }
+        Err(_) => None,
+    }
+}
+
+/// Whether a file looks minified (few, very long lines) or carries a
+/// generated-file marker in its first lines. Prose files are only checked
+/// for markers, since one-line paragraphs are normal there.
+pub fn is_minified_or_generated(rel_path: &str, content: &str) -> bool {
+    let generated = content
+        .lines()
+        .take(GENERATED_HEADER_LINES)
+        .any(|line| GENERATED_MARKERS.iter().any(|m| line.contains(m)));
+    if generated {
+        return true;
+    }
+
+    if content.len() < MINIFIED_MIN_SIZE || is_text_file(rel_path) {
+        return false;
+    }
+    let lines = content.lines().count().max(1);
+    content.len() / lines > MINIFIED_AVG_LINE
+}
+
+fn is_text_file(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| TEXT_EXTENSIONS.contains(&format!(".{}", e.to_lowercase()).as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skip_ignores_extension_case() {
+        assert_eq!(
+            name_skip_reason(Path::new("assets/LOGO.PNG")),
+            Some("binary extension")
+        );
+        assert_eq!(
+            name_skip_reason(Path::new("web/package-LOCK.JSON")),
+            Some("lockfile")
+        );
+        assert_eq!(name_skip_reason(Path::new("src/Main.Java")), None);
+    }
+
+    #[test]
+    fn detects_minified_and_generated() {
+        let minified = format!("var a={};", "x".repeat(5000));
+        assert!(is_minified_or_generated("dist/app.min.js", &minified));
+        assert!(!is_minified_or_generated("notes.md", &minified));
+
+        let normal = "function f() {\n  return 1;\n}\n".repeat(200);
+        assert!(!is_minified_or_generated("src/app.js", &normal));
+
+        let go = format!("// Code generated by protoc. DO NOT EDIT.\n{normal}");
+        assert!(is_minified_or_generated("api.pb.go", &go));
+        let rs = format!("// This file is @generated by build.rs\n{normal}");
+        assert!(is_minified_or_generated("gen.rs", &rs));
+        let late = format!("{normal}// DO NOT EDIT below\n");
+        assert!(!is_minified_or_generated("src/app.js", &late));
+    }
+
+    #[test]
+    fn decode_strips_bom_and_transcodes() {
+        let bom = b"\xEF\xBB\xBFfn main() {}".to_vec();
+        assert_eq!(
+            decode(bom, false),
+            Some(("fn main() {}".to_string(), false))
+        );
+
+        let latin1 = b"# caf\xE9\nx = 1\n".to_vec();
+        assert_eq!(decode(latin1.clone(), false), None);
+        assert_eq!(
+            decode(latin1, true),
+            Some(("# caf\u{e9}\nx = 1\n".to_string(), true))
+        );
+
+        let mut utf16 = vec![0xFF, 0xFE];
+        utf16.extend("int x;".encode_utf16().flat_map(u16::to_le_bytes));
+        assert_eq!(decode(utf16.clone(), false), None);
+        assert_eq!(decode(utf16, true), Some(("int x;".to_string(), true)));
+    }
+
+    #[test]
+    fn workspace_scans_only_members() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path().canonicalize().unwrap();
+        for dir in ["api", "shared", "vendor"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+            std::fs::write(root.join(dir).join("lib.rs"), "fn f() {}\n").unwrap();
+        }
+        std::fs::write(
+            root.join(WORKSPACE_FILE),
+            "# services\napi\nshared/  # common code\n",
+        )
+        .unwrap();
+
+        let mut files: Vec<PathBuf> = scan(&root, &IndexOptions::default())
+            .unwrap()
+            .into_keys()
+            .collect();
+        files.sort();
+        assert_eq!(files, [root.join("api/lib.rs"), root.join("shared/lib.rs")]);
+
+        std::fs::write(root.join(WORKSPACE_FILE), "api\n../elsewhere\n").unwrap();
+        assert!(workspace_members(&root).is_err());
+    }
+
+    #[test]
+    fn vcs_and_index_dirs_never_scanned() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path().canonicalize().unwrap();
+        std::fs::write(root.join("main.rs"), "fn main() {}\n").unwrap();
+        for dir in [".git/logs", ".hg", ".svn", INDEX_DIR] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::write(
+            root.join(".git/logs/HEAD"),
+            "0000 1111 Alice <a@example.com> 1700000000 +0000\tcommit: init\n",
+        )
+        .unwrap();
+        std::fs::write(root.join(".hg/notes.txt"), "notes\n").unwrap();
+        std::fs::write(root.join(INDEX_DIR).join("manifest.json"), "{}\n").unwrap();
+
+        let options = IndexOptions::default();
+        let scanned: Vec<PathBuf> = scan(&root, &options).unwrap().into_keys().collect();
+        assert_eq!(scanned, [root.join("main.rs")]);
+        let stats: Vec<PathBuf> = scan_metadata(&root, &options)
+            .unwrap()
+            .into_keys()
+            .collect();
+        assert_eq!(stats, [root.join("main.rs")]);
+    }
+
+    #[test]
+    fn oversized_files_skipped_by_both_scans() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path().canonicalize().unwrap();
+        std::fs::write(root.join("small.rs"), "fn f() {}\n").unwrap();
+        let big = "// filler\n".repeat(MAX_FILE_SIZE as usize / 10 + 1);
+        std::fs::write(root.join("big.rs"), big).unwrap();
+
+        let options = IndexOptions::default();
+        let scanned: Vec<PathBuf> = scan(&root, &options).unwrap().into_keys().collect();
+        assert_eq!(scanned, [root.join("small.rs")]);
+        let stats: Vec<PathBuf> = scan_metadata(&root, &options)
+            .unwrap()
+            .into_keys()
+            .collect();
+        assert_eq!(stats, [root.join("small.rs")]);
+    }
+
+    #[test]
+    fn gitattributes_linguist_generated() {
+        let dir = std::env::temp_dir().join(format!("og-gitattributes-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join(".git")).unwrap();
+        std::fs::write(
+            dir.join(".gitattributes"),
+            "# generated code\n*.pb.go linguist-generated=true\ndist/** linguist-generated\ndist/keep.js -linguist-generated\n*.rs text eol=lf\n",
+        )
+        .unwrap();
+
+        let m = generated_matcher(&dir).unwrap();
+        let generated = |p: &str| m.matched(dir.join(p), false).is_ignore();
+        assert!(generated("api/service.pb.go"));
+        assert!(generated("dist/bundle.js"));
+        assert!(!generated("dist/keep.js"));
+        assert!(!generated("src/main.rs"));
+
+        let options = IndexOptions {
+            index_generated: true,
+            ..IndexOptions::default()
+        };
+        assert!(generated_filter(&dir, &options).is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -1,8 +1,12 @@
+pub mod anchor;
 pub mod boost;
 pub mod cli;
+pub mod codeowners;
 pub mod embedder;
 pub mod extractor;
 pub mod index;
+pub mod logging;
 pub mod synonyms;
+pub mod testfiles;
 pub mod tokenize;
 pub mod types;
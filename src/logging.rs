@@ -0,0 +1,69 @@
+use std::io::Write;
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// Env var overriding the log level: error, warn, info, debug, trace or off.
+pub const LOG_ENV: &str = "OG_LOG";
+
+/// Writes og's own log events to stderr as `[LEVEL module] message`.
+/// Events from dependencies are dropped.
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level() && metadata.target().starts_with("omengrep")
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let target = record.target().trim_start_matches("omengrep::");
+        let _ = writeln!(
+            std::io::stderr().lock(),
+            "[{} {target}] {}",
+            record.level(),
+            record.args()
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+/// Level for `-v` given `verbosity` times, unless `env` (`OG_LOG`) names one.
+/// Without either, logging is off so default output is unchanged.
+fn level(verbosity: u8, env: Option<&str>) -> LevelFilter {
+    if let Some(level) = env.and_then(|e| e.trim().parse().ok()) {
+        return level;
+    }
+    match verbosity {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Install the stderr logger. `-v` logs scan and index summaries, `-vv`
+/// adds skipped files with reasons, embedding batches and store operations.
+pub fn init(verbosity: u8) {
+    let env = std::env::var(LOG_ENV).ok();
+    if log::set_logger(&StderrLogger).is_ok() {
+        log::set_max_level(level(verbosity, env.as_deref()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_overrides_verbosity() {
+        assert_eq!(level(0, None), LevelFilter::Off);
+        assert_eq!(level(1, None), LevelFilter::Info);
+        assert_eq!(level(5, None), LevelFilter::Trace);
+        assert_eq!(level(2, Some("warn")), LevelFilter::Warn);
+        assert_eq!(level(0, Some("DEBUG")), LevelFilter::Debug);
+        assert_eq!(level(1, Some("loud")), LevelFilter::Info);
+    }
+}
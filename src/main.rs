@@ -1,7 +1,14 @@
+use std::io;
 use std::process;
 
 fn main() {
     if let Err(e) = omengrep::cli::run() {
+        // Output piped into a reader that exited early (`og ... | head`)
+        if e.downcast_ref::<io::Error>()
+            .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe)
+        {
+            process::exit(omengrep::types::EXIT_MATCH);
+        }
         eprintln!("Error: {e:#}");
         process::exit(omengrep::types::EXIT_ERROR);
     }
@@ -1,3 +1,5 @@
+use anyhow::{bail, Result};
+
 /// Code vocabulary synonym table for BM25 query expansion.
 ///
 /// Applied at query time only — expands natural language query terms to
@@ -298,6 +300,69 @@ pub fn expand_query(text: &str) -> String {
     format!("{text} {}", additions.join(" "))
 }
 
+/// A project acronym and the words it stands for (`.og/acronyms.txt`).
+pub type Acronym = (String, Vec<String>);
+
+/// Parse an acronym map: one `ACRONYM = spelled out words` per line, with
+/// blank lines and `#` comments skipped. Entries are lowercased.
+pub fn parse_acronyms(text: &str) -> Result<Vec<Acronym>> {
+    let mut acronyms = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((acronym, words)) = line.split_once('=') else {
+            bail!("line {}: expected 'ACRONYM = words', got '{line}'", n + 1);
+        };
+        let acronym = acronym.trim().to_lowercase();
+        let words = words_of(words);
+        if acronym.is_empty() || words.is_empty() {
+            bail!("line {}: expected 'ACRONYM = words', got '{line}'", n + 1);
+        }
+        acronyms.push((acronym, words));
+    }
+    Ok(acronyms)
+}
+
+/// Expand BM25 query text with acronyms in both directions: an acronym in
+/// the text appends the words it stands for, and those words in sequence
+/// append the acronym. Words already in the text are not repeated.
+pub fn expand_acronyms(text: &str, acronyms: &[Acronym]) -> String {
+    let words = words_of(text);
+    let mut additions: Vec<&str> = Vec::new();
+    for (acronym, expansion) in acronyms {
+        if words.contains(acronym) {
+            additions.extend(
+                expansion
+                    .iter()
+                    .filter(|w| !words.contains(w))
+                    .map(String::as_str),
+            );
+        } else if words
+            .windows(expansion.len())
+            .any(|window| window == expansion.as_slice())
+        {
+            additions.push(acronym);
+        }
+    }
+
+    if additions.is_empty() {
+        return text.to_string();
+    }
+    let mut seen = std::collections::HashSet::new();
+    additions.retain(|s| seen.insert(*s));
+    format!("{text} {}", additions.join(" "))
+}
+
+/// Lowercased alphanumeric words, in order.
+fn words_of(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -332,6 +397,25 @@ mod tests {
         assert_eq!(result, "foobar baz");
     }
 
+    #[test]
+    fn expands_acronyms_both_ways() {
+        let acronyms =
+            parse_acronyms("# team jargon\nACL = access control list\n\nPR = pull request\n")
+                .unwrap();
+        assert_eq!(acronyms.len(), 2);
+        assert_eq!(
+            expand_acronyms("check acl", &acronyms),
+            "check acl access control list"
+        );
+        assert_eq!(
+            expand_acronyms("access control list check", &acronyms),
+            "access control list check acl"
+        );
+        assert_eq!(expand_acronyms("pull access", &acronyms), "pull access");
+        assert!(parse_acronyms("ACL access control list").is_err());
+        assert!(parse_acronyms("ACL =").is_err());
+    }
+
     #[test]
     fn skips_terms_already_in_text() {
         // "login" is already in text — should not be added again via auth expansion
@@ -0,0 +1,78 @@
+use std::path::Path;
+
+/// Directories whose contents are tests.
+const TEST_DIRS: &[&str] = &["tests", "test", "__tests__", "spec", "specs"];
+
+/// File stem suffixes: `foo_test.go`, `foo.test.ts`, `foo.spec.js`, `foo_spec.rb`.
+const TEST_SUFFIXES: &[&str] = &["_test", "_tests", ".test", ".spec", "_spec"];
+
+/// File stem prefixes: `test_foo.py`.
+const TEST_PREFIXES: &[&str] = &["test_"];
+
+/// Exact file names: pytest fixtures.
+const TEST_FILES: &[&str] = &["conftest.py"];
+
+/// Extensions where a `FooTest`/`FooTests` class name marks a test file.
+const CLASS_TEST_EXTS: &[&str] = &["java", "kt", "scala", "cs", "swift", "php"];
+
+/// Whether `path` (relative to the search root) is a test file by the naming
+/// conventions above or a test directory among its parents. Shared by
+/// `--no-tests` and `--tests-only`.
+pub fn is_test_path(path: &Path) -> bool {
+    let in_test_dir = path
+        .parent()
+        .into_iter()
+        .flat_map(Path::components)
+        .any(|c| TEST_DIRS.contains(&c.as_os_str().to_string_lossy().as_ref()));
+    if in_test_dir {
+        return true;
+    }
+
+    let Some(name) = path.file_name().map(|n| n.to_string_lossy()) else {
+        return false;
+    };
+    if TEST_FILES.contains(&name.as_ref()) {
+        return true;
+    }
+    let (stem, ext) = name.rsplit_once('.').unwrap_or((&name, ""));
+    TEST_SUFFIXES.iter().any(|s| stem.ends_with(s))
+        || TEST_PREFIXES.iter().any(|p| stem.starts_with(p))
+        || (CLASS_TEST_EXTS.contains(&ext)
+            && ["Test", "Tests"].iter().any(|s| {
+                stem.strip_suffix(s)
+                    .is_some_and(|rest| !rest.is_empty() && !rest.ends_with('_'))
+            }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_per_language_conventions() {
+        for path in [
+            "pkg/server_test.go",
+            "app/test_models.py",
+            "conftest.py",
+            "src/button.test.tsx",
+            "src/api.spec.js",
+            "lib/user_spec.rb",
+            "src/main/java/FooTest.java",
+            "Tests/ParserTests.swift",
+            "tests/integration.rs",
+            "src/__tests__/util.js",
+        ] {
+            assert!(is_test_path(Path::new(path)), "{path}");
+        }
+        for path in [
+            "src/contest.rs",
+            "src/testing.py",
+            "src/Attest.java",
+            "src/latest.ts",
+            "tests",
+            "src/Test.java",
+        ] {
+            assert!(!is_test_path(Path::new(path)), "{path}");
+        }
+    }
+}
@@ -12,11 +12,18 @@ static CAMEL_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"([a-z0-9])([A-Z
 /// Regex matching ALLCAPS -> lowercase transitions (e.g., HTTPSClient -> HTTPS|Client).
 static UPPER_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"([A-Z]+)([A-Z][a-z])").unwrap());
 
+/// Shortest identifier considered for splitting (`og build --split-min-word`).
+pub const DEFAULT_MIN_WORD: usize = 3;
+
+/// Shortest split part kept (`og build --split-min-part`).
+pub const DEFAULT_MIN_PART: usize = 2;
+
 /// Split a single identifier into its component parts, lowercased.
 ///
 /// Handles camelCase, PascalCase, ALLCAPS, and snake_case.
+/// Parts shorter than `min_part` are dropped.
 /// Returns empty vec if the word doesn't need splitting.
-fn split_word(word: &str) -> Vec<String> {
+fn split_word(word: &str, min_part: usize) -> Vec<String> {
     let has_camel = CAMEL_RE.is_match(word);
     let has_upper = UPPER_RE.is_match(word);
     let has_underscore = word.contains('_');
@@ -32,7 +39,7 @@ fn split_word(word: &str) -> Vec<String> {
 
     let parts: Vec<String> = expanded
         .split(['_', ' '])
-        .filter(|s| s.len() >= 2)
+        .filter(|s| s.len() >= min_part)
         .map(|s| s.to_lowercase())
         .collect();
 
@@ -137,6 +144,35 @@ const KEYWORD_STOP_LIST: &[&str] = &[
     "public",
 ];
 
+/// Common English words that carry no weight in natural-language queries.
+const ENGLISH_STOP_LIST: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "does", "do", "how", "i", "in", "is", "it",
+    "of", "on", "or", "that", "the", "their", "there", "these", "this", "those", "to", "was",
+    "what", "when", "which", "who", "why", "will",
+];
+
+/// Drop language keywords and English stopwords from a BM25 query
+/// (`--strip-stopwords`): "the function that handles errors" -> "function
+/// handles errors". Words are compared case-insensitively, ignoring
+/// surrounding punctuation. A query made only of stopwords is kept as is.
+pub fn strip_stopwords(query: &str) -> String {
+    let kept: Vec<&str> = query
+        .split_whitespace()
+        .filter(|word| {
+            let word = word
+                .trim_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase();
+            !KEYWORD_STOP_LIST.contains(&word.as_str())
+                && !ENGLISH_STOP_LIST.contains(&word.as_str())
+        })
+        .collect();
+    if kept.is_empty() {
+        query.to_string()
+    } else {
+        kept.join(" ")
+    }
+}
+
 /// Split code identifiers for BM25 text search.
 ///
 /// Finds camelCase and snake_case identifiers in the text and appends
@@ -146,18 +182,24 @@ const KEYWORD_STOP_LIST: &[&str] = &[
 /// The original text is preserved — split terms are appended at the end.
 /// Language keywords are filtered from split terms to reduce noise.
 pub fn split_identifiers(text: &str) -> String {
+    split_identifiers_with(text, DEFAULT_MIN_WORD, DEFAULT_MIN_PART)
+}
+
+/// `split_identifiers` with explicit limits: identifiers shorter than
+/// `min_word` are left unsplit and split parts shorter than `min_part` dropped.
+pub fn split_identifiers_with(text: &str, min_word: usize, min_part: usize) -> String {
     let mut extra: Vec<String> = Vec::new();
 
     for mat in IDENT_RE.find_iter(text) {
         let word = mat.as_str();
-        if word.len() < 4 {
+        if word.len() < min_word {
             continue;
         }
         let word_lower = word.to_ascii_lowercase();
         if KEYWORD_STOP_LIST.contains(&word_lower.as_str()) {
             continue;
         }
-        let parts = split_word(word);
+        let parts = split_word(word, min_part);
         for part in parts {
             if !KEYWORD_STOP_LIST.contains(&part.as_str()) {
                 extra.push(part);
@@ -180,7 +222,7 @@ pub fn extract_terms(text: &str) -> Vec<String> {
 
     for mat in IDENT_RE.find_iter(text) {
         let word = mat.as_str();
-        let parts = split_word(word);
+        let parts = split_word(word, DEFAULT_MIN_PART);
         if parts.is_empty() {
             // No splitting needed — add as-is (lowercased)
             terms.push(word.to_lowercase());
@@ -205,6 +247,19 @@ pub fn extract_terms(text: &str) -> Vec<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn strip_stopwords_keeps_content_words() {
+        assert_eq!(
+            strip_stopwords("the function that handles errors"),
+            "function handles errors"
+        );
+        assert_eq!(
+            strip_stopwords("How is the Cache invalidated?"),
+            "Cache invalidated?"
+        );
+        assert_eq!(strip_stopwords("for while"), "for while");
+    }
+
     #[test]
     fn camel_case() {
         let result = split_identifiers("getUserProfile");
@@ -240,6 +295,20 @@ mod tests {
     fn short_words_skipped() {
         let result = split_identifiers("fn do");
         assert_eq!(result, "fn do");
+        // Even with no word limit, a 2-char word has no 2-char parts to split into
+        assert_eq!(
+            split_identifiers_with("fn do", 0, DEFAULT_MIN_PART),
+            "fn do"
+        );
+    }
+
+    #[test]
+    fn configurable_lengths() {
+        // Single-letter parts are dropped by default
+        assert_eq!(split_identifiers("getX"), "getX");
+        assert_eq!(split_identifiers_with("getX", 3, 1), "getX get x");
+        assert_eq!(split_identifiers_with("apiV2", 3, 2), "apiV2 api v2");
+        assert_eq!(split_identifiers_with("apiV2", 6, 2), "apiV2");
     }
 
     #[test]
@@ -1,9 +1,10 @@
 use serde::{Deserialize, Serialize};
 
 /// A code block extracted from a source file.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Block {
-    /// Block ID: "rel/path:start_line:name"
+    /// Block ID: "rel/path:name:content_hash", with "~n" on the nth repeat of
+    /// the same name and content in a file (see `Block::make_id`).
     pub id: String,
     /// Relative file path from index root.
     pub file: String,
@@ -17,22 +18,75 @@ pub struct Block {
     pub end_line: usize,
     /// Source content of the block.
     pub content: String,
+    /// Single-file component region (script, template, style) the block came from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Surrounding context (imports, enclosing type) prepended to the embedding text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    /// Blame summary (`og build --with-git`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitInfo>,
+    /// Part index when an over-long block was split into overlapping sub-blocks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub part: Option<usize>,
+    /// Raw tree-sitter node kind (e.g. `trait_item`) before type normalization.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture: Option<String>,
+    /// Source language (`rust`, `python`, `markdown`, ...), from the extension or shebang.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+/// Dominant author and most recent commit time of a block's lines.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitInfo {
+    pub author: String,
+    /// Unix timestamp (seconds) of the newest commit touching the block.
+    pub commit_time: u64,
 }
 
 impl Block {
-    pub fn make_id(file: &str, start_line: usize, name: &str) -> String {
-        format!("{file}:{start_line}:{name}")
+    /// Stable ID from file, name and a content hash instead of the start line,
+    /// so a block keeps its ID when lines above it are inserted or removed.
+    pub fn make_id(file: &str, name: &str, content: &str) -> String {
+        let hash = blake3::hash(content.as_bytes());
+        format!("{file}:{name}:{}", &hash.to_hex()[..16])
+    }
+
+    /// Set each block's ID from its current name and content, numbering repeats
+    /// of the same ID `~1`, `~2`, ... in order. Run once a file's blocks are final.
+    pub fn assign_ids(blocks: &mut [Block]) {
+        let mut seen: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+        for block in blocks.iter_mut() {
+            let id = Block::make_id(&block.file, &block.name, &block.content);
+            let repeats = seen.entry(id.clone()).or_default();
+            block.id = match *repeats {
+                0 => id,
+                n => format!("{id}~{n}"),
+            };
+            *repeats += 1;
+        }
     }
 
-    /// Text representation for embedding: "type name\ncontent"
+    /// Text representation for embedding: "[context\n]type name\ncontent"
     pub fn embedding_text(&self) -> String {
-        format!("{} {}\n{}", self.block_type, self.name, self.content)
+        match &self.context {
+            Some(context) => format!(
+                "{context}\n{} {}\n{}",
+                self.block_type, self.name, self.content
+            ),
+            None => format!("{} {}\n{}", self.block_type, self.name, self.content),
+        }
     }
 }
 
 /// A search result returned to the user.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SearchResult {
+    /// Block ID in the store (not part of JSON output).
+    #[serde(skip)]
+    pub id: String,
     /// File path (absolute for display, relative for JSON).
     pub file: String,
     /// Block type.
@@ -49,6 +103,22 @@ pub struct SearchResult {
     pub content: Option<String>,
     /// Similarity/relevance score.
     pub score: f32,
+    /// Blame summary, when the index was built with `--with-git`.
+    #[serde(flatten)]
+    pub git: Option<GitInfo>,
+    /// Raw tree-sitter node kind, for code blocks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture: Option<String>,
+    /// Source language of the block's file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Checksum of the block's content at index time, for change tracking
+    /// across index versions. None for indexes built before it was stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// Set by `--verify` when the block's first line was not found near `line`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stale: bool,
 }
 
 /// Parsed file reference from CLI input.
@@ -73,22 +143,283 @@ pub enum OutputFormat {
     NoContent,
     /// Files only: unique file paths.
     FilesOnly,
+    /// Default output with one header per file and its blocks beneath.
+    Grouped,
+    /// Directory tree with match counts per directory and file.
+    Tree,
+    /// One plain `score file:line type name` line per result.
+    Summary,
 }
 
 impl OutputFormat {
-    pub fn from_flags(json: bool, files_only: bool, no_content: bool) -> Self {
+    pub fn from_flags(
+        json: bool,
+        files_only: bool,
+        no_content: bool,
+        group_by_file: bool,
+        pretty: bool,
+        summary: bool,
+    ) -> Self {
         if files_only {
             Self::FilesOnly
         } else if json {
             Self::Json
         } else if no_content {
             Self::NoContent
+        } else if group_by_file {
+            Self::Grouped
+        } else if pretty {
+            Self::Tree
+        } else if summary {
+            Self::Summary
         } else {
             Self::Default
         }
     }
 }
 
+/// Which retrieval signals a search uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchMode {
+    /// BM25+MaxSim and pure semantic results, merged by ID.
+    #[default]
+    Hybrid,
+    /// Pure semantic (MaxSim) results only.
+    SemanticOnly,
+    /// BM25 candidates (MaxSim-reranked) only.
+    Bm25Only,
+}
+
+impl SearchMode {
+    pub fn from_flags(semantic_only: bool, bm25_only: bool) -> Self {
+        if semantic_only {
+            Self::SemanticOnly
+        } else if bm25_only {
+            Self::Bm25Only
+        } else {
+            Self::Hybrid
+        }
+    }
+}
+
+/// Which block kinds similar-code search returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DocFilter {
+    /// Code blocks only.
+    #[default]
+    CodeOnly,
+    /// Code and documentation blocks.
+    All,
+    /// Documentation blocks (text, section) only.
+    DocsOnly,
+}
+
+impl DocFilter {
+    pub fn from_flags(include_docs: bool, docs_only: bool) -> Self {
+        if docs_only {
+            Self::DocsOnly
+        } else if include_docs {
+            Self::All
+        } else {
+            Self::CodeOnly
+        }
+    }
+
+    /// Whether a block passes the filter.
+    pub fn keeps(self, is_doc: bool) -> bool {
+        match self {
+            Self::CodeOnly => !is_doc,
+            Self::All => true,
+            Self::DocsOnly => is_doc,
+        }
+    }
+}
+
+/// Collapsing of results that share a block name (`--unique-names`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UniqueNames {
+    /// Keep every result.
+    #[default]
+    Off,
+    /// Best result per name across all files.
+    Global,
+    /// Best result per name within each file (`--unique-names-per-file`).
+    PerFile,
+}
+
+impl UniqueNames {
+    pub fn from_flags(unique_names: bool, per_file: bool) -> Self {
+        if per_file {
+            Self::PerFile
+        } else if unique_names {
+            Self::Global
+        } else {
+            Self::Off
+        }
+    }
+}
+
+/// Which files search results come from, by test file conventions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TestFilter {
+    /// Tests and non-tests.
+    #[default]
+    All,
+    /// Skip test files (`--no-tests`).
+    NoTests,
+    /// Only test files (`--tests-only`).
+    TestsOnly,
+}
+
+impl TestFilter {
+    pub fn from_flags(no_tests: bool, tests_only: bool) -> Self {
+        if tests_only {
+            Self::TestsOnly
+        } else if no_tests {
+            Self::NoTests
+        } else {
+            Self::All
+        }
+    }
+
+    /// Whether a file passes the filter.
+    pub fn keeps(self, is_test: bool) -> bool {
+        match self {
+            Self::All => true,
+            Self::NoTests => !is_test,
+            Self::TestsOnly => is_test,
+        }
+    }
+}
+
+/// Index-time options. Recorded in the manifest so incremental updates
+/// (including auto-update on search) reuse the settings the index was built with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IndexOptions {
+    /// Lines per block for files without a tree-sitter query (or that fail to parse).
+    pub fallback_lines: usize,
+    /// Prepend leading imports and the enclosing type name to code block embeddings.
+    pub embed_context: bool,
+    /// Prepend leading doc comments and docstrings to code block embeddings.
+    pub embed_docs: bool,
+    /// Store per-block git blame info (author, last commit time).
+    pub with_git: bool,
+    /// Skip top-level constant/variable blocks.
+    pub skip_constants: bool,
+    /// Index files that look minified or generated instead of skipping them.
+    pub keep_minified: bool,
+    /// Index files marked `linguist-generated` in `.gitattributes`.
+    pub index_generated: bool,
+    /// Convert UTF-16 and other non-UTF-8 (Latin-1) files to UTF-8 instead of skipping them.
+    pub transcode: bool,
+    /// Store raw BM25 text without appending split identifier parts.
+    pub no_bm25_expand: bool,
+    /// Identifiers shorter than this are not split into BM25 terms.
+    pub split_min_word: usize,
+    /// Split parts shorter than this are dropped from BM25 terms.
+    pub split_min_part: usize,
+    /// Drop blocks with fewer code tokens than this (0 keeps all; `og build --prune`).
+    pub prune_min_tokens: usize,
+    /// Append each block's directory and file name words to its BM25 text.
+    pub path_tokens: bool,
+    /// Split captured blocks longer than this many lines that contain no other
+    /// block into sequential parts (0 keeps them whole; `og build --max-block-lines`).
+    pub max_block_lines: usize,
+    /// Index recent git commit messages as `commit` blocks (`og build --with-commits`).
+    pub with_commits: bool,
+    /// Keep one vector store per top-level directory, so scoped searches open
+    /// only theirs (`og build --shard`).
+    pub shard: bool,
+}
+
+impl Default for IndexOptions {
+    fn default() -> Self {
+        Self {
+            fallback_lines: 50,
+            embed_context: false,
+            embed_docs: false,
+            with_git: false,
+            skip_constants: false,
+            keep_minified: false,
+            index_generated: false,
+            transcode: false,
+            no_bm25_expand: false,
+            split_min_word: crate::tokenize::DEFAULT_MIN_WORD,
+            split_min_part: crate::tokenize::DEFAULT_MIN_PART,
+            prune_min_tokens: 0,
+            path_tokens: false,
+            max_block_lines: 0,
+            with_commits: false,
+            shard: false,
+        }
+    }
+}
+
+impl IndexOptions {
+    /// True when `other` differs only in options that shape BM25 text, so
+    /// stored blocks and embeddings stay valid and only the text needs rebuilding.
+    pub fn differs_only_in_bm25(&self, other: &Self) -> bool {
+        self != other && self.without_bm25() == other.without_bm25()
+    }
+
+    fn without_bm25(&self) -> Self {
+        Self {
+            no_bm25_expand: false,
+            split_min_word: 0,
+            split_min_part: 0,
+            path_tokens: false,
+            ..self.clone()
+        }
+    }
+}
+
+/// `og build` index options, `None` for each flag not passed. Applied over an
+/// existing index's options so a plain `og build` keeps the settings it was built with.
+#[derive(Debug, Clone, Default)]
+pub struct IndexOptionFlags {
+    pub fallback_lines: Option<usize>,
+    pub embed_context: Option<bool>,
+    pub embed_docs: Option<bool>,
+    pub with_git: Option<bool>,
+    pub skip_constants: Option<bool>,
+    pub keep_minified: Option<bool>,
+    pub index_generated: Option<bool>,
+    pub transcode: Option<bool>,
+    pub no_bm25_expand: Option<bool>,
+    pub split_min_word: Option<usize>,
+    pub split_min_part: Option<usize>,
+    pub prune_min_tokens: Option<usize>,
+    pub path_tokens: Option<bool>,
+    pub max_block_lines: Option<usize>,
+    pub with_commits: Option<bool>,
+    pub shard: Option<bool>,
+}
+
+impl IndexOptionFlags {
+    /// `base` with every passed flag replacing its setting.
+    pub fn apply(&self, base: IndexOptions) -> IndexOptions {
+        IndexOptions {
+            fallback_lines: self.fallback_lines.unwrap_or(base.fallback_lines),
+            embed_context: self.embed_context.unwrap_or(base.embed_context),
+            embed_docs: self.embed_docs.unwrap_or(base.embed_docs),
+            with_git: self.with_git.unwrap_or(base.with_git),
+            skip_constants: self.skip_constants.unwrap_or(base.skip_constants),
+            keep_minified: self.keep_minified.unwrap_or(base.keep_minified),
+            index_generated: self.index_generated.unwrap_or(base.index_generated),
+            transcode: self.transcode.unwrap_or(base.transcode),
+            no_bm25_expand: self.no_bm25_expand.unwrap_or(base.no_bm25_expand),
+            split_min_word: self.split_min_word.unwrap_or(base.split_min_word),
+            split_min_part: self.split_min_part.unwrap_or(base.split_min_part),
+            prune_min_tokens: self.prune_min_tokens.unwrap_or(base.prune_min_tokens),
+            path_tokens: self.path_tokens.unwrap_or(base.path_tokens),
+            max_block_lines: self.max_block_lines.unwrap_or(base.max_block_lines),
+            with_commits: self.with_commits.unwrap_or(base.with_commits),
+            shard: self.shard.unwrap_or(base.shard),
+        }
+    }
+}
+
 /// Stats returned from indexing operations.
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct IndexStats {
@@ -97,6 +428,13 @@ pub struct IndexStats {
     pub skipped: usize,
     pub errors: usize,
     pub deleted: usize,
+    /// Blocks dropped by `--prune`.
+    pub pruned: usize,
+    /// Files moved to a new path with identical content, reusing their vectors.
+    pub renamed: usize,
+    /// Blocks of changed files whose stable ID and text were unchanged, kept
+    /// without re-embedding.
+    pub reused: usize,
 }
 
 /// Exit codes matching Python implementation.
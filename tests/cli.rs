@@ -340,3 +340,116 @@ fn similar_search_shows_raw_score_not_percentage() {
         "similar search must not show '% similar'; got: {stdout}"
     );
 }
+
+#[test]
+fn search_batch_json_keyed_by_query() {
+    let tmp = build_fixture_index();
+    let queries = tmp.path().join("queries.txt");
+    std::fs::write(&queries, "authentication\n\nerror handling\n").unwrap();
+
+    let output = og()
+        .args([
+            "--json",
+            "-n",
+            "2",
+            "--batch",
+            queries.to_str().unwrap(),
+            tmp.path().to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let obj = parsed.as_object().unwrap();
+    assert_eq!(obj.len(), 2);
+    assert!(obj["authentication"].is_array());
+    assert!(obj["error handling"].is_array());
+}
+
+#[test]
+fn list_json_reports_index_metadata() {
+    let tmp = build_fixture_index();
+
+    let output = og()
+        .args(["list", "--json", tmp.path().to_str().unwrap()])
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let first = &parsed.as_array().unwrap()[0];
+    assert!(first["blocks"].as_u64().unwrap() > 0);
+    assert!(first["model"].is_string());
+    assert_eq!(first["needs_rebuild"], false);
+}
+
+#[test]
+fn vacuum_keeps_index_searchable() {
+    let tmp = build_fixture_index();
+
+    og().args(["vacuum", tmp.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Reclaimed"));
+
+    og().args(["authentication", tmp.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("auth.py"));
+}
+
+#[test]
+fn search_count_prints_integer() {
+    let tmp = build_fixture_index();
+
+    let output = og()
+        .args(["--count", "-n", "3", "error", tmp.path().to_str().unwrap()])
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    assert_eq!(stdout.trim(), "3");
+}
+
+#[test]
+fn search_group_by_file_prints_each_file_once() {
+    let tmp = build_fixture_index();
+
+    let output = og()
+        .args([
+            "--group-by-file",
+            "-C",
+            "0",
+            "-n",
+            "10",
+            "error",
+            tmp.path().to_str().unwrap(),
+        ])
+        .env("NO_COLOR", "1")
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let headers: Vec<&str> = stdout
+        .lines()
+        .filter(|l| !l.is_empty() && !l.starts_with(' '))
+        .collect();
+    let unique: std::collections::HashSet<&&str> = headers.iter().collect();
+    assert!(!headers.is_empty());
+    assert_eq!(headers.len(), unique.len(), "file headers repeated");
+}
+
+#[test]
+fn follow_rejects_one_shot_flags() {
+    for flag in [
+        ["--timeout", "100"],
+        ["--max-tokens", "8"],
+        ["--output", "out.txt"],
+    ] {
+        og().args(["--follow", flag[0], flag[1], "query", "."])
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("cannot be used with"));
+    }
+}